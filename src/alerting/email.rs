@@ -3,8 +3,10 @@ use lettre::message::{MultiPart, SinglePart};
 use lettre::transport::smtp::authentication::{Credentials, Mechanism};
 use dotenv::dotenv as dotenv_linux;
 use dotenvy::dotenv as dotenv_windows;
+use chrono::{DateTime, Utc};
 
-use crate::structs::data::SaleInfo;
+use crate::file_ops::{settings, thresholds};
+use crate::structs::data::{BestDeal, DealKind, SaleInfo};
 
 pub fn send_plain_text_msg(recipient: &str, subject: &str, body: &str) {
     if cfg!(target_os = "windows") { dotenv_windows().ok(); }
@@ -39,7 +41,7 @@ pub fn send_plain_text_msg(recipient: &str, subject: &str, body: &str) {
 }
 
 pub fn get_stylesheet() -> String {
-    return String::from(r#"
+    String::from(r#"
         <style>
             img {
                 max-width: auto;
@@ -72,12 +74,30 @@ pub fn get_stylesheet() -> String {
                 color: #053e00;
             }
         </style>
-    "#);
+    "#)
 }
 
-pub fn create_storefront_table_html(store_name: &str, sales: Vec<SaleInfo>) -> String{
+fn get_subscription_html(s_info: &SaleInfo) -> String {
+    let label = s_info.subscription.get_label();
+    if label.is_empty() { label } else { format!("<br><small>{}</small>", label) }
+}
+
+fn get_countdown_html(s_info: &SaleInfo, now: DateTime<Utc>) -> String {
+    let countdown = thresholds::get_countdown_str(s_info, now);
+    if countdown.is_empty() { countdown }
+    else if thresholds::is_ending_soon(s_info, now) { format!("<br><strong>{}</strong>", countdown) }
+    else { format!("<br><small>{}</small>", countdown) }
+}
+
+pub fn create_storefront_table_html(store_name: &str, mut sales: Vec<SaleInfo>) -> String{
     let mut rows = String::new(); 
+    let now = Utc::now();
+    thresholds::sort_by_sale_end(&mut sales, now, |s_info| s_info);
     for s_info in sales{
+        let deal_str = match s_info.deal {
+            DealKind::FreeGame => String::from("<strong>FREE this week</strong>"),
+            DealKind::Sale => format!("({}% off)", s_info.discount_percentage),
+        };
         rows += &format!("<tr>
                 <td>
                     <a href=\"{store_page}\">
@@ -87,12 +107,12 @@ pub fn create_storefront_table_html(store_name: &str, sales: Vec<SaleInfo>) -> S
                 <td style=\"text-align: left;\">
                     <a href=\"{store_page}\">{title}</a>
                 </td>
-                <td><del>${old_price}</del> ${new_price}</td>
-                <td style=\"text-align: center;\">({price_off}% off)</td>
+                <td><del>{old_price}</del> {new_price} {currency}{subscription}</td>
+                <td style=\"text-align: center;\">{deal}{countdown}</td>
             </tr>", 
         icon=s_info.icon_link, title=s_info.title,
-        old_price=s_info.original_price, new_price=s_info.current_price, 
-        price_off=s_info.discount_percentage, store_page=s_info.store_page_link);
+        old_price=s_info.original_price, new_price=s_info.current_price, currency=s_info.currency(),
+        subscription=get_subscription_html(&s_info), deal=deal_str, countdown=get_countdown_html(&s_info, now), store_page=s_info.store_page_link);
     }
     let data = format!(r#"
         <h2 class="storefront">{}</h2>
@@ -100,12 +120,69 @@ pub fn create_storefront_table_html(store_name: &str, sales: Vec<SaleInfo>) -> S
             {}
         </table>
     "#, store_name, rows);
-    return data;
+    data
+}
+
+// One row per game at the cheapest store with the other stores' prices listed below the title
+pub fn create_best_deals_table_html(mut deals: Vec<BestDeal>) -> String{
+    let mut rows = String::new();
+    let now = Utc::now();
+    thresholds::sort_by_sale_end(&mut deals, now, |deal| &deal.sale);
+    for deal in deals{
+        let s_info = deal.sale;
+        let deal_str = match s_info.deal {
+            DealKind::FreeGame => String::from("<strong>FREE this week</strong>"),
+            DealKind::Sale => format!("({}% off)", s_info.discount_percentage),
+        };
+        let other_prices : Vec<String> = deal.other_prices.iter()
+            .map(|price| format!("{}: {} {}", price.store_name, price.current_price, price.current_price.currency))
+            .collect();
+        let other_prices_str = if other_prices.is_empty() { String::new() }
+                               else { format!("<br><small>Also on {}</small>", other_prices.join(", ")) };
+        rows += &format!("<tr>
+                <td>
+                    <a href=\"{store_page}\">
+                        <img src=\"{icon}\" alt=\"{title}\">
+                    </a>
+                </td>
+                <td style=\"text-align: left;\">
+                    <a href=\"{store_page}\">{title}</a>{other_prices}
+                </td>
+                <td class=\"storefront\">{store_name}</td>
+                <td><del>{old_price}</del> {new_price} {currency}{subscription}</td>
+                <td style=\"text-align: center;\">{deal}{countdown}</td>
+            </tr>",
+        icon=s_info.icon_link, title=s_info.title, other_prices=other_prices_str, store_name=deal.store_name,
+        old_price=s_info.original_price, new_price=s_info.current_price, currency=s_info.currency(),
+        subscription=get_subscription_html(&s_info), deal=deal_str, countdown=get_countdown_html(&s_info, now), store_page=s_info.store_page_link);
+    }
+    format!(r#"
+        <h2 class="storefront">Best deals</h2>
+        <table>
+            {}
+        </table>
+    "#, rows)
+}
+
+pub fn create_failures_html(failures: &[String]) -> String{
+    let mut items = String::new();
+    for failure in failures{
+        items += &format!("<li>{}</li>", failure);
+    }
+    format!(r#"
+        <h2 class="storefront">Failed price checks</h2>
+        <ul>
+            {}
+        </ul>
+    "#, items)
 }
 
 pub fn create_html_body(sales_info_html: &str) -> String{
     let stylesheet = get_stylesheet();
-    return format!(r#"
+    let listing_str = if settings::get_best_deal_state() {
+        "Each game is listed once at the cheapest store, with the prices on the other stores below the title."
+    } else { "A game may appear multiple times if the product is on sale on multiple storefronts." };
+    format!(r#"
         <!DOCTYPE html>
         <html>
         <head>
@@ -115,12 +192,12 @@ pub fn create_html_body(sales_info_html: &str) -> String{
         <body>
             <p>
                 One or more games is less than or equal to the respective price threshold you set. 
-                A game may appear multiple times if the product is on sale on multiple storefronts.
+                {}
             </p>
             {}
         </body>
         </html>
-    "#, stylesheet, sales_info_html);
+    "#, stylesheet, listing_str, sales_info_html)
 }
 
 pub fn send_html_msg(recipient: &str, subject: &str, body: &str) {
@@ -133,7 +210,7 @@ pub fn send_html_msg(recipient: &str, subject: &str, body: &str) {
     let smtp_user = std::env::var("SMTP_USERNAME").expect("SMTP_USERNAME must be set");
     let smtp_pwd = std::env::var("SMTP_PWD").expect("SMTP_PWD must be set");
 
-    let html_content = format!(r#"{}"#, create_html_body(body));
+    let html_content = create_html_body(body);
     let email = Message::builder()
         .from(smtp_email.parse().unwrap())
         .to(recipient.parse().unwrap())
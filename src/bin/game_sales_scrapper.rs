@@ -1,41 +1,150 @@
 use dotenv::dotenv as dotenv_linux;
 use dotenvy::dotenv as dotenv_windows;
-use std::io::Write;
-use std::io;
 use clap::{arg, command, Arg, ArgAction, Command, ArgMatches};
 use clap::parser::ValueSource;
+use chrono::{DateTime, Utc};
 
 // Internal libraries
-use game_sales_scrapper::stores::{steam, gog, microsoft_store};
+use game_sales_scrapper::stores::{compare, epic, http, microsoft_store, steam, storefront};
+use game_sales_scrapper::stores::http::Transport;
+use game_sales_scrapper::stores::storefront::SelectionMode;
 use game_sales_scrapper::alerting::email;
-use game_sales_scrapper::file_ops::{csv, thresholds,
-                                    settings::{self, STEAM_STORE_ID, GOG_STORE_ID, MICROSOFT_STORE_ID}};
+use game_sales_scrapper::file_ops::{csv, thresholds, settings, history};
 use game_sales_scrapper::json;
-use game_sales_scrapper::structs::data::{SaleInfo, SimpleGameThreshold};
-use game_sales_scrapper::structs::gog_response::GameInfo as GOGGameInfo;
-use game_sales_scrapper::structs::microsoft_store_response::ProductInfo;
+use game_sales_scrapper::structs::data::{BestDeal, DealKind, InsertReport, Money, PriceComparison, SaleInfo, SimpleGameThreshold, StoreSearchResult};
 
 fn get_recipient() -> String {
     if cfg!(target_os = "windows") { dotenv_windows().ok(); }
     else if cfg!(target_os = "linux") { dotenv_linux().ok(); }
-    let recipient = std::env::var("RECIPIENT_EMAIL").expect("RECIPIENT_EMAIL must be set");
-    recipient
+    std::env::var("RECIPIENT_EMAIL").expect("RECIPIENT_EMAIL must be set")
 }
 
 fn storefront_check() -> Vec<String> {
     let selected_stores = settings::get_selected_stores();
-    if selected_stores.len() == 0 {
+    if selected_stores.is_empty() {
         panic!("Please configure which stores to query. Run \'game_sales_scrapper config --help\' for more info.");
     }
     selected_stores
 }
 
-fn get_simple_prices_str(store_name: &str, sales: Vec<SaleInfo>) -> String{
+// Sets how search results are picked for add and bulk-insert
+fn set_selection_mode(args: &ArgMatches) {
+    if let Some(min_score) = args.get_one::<f64>("auto-match") {
+        if !(0.0..=1.0).contains(min_score) {
+            panic!("The auto match score must be between 0 and 1 not \'{}\'", min_score);
+        }
+        storefront::set_selection_mode(SelectionMode::AutoMatch(*min_score));
+    }
+    else if args.get_flag("non-interactive") {
+        storefront::set_selection_mode(SelectionMode::AutoMatch(storefront::DEFAULT_AUTO_MATCH_SCORE));
+    }
+}
+
+// Machine-readable summary (printed as the last line of output)
+fn print_insert_report(report: &InsertReport) {
+    if storefront::is_interactive() { return; }
+    println!("{}", serde_json::to_string(report).expect("Could not convert insert report to string."));
+}
+
+fn get_price_cell(price: Option<&Money>) -> String {
+    price.map(|price| price.to_string()).unwrap_or(String::from("-"))
+}
+
+// Text table with columns sized to fit their contents
+fn get_table(header: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths : Vec<usize> = header.iter().map(|cell| cell.chars().count()).collect();
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let format_row = |row: &[String]| -> String {
+        row.iter().zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<String>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+    let mut table = format_row(header);
+    table.push_str(&format!("\n{}", widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<String>>().join("-+-")));
+    for row in rows.iter() {
+        table.push_str(&format!("\n{}", format_row(row)));
+    }
+    table
+}
+
+fn get_search_table(results: &[StoreSearchResult]) -> String {
+    let header = ["Store", "ID", "Title", "Base", "Current", "Discount"].map(String::from);
+    let rows : Vec<Vec<String>> = results.iter().map(|row| vec![
+        row.store.clone(),
+        row.result.id.clone(),
+        row.result.title.clone(),
+        get_price_cell(row.result.price.as_ref()),
+        get_price_cell(row.result.current_price.as_ref()),
+        row.result.discount_percentage.map(|discount| format!("{}%", discount)).unwrap_or(String::from("-")),
+    ]).collect();
+    get_table(&header, &rows)
+}
+
+// One row per game and one column per store. The cheapest store is marked with "*" and
+// every other store shows how much more it costs (prices in another currency show the currency instead).
+fn get_compare_table(comparisons: &[PriceComparison]) -> String {
+    let mut store_names : Vec<String> = Vec::new();
+    for store in storefront::get_storefronts() {
+        let store_name = store.name().to_string();
+        if comparisons.iter().any(|game| game.prices.iter().any(|price| price.store_name == store_name)) {
+            store_names.push(store_name);
+        }
+    }
+    let mut header = vec![String::from("Title")];
+    header.extend(store_names.iter().cloned());
+    header.extend([String::from("Cheapest"), String::from("Saves")]);
+    let rows : Vec<Vec<String>> = comparisons.iter().map(|game| {
+        let cheapest = compare::get_cheapest(game);
+        let mut row = vec![game.title.clone()];
+        for store_name in store_names.iter() {
+            let Some(price) = game.prices.iter().find(|price| price.store_name == *store_name) else {
+                row.push(String::from("-"));
+                continue;
+            };
+            let mut cell = price.current_price.to_string();
+            if price.discount_percentage > 0.0 { cell.push_str(&format!(" (-{}%)", price.discount_percentage)); }
+            match compare::get_savings(game, price) {
+                _ if cheapest == Some(price) => cell.insert(0, '*'),
+                Some(savings) => cell.push_str(&format!(" +{}", savings)),
+                None => cell.push_str(&format!(" {}", price.current_price.currency)),
+            }
+            row.push(cell);
+        }
+        row.push(cheapest.map(|price| price.store_name.clone()).unwrap_or(String::from("-")));
+        row.push(if cheapest.is_some() { format!("{} {}", compare::get_max_savings(game), game.currency) } else { String::from("-") });
+        row
+    }).collect();
+    get_table(&header, &rows)
+}
+
+fn get_subscription_str(game: &SaleInfo) -> String {
+    let label = game.subscription.get_label();
+    if label.is_empty() { label } else { format!(" [{}]", label) }
+}
+
+fn get_countdown_str(game: &SaleInfo, now: DateTime<Utc>) -> String {
+    let countdown = thresholds::get_countdown_str(game, now);
+    if countdown.is_empty() { countdown } else { format!(" [{}]", countdown) }
+}
+
+fn get_simple_prices_str(store_name: &str, mut sales: Vec<SaleInfo>) -> String{
     let mut prices_str = String::new();
+    let now = Utc::now();
+    thresholds::sort_by_sale_end(&mut sales, now, |game| game);
     for game in sales.iter(){
         prices_str.push_str(&format!("\n\t- {} : {} -> {} ({}% off)",
                                    game.title, game.original_price, game.current_price,
                                    game.discount_percentage));
+        if game.deal == DealKind::FreeGame { prices_str.push_str(" [FREE this week]"); }
+        prices_str.push_str(&get_countdown_str(game, now));
+        prices_str.push_str(&get_subscription_str(game));
     }
     if !prices_str.is_empty() {
         let header_str = format!("\n{} game(s) that met your desired price:", store_name);
@@ -44,182 +153,103 @@ fn get_simple_prices_str(store_name: &str, sales: Vec<SaleInfo>) -> String{
     prices_str
 }
 
-async fn check_prices(use_html: bool) -> String {
-    let thresholds = thresholds::load_data().unwrap_or_else(|_e|Vec::new());
-    let mut steam_sales: Vec<SaleInfo> = Vec::new();
-    let mut gog_sales: Vec<SaleInfo> = Vec::new();
-    let mut microsoft_store_sales: Vec<SaleInfo> = Vec::new();
-    let http_client = reqwest::Client::new();
-    let mut output = String::new();
-    for elem in thresholds.iter(){
-        if elem.steam_id != 0 {
-            match steam::get_price_details(elem.steam_id, &http_client).await {
-                Ok(info) => {
-                    let current_price = info.current_price.parse::<f64>().unwrap();
-                    if elem.desired_price >= current_price {
-                        steam_sales.push(info);
-                    }
-                },
-                Err(e) => println!("{}", e)
-            }
-        }
-        if elem.gog_id != 0 {
-            if gog::VERSION == 1{
-                match gog::get_price_details(&elem.title).await {
-                    Some(po) => {
-                        let current_price = po.final_amount.parse::<f64>().unwrap();
-                        if elem.desired_price >= current_price {
-                            let price_str = format!("\n\t- {} : {} -> {} {} ({}% off)",
-                                                    elem.title, po.base_amount, po.final_amount,
-                                                    po.currency, po.discount_percentage);
-                            output.push_str(&price_str);
-                        }
-                    },
-                    None => ()
-                }
-            }
-            else if gog::VERSION == 2{
-                match gog::get_price_details_v2(&elem.title, &http_client).await {
-                    Some(info) => {
-                        let current_price = info.current_price.parse::<f64>().unwrap();
-                        if elem.desired_price >= current_price {
-                            gog_sales.push(info);
-                        }
-                    },
-                    None => ()
-                }
-            }
-        }
-        if !elem.microsoft_store_id.is_empty() {
-            match microsoft_store::get_price_details(&elem.microsoft_store_id, &http_client).await {
-                Some(info) => {
-                    let current_price = info.current_price.parse::<f64>().unwrap();
-                    if elem.desired_price >= current_price {
-                        microsoft_store_sales.push(info);
-                    }
-                },
-                None => ()
-            }
+fn get_best_deals_str(mut deals: Vec<BestDeal>) -> String{
+    let mut prices_str = String::new();
+    let now = Utc::now();
+    thresholds::sort_by_sale_end(&mut deals, now, |deal| &deal.sale);
+    for deal in deals.iter(){
+        let game = &deal.sale;
+        prices_str.push_str(&format!("\n\t- {} : {} -> {} ({}% off) on {}",
+                                   game.title, game.original_price, game.current_price,
+                                   game.discount_percentage, deal.store_name));
+        if game.deal == DealKind::FreeGame { prices_str.push_str(" [FREE this week]"); }
+        prices_str.push_str(&get_countdown_str(game, now));
+        prices_str.push_str(&get_subscription_str(game));
+        for price in deal.other_prices.iter(){
+            prices_str.push_str(&format!("\n\t\t{} : {} {}", price.store_name, price.current_price, price.current_price.currency));
         }
     }
-    if !steam_sales.is_empty(){
-        let store_name = settings::get_proper_store_name(STEAM_STORE_ID).unwrap();
-        if use_html { output.push_str(&email::create_storefront_table_html(&store_name, steam_sales)); }
-        else { output.push_str(&get_simple_prices_str(&store_name, steam_sales)); }
-    }
-    if !gog_sales.is_empty(){
-        let store_name = settings::get_proper_store_name(GOG_STORE_ID).unwrap();
-        if use_html { output.push_str(&email::create_storefront_table_html(&store_name, gog_sales)); }
-        else { output.push_str(&get_simple_prices_str(&store_name, gog_sales)); }
-    }
-    if !microsoft_store_sales.is_empty(){
-        let store_name = settings::get_proper_store_name(MICROSOFT_STORE_ID).unwrap();
-        if use_html { output.push_str(&email::create_storefront_table_html(&store_name, microsoft_store_sales)); }
-        else{ output.push_str(&get_simple_prices_str(&store_name, microsoft_store_sales)); }
+    if !prices_str.is_empty() {
+        prices_str = String::from("\nBest deal for game(s) that met your desired price:") + &prices_str;
     }
-    output
+    prices_str
 }
 
-async fn steam_insert_sequence(alias: &str, title: &str, price: f64, client: &reqwest::Client) {
-    match steam::check_game(title).await {
-        Some(data) => thresholds::add_steam_game(alias.to_string(), data, price, &client).await,
-        None => {
-            match steam::search_game(title).await {
-                Some(t) => {
-                    match steam::check_game(&t).await {
-                            Some(data) => thresholds::add_steam_game(alias.to_string(), data, price, &client).await,
-                            None => eprintln!("Something went wrong")
-                    }
-                }
-                None => ()
-            }
-        }
+fn get_failures_str(failures: &[String]) -> String{
+    let mut failures_str = format!("\n{} price check(s) failed:", failures.len());
+    for failure in failures.iter(){
+        failures_str.push_str(&format!("\n\t- {}", failure));
     }
+    failures_str
 }
 
-async fn gog_insert_sequence(alias: &str, title: &str, price: f64, client: &reqwest::Client){
-    let mut search_list : Vec<GOGGameInfo> = Vec::new();
-    match gog::search_game_by_title_v2(title, &client).await {
-        Ok(data) => search_list = data,
-        Err(e) => println!("Search GOG Game Error: {}", e)
-    }
-    if !search_list.is_empty() {
-        println!("GOG search results:");
-        for (i, game) in search_list.iter().enumerate(){
-            let price = match &game.price{
-                Some(po) => po.base_money.amount.clone(),
-                None => String::from("0"),
-            };
-            println!("  [{}] {} - ${}", i, game.title, price);
-        }
-        println!("  [q] SKIP");
-        let mut input = String::new();
-        print!("Type integer corresponding to game title or type \"q\" to quit: ");
-        let _ = io::stdout().flush();
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read user input");
-        if input.trim() == "q" {
-            eprintln!("Request terminated.");
-        }
-        else {
-            match input.trim().parse::<usize>() {
-                Ok(idx) => {
-                    if idx < search_list.len(){
-                        //title = &search_list[idx].title;
-                        let game = &search_list[idx];
-                        thresholds::add_gog_game(alias.to_string(), game, price);
-                    }
-                    else if idx >= search_list.len(){
-                        eprintln!("Integer \"{}\" is invalid. Request terminated.", idx);
-                    }
-                },
-                Err(e) => println!("Invalid input: {}\nError: {}", input, e)
+// Returns the report of the alerts and best deals with the price checks that failed
+async fn check_prices(use_html: bool) -> (String, Vec<String>) {
+    let thresholds = thresholds::load_data().unwrap_or_else(|_e|Vec::new());
+    let price_history = history::load_data().unwrap_or_else(|_e|Vec::new());
+    let storefronts = storefront::get_storefronts();
+    let use_best_deal = settings::get_best_deal_state();
+    let mut sales_by_store: Vec<Vec<SaleInfo>> = storefronts.iter().map(|_| Vec::new()).collect();
+    // Used instead of sales_by_store for best deal reports
+    let mut alerts = Vec::new();
+    let mut prices = Vec::new();
+    let mut observed = Vec::new();
+    let mut failures = Vec::new();
+    let http_client = reqwest::Client::new();
+    let mut output = String::new();
+    let mut results = storefront::get_price_details_all(&storefronts, &thresholds, &http_client).await;
+    // Keep the store order so the first store wins a tie for the best deal
+    results.sort_by_key(|(idx, _, _)| *idx);
+    for (idx, pos, result) in results {
+        let store = &storefronts[idx];
+        let elem = &thresholds[pos];
+        let info = match result {
+            Ok(info) => info,
+            Err(e) => {
+                failures.push(format!("{} ({}): {}", elem.title, store.name(), e));
+                continue;
             }
+        };
+        observed.push((elem.title.clone(), history::create_record(store.id(), &info)));
+        prices.push((pos, compare::to_store_price(store.as_ref(), &info)));
+        let game_history = price_history.iter().find(|game| game.title == elem.title);
+        // Free games are always reported (regardless of the threshold currency or rules)
+        let is_alert = if info.deal == DealKind::FreeGame { true }
+        else if !thresholds::is_same_currency(elem, &info) {
+            eprintln!("Skipped {} price for \"{}\": price is in {} but threshold is in {}",
+                      store.name(), elem.title, info.currency(), thresholds::get_currency(elem));
+            false
         }
+        else { thresholds::is_alert(elem, &info, game_history) };
+        if !is_alert { continue; }
+        if use_best_deal { alerts.push((pos, store.name().to_string(), info)); }
+        else { sales_by_store[idx].push(info); }
     }
-    else{
-        println!("Could not find a game title matching \"{}\" on GOG.", title);
-    }
-}
-
-async fn microsoft_store_insert_sequence(alias: &str, title: &str, price: f64, client: &reqwest::Client){
-    let mut search_list : Vec<ProductInfo> = Vec::new();
-    match microsoft_store::search_game_by_title(title, &client).await {
-        Ok(data) => search_list = data,
-        Err(e) => println!("Search Microsoft Store Error: {}", e)
-    }
-    if !search_list.is_empty() {
-        println!("Microsoft Store search results:");
-        for(i, game) in search_list.iter().enumerate(){
-            println!("  [{}] {} - ${}", i, game.title, game.price_info.msrp.unwrap_or_default());
+    history::add_records(observed);
+    // Weekly free games are reported even when no threshold tracks them
+    if let Some(idx) = storefronts.iter().position(|store| store.id() == settings::EPIC_STORE_ID) &&
+       settings::get_selected_stores().iter().any(|store_id| store_id == settings::EPIC_STORE_ID) {
+        match epic::get_free_games(&settings::get_region(), Utc::now(), &http_client).await {
+            Ok(free_games) => sales_by_store[idx].extend(free_games.into_iter()
+                .filter(|(epic_id, _)| !thresholds.iter().any(|elem| elem.epic_id == *epic_id))
+                .map(|(_, info)| info)),
+            Err(e) => failures.push(format!("Weekly free games ({}): {}", settings::EPIC_STORE_NAME, e)),
         }
-        println!("  [q] SKIP");
-        let mut input = String::new();
-        print!("Type integer corresponding to game title or type \"q\" to quit: ");
-        let _ = io::stdout().flush();
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read user input");
-        if input.trim() == "q" { eprintln!("Request terminated."); }
-        else {
-            match input.trim().parse::<usize>() {
-                Ok(idx) => {
-                    if idx < search_list.len(){
-                        let game = &search_list[idx];
-                        thresholds::add_microsoft_store_game(alias.to_string(), game, price);
-                    }
-                    else if idx >= search_list.len(){
-                        eprintln!("Integer \"{}\" is invalid. Request terminated.", idx);
-                    }
-                },
-                Err(e) => println!("Invalid input: {}\nError: {}", input, e)
-            }
+    }
+    if use_best_deal {
+        let deals = compare::get_best_deals(&thresholds, alerts, &prices);
+        if !deals.is_empty() {
+            if use_html { output.push_str(&email::create_best_deals_table_html(deals)); }
+            else { output.push_str(&get_best_deals_str(deals)); }
         }
     }
-    else {
-        println!("Could not find a game title matching \"{}\" on the Microsoft Store.", title);
+    for (store, sales) in storefronts.iter().zip(sales_by_store) {
+        if sales.is_empty() { continue; }
+        let store_name = store.name();
+        if use_html { output.push_str(&email::create_storefront_table_html(store_name, sales)); }
+        else { output.push_str(&get_simple_prices_str(store_name, sales)); }
     }
+    (output, failures)
 }
 
 // Main function
@@ -250,14 +280,105 @@ async fn main(){
     let microsoft_store_arg =  arg!(-m --microsoft_store "Search Microsoft Store")
         .action(ArgAction::SetTrue)
         .required(false);
+    let humble_bundle_arg = arg!(-b --humble_bundle "Search Humble Bundle Store")
+        .action(ArgAction::SetTrue)
+        .required(false);
+    let epic_arg = arg!(-e --epic "Search Epic Games Store")
+        .action(ArgAction::SetTrue)
+        .required(false);
     let all_stores_arg = arg!(-a --all_stores "Search all game stores")
         .action(ArgAction::SetTrue)
-        .conflicts_with_all(["steam", "gog", "microsoft_store"])
+        .conflicts_with_all(["steam", "gog", "microsoft_store", "humble_bundle", "epic"])
         .required(false);
     let alias_state_arg = arg!(-i --alias_state "Enable aliases for game titles (Possible options: [0,1])")
         .action(ArgAction::Set)
         .value_parser(clap::value_parser!(i32))
         .required(false);
+    let best_deal_arg = arg!(-d --best_deal "Only list each game once at its cheapest store in price reports (Possible options: [0,1])")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(i32))
+        .required(false);
+    let price_rule_arg = arg!(-p --price_rule "Alert when price is at or below the price threshold (Possible options: [0,1])")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(i32))
+        .required(false);
+    let all_time_low_arg = arg!(-l --all_time_low "Alert when price is at or below the all-time low (Possible options: [0,1])")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(i32))
+        .required(false);
+    let min_discount_arg = arg!(-d --min_discount "Alert when discount percentage is at least this value (0 to disable)")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(f64))
+        .required(false);
+    let percentile_arg = arg!(-b --bottom_percentile "Alert when price is in the bottom X% of recorded prices (0 to disable)")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(f64))
+        .required(false);
+    let game_pass_arg = arg!(-g --game_pass "Alert when the game is added to Game Pass on the Microsoft Store (Possible options: [0,1])")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(i32))
+        .required(false);
+    let ending_soon_arg = arg!(-e --ending_soon "Alert once when a sale ends within 48 hours (Possible options: [0,1])")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(i32))
+        .required(false);
+    let match_all_arg = arg!(-m --match_all "Require all rules to be met instead of any one rule (Possible options: [0,1])")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(i32))
+        .required(false);
+    let region_arg = arg!(-r --region "Country code used for store prices (e.g. US, GB, DE)")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(String))
+        .required(false);
+    let store_arg = arg!(-s --store "Store ID (e.g. steam, gog, microsoft_store)")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(String))
+        .required(true);
+    let concurrency_arg = arg!(-n --concurrency "Maximum number of requests sent to the store at once")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(usize))
+        .required(false);
+    let rate_arg = arg!(-r --rate "Maximum number of requests per second sent to the store (f64)")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(f64))
+        .required(false);
+    let non_interactive_arg = Arg::new("non-interactive")
+        .long("non-interactive")
+        .action(ArgAction::SetTrue)
+        .required(false)
+        .help("Never prompt: pick a search result only if it matches the title exactly (after normalization)");
+    let auto_match_arg = Arg::new("auto-match")
+        .long("auto-match")
+        .value_name("MIN_SCORE")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(f64))
+        .required(false)
+        .help("Never prompt: pick the best search result if its match score (0 to 1) is at least this value");
+    let add_ons_arg = Arg::new("add-ons")
+        .long("add-ons")
+        .action(ArgAction::SetTrue)
+        .required(false)
+        .help("Also search for Microsoft Store add-ons (e.g. DLC)");
+    let microsoft_store_id_arg = Arg::new("microsoft-store-id")
+        .long("microsoft-store-id")
+        .value_name("PRODUCT_ID")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(String))
+        .required(false)
+        .help("Track a Microsoft Store game or add-on by its product ID instead of searching for the title");
+    let search_title_arg = Arg::new("title")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(String))
+        .required(true)
+        .help("Title to search for");
+    let discounted_arg = arg!(-d --discounted "Only include games that are discounted on at least one store")
+        .action(ArgAction::SetTrue)
+        .required(false);
+    let json_arg = Arg::new("json")
+        .long("json")
+        .action(ArgAction::SetTrue)
+        .required(false)
+        .help("Print the search results as JSON");
     let test_flag_arg = arg!(-z --test_flag "Flag for saving data using the TEST_PATH env variable")
         .action(ArgAction::SetTrue)
         .hide(true)
@@ -272,20 +393,35 @@ async fn main(){
                     &steam_store_arg,
                     &gog_store_arg,
                     &microsoft_store_arg,
+                    &humble_bundle_arg,
+                    &epic_arg,
                     &all_stores_arg,
                     &alias_state_arg,
+                    &best_deal_arg,
+                    &region_arg,
                     &test_flag_arg
                 ])
         )
         .subcommand(
             Command::new("add")
                 .about("Add a game to price thresholds")
-                .args([&title_arg, &price_arg, &alias_arg, &test_flag_arg])
+                .args([&title_arg.clone().required(false).required_unless_present("microsoft-store-id"), &price_arg, &alias_arg, &non_interactive_arg,
+                       &auto_match_arg, &add_ons_arg, &microsoft_store_id_arg, &test_flag_arg])
         )
         .subcommand(
             Command::new("bulk-insert")
                 .about("Add multiple games via CSV file")
-                .args([&file_arg, &test_flag_arg])
+                .args([&file_arg, &non_interactive_arg, &auto_match_arg, &add_ons_arg, &test_flag_arg])
+        )
+        .subcommand(
+            Command::new("search")
+                .about("Search the selected stores without adding a price threshold")
+                .args([&search_title_arg, &json_arg, &add_ons_arg, &test_flag_arg])
+        )
+        .subcommand(
+            Command::new("compare")
+                .about("Compare the current price of every tracked game across stores")
+                .args([&discounted_arg, &test_flag_arg])
         )
         .subcommand(
             Command::new("update")
@@ -297,6 +433,27 @@ async fn main(){
                 .about("Remove game from price thresholds")
                 .args([&title_arg, &test_flag_arg])
         )
+        .subcommand(
+            Command::new("rules")
+                .about("Set alert rules for game (combined with price threshold)")
+                .args([&title_arg, &price_rule_arg, &all_time_low_arg, &min_discount_arg,
+                       &percentile_arg, &game_pass_arg, &ending_soon_arg, &match_all_arg, &test_flag_arg])
+        )
+        .subcommand(
+            Command::new("region")
+                .about("Set region for game (use \"default\" for the configured region)")
+                .args([&title_arg, &region_arg.clone().required(true), &test_flag_arg])
+        )
+        .subcommand(
+            Command::new("limits")
+                .about("Set concurrency and rate limits used when checking prices on a store")
+                .args([&store_arg, &concurrency_arg, &rate_arg, &test_flag_arg])
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Show recorded price history for game")
+                .args([&title_arg, &test_flag_arg])
+        )
         .arg(
             Arg::new("selected-stores")
                 .short('l')
@@ -343,6 +500,22 @@ async fn main(){
                 .required(false)
                 .help("Send email if game(s) are below price threshold")
         )
+        .arg(
+            Arg::new("offline")
+                .long("offline")
+                .action(ArgAction::SetTrue)
+                .requires("check-prices")
+                .required(false)
+                .help("Check prices using the last recorded store responses instead of sending requests")
+        )
+        .arg(
+            Arg::new("refresh")
+                .long("refresh")
+                .action(ArgAction::SetTrue)
+                .requires("cache")
+                .required(false)
+                .help("Only update cached games that changed since the last cache update")
+        )
         .arg(test_flag_arg)
     .get_matches();
 
@@ -353,52 +526,67 @@ async fn main(){
 
             let search_steam = config_args.value_source("steam").unwrap();
             let search_gog = config_args.value_source("gog").unwrap();
-            //let search_humble_bundle = config_args.value_source("humble_bundle").unwrap();
+            let search_humble_bundle = config_args.value_source("humble_bundle").unwrap();
             let search_microsoft_store = config_args.value_source("microsoft_store").unwrap();
+            let search_epic = config_args.value_source("epic").unwrap();
             let search_all = config_args.value_source("all_stores").unwrap();
 
             let mut selected : Vec<String> = Vec::new();
             if search_steam == ValueSource::CommandLine { selected.push(settings::STEAM_STORE_ID.to_string()); }
             if search_gog == ValueSource::CommandLine { selected.push(settings::GOG_STORE_ID.to_string()); }
-            //if search_humble_bundle == ValueSource::CommandLine { selected.push(settings::HUMBLE_BUNDLE_STORE_ID.to_string()); }
             if search_microsoft_store == ValueSource::CommandLine { selected.push(settings::MICROSOFT_STORE_ID.to_string()); }
+            if search_humble_bundle == ValueSource::CommandLine { selected.push(settings::HUMBLE_BUNDLE_STORE_ID.to_string()); }
+            if search_epic == ValueSource::CommandLine { selected.push(settings::EPIC_STORE_ID.to_string()); }
             if search_all == ValueSource::CommandLine { selected = settings::get_available_stores(); } 
-            if selected.len() > 0 { settings::update_selected_stores(selected); }
+            if !selected.is_empty() { settings::update_selected_stores(selected); }
             if config_args.contains_id("alias_state"){
-                let alias_state : i32 = config_args.get_one::<i32>("alias_state").unwrap().clone();
+                let alias_state : i32 = *config_args.get_one::<i32>("alias_state").unwrap();
                 if alias_state == 0 || alias_state == 1{ settings::update_alias_state(alias_state); }
                 else { panic!("The alias state must be set to 0 or 1 not \'{}\'", alias_state); }
             }
+            if let Some(best_deal) = config_args.get_one::<i32>("best_deal") {
+                if *best_deal == 0 || *best_deal == 1 { settings::update_best_deal_state(*best_deal); }
+                else { panic!("The best deal state must be set to 0 or 1 not \'{}\'", best_deal); }
+            }
+            if let Some(region) = config_args.get_one::<String>("region") {
+                settings::update_region(region);
+            }
         },
         Some(("add", add_args)) => {
             let test_flag = add_args.value_source("test_flag").unwrap();
             if test_flag == ValueSource::CommandLine { json::enable_test_flag(); }
 
+            set_selection_mode(add_args);
+            microsoft_store::set_include_add_ons(add_args.get_flag("add-ons"));
             let selected_stores = storefront_check();
             let alias = if add_args.contains_id("alias") && settings::get_alias_state() {
                 add_args.get_one::<String>("alias").unwrap().clone()
             } else { 
                 thresholds::set_game_alias() 
             };
-            let title = add_args.get_one::<String>("title").unwrap().clone();
-            let price = add_args.get_one::<f64>("price").unwrap().clone();
+            let price = *add_args.get_one::<f64>("price").unwrap();
             let http_client = reqwest::Client::new();
-            for store in selected_stores.iter(){
-                if store == settings::STEAM_STORE_ID {
-                    steam_insert_sequence(&alias, &title, price, &http_client).await;
-                } 
-                if store == settings::GOG_STORE_ID {
-                    gog_insert_sequence(&alias, &title, price, &http_client).await;
-                }
-                if store == settings::MICROSOFT_STORE_ID {
-                    microsoft_store_insert_sequence(&alias, &title, price, &http_client).await;
+            let mut report = InsertReport::default();
+            // The product ID is only looked up on the Microsoft Store (the title is not searched)
+            if let Some(product_id) = add_args.get_one::<String>("microsoft-store-id") {
+                let outcome = microsoft_store::insert_by_product_id(&alias, product_id, price, &http_client).await;
+                report.add(product_id, settings::MICROSOFT_STORE_ID, outcome);
+            }
+            else {
+                let title = add_args.get_one::<String>("title").unwrap().clone();
+                for store in selected_stores.iter().filter_map(|id| storefront::get_storefront(id)){
+                    let outcome = store.insert_sequence(&alias, &title, price, &http_client).await;
+                    report.add(&title, store.id(), outcome);
                 }
             }
+            print_insert_report(&report);
         },
         Some(("bulk-insert", bulk_args)) => {
             let test_flag = bulk_args.value_source("test_flag").unwrap();
             if test_flag == ValueSource::CommandLine { json::enable_test_flag(); }
 
+            set_selection_mode(bulk_args);
+            microsoft_store::set_include_add_ons(bulk_args.get_flag("add-ons"));
             let selected_stores = storefront_check();
             let mut game_list: Vec<SimpleGameThreshold> = Vec::new();
             let file_path = bulk_args.get_one::<String>("file").unwrap().clone();
@@ -407,61 +595,152 @@ async fn main(){
                 Err(e) => eprintln!("Could not parse file: {}\n{}", file_path, e),
             }
             let http_client = reqwest::Client::new();
+            let mut report = InsertReport::default();
             for game in game_list.iter(){
                 println!("INSERT GAME -> \"{}\"", game.name);
                 let title = &game.name;
                 let alias = thresholds::set_game_alias();
                 let price: f64 = game.price;
-                for store in selected_stores.iter(){
-                    if store == settings::STEAM_STORE_ID {
-                        steam_insert_sequence(&alias, &title, price, &http_client).await;
-                    }
-                    if store == settings::GOG_STORE_ID {
-                        gog_insert_sequence(&alias, &title, price, &http_client).await;
-                    }
-                    if store == settings::MICROSOFT_STORE_ID {
-                        microsoft_store_insert_sequence(&alias, &title, price, &http_client).await;
-                    }
+                for store in selected_stores.iter().filter_map(|id| storefront::get_storefront(id)){
+                    let outcome = store.insert_sequence(&alias, title, price, &http_client).await;
+                    report.add(title, store.id(), outcome);
                 }
             }
+            print_insert_report(&report);
+        },
+        Some(("search", search_args)) => {
+            let test_flag = search_args.value_source("test_flag").unwrap();
+            if test_flag == ValueSource::CommandLine { json::enable_test_flag(); }
+
+            microsoft_store::set_include_add_ons(search_args.get_flag("add-ons"));
+            let stores : Vec<Box<dyn storefront::Storefront>> = storefront_check().iter()
+                .filter_map(|id| storefront::get_storefront(id))
+                .collect();
+            let title = search_args.get_one::<String>("title").unwrap().clone();
+            let http_client = reqwest::Client::new();
+            let (results, failures) = storefront::search_stores(&stores, &title, &http_client).await;
+            for failure in failures.iter() {
+                eprintln!("Search failed for {}", failure);
+            }
+            if search_args.get_flag("json") {
+                println!("{}", serde_json::to_string(&results).expect("Could not convert search results to string."));
+            }
+            else if results.is_empty() { println!("Could not find a game title matching \"{}\".", title); }
+            else { println!("{}", get_search_table(&results)); }
+        },
+        Some(("compare", compare_args)) => {
+            let test_flag = compare_args.value_source("test_flag").unwrap();
+            if test_flag == ValueSource::CommandLine { json::enable_test_flag(); }
+
+            let thresholds = thresholds::load_data().unwrap_or_else(|_e|Vec::new());
+            let http_client = reqwest::Client::new();
+            let mut comparisons = compare::compare_prices(&thresholds, &http_client).await;
+            for game in comparisons.iter() {
+                for failure in game.failures.iter() {
+                    eprintln!("Price check failed for {} ({})", game.title, failure);
+                }
+            }
+            comparisons.retain(|game| !game.prices.is_empty());
+            if compare_args.get_flag("discounted") { comparisons.retain(compare::is_discounted); }
+            if comparisons.is_empty() { println!("No prices to compare"); }
+            else { println!("{}", get_compare_table(&comparisons)); }
         },
         Some(("update", update_args)) => {
             let test_flag = update_args.value_source("test_flag").unwrap();
             if test_flag == ValueSource::CommandLine { json::enable_test_flag(); }
 
             let title = update_args.get_one::<String>("title").unwrap().clone();
-            let price = update_args.get_one::<f64>("price").unwrap().clone();
+            let price = *update_args.get_one::<f64>("price").unwrap();
             thresholds::update_price(&title, price);
         },
         Some(("remove", remove_args)) => {
             let test_flag = remove_args.value_source("test_flag").unwrap();
             if test_flag == ValueSource::CommandLine { json::enable_test_flag(); }
-
-            if remove_args.contains_id("test_flag") { json::enable_test_flag(); }
             let title = remove_args.get_one::<String>("title").unwrap().clone();
             thresholds::remove(&title);
         },
+        Some(("rules", rules_args)) => {
+            let test_flag = rules_args.value_source("test_flag").unwrap();
+            if test_flag == ValueSource::CommandLine { json::enable_test_flag(); }
+
+            let title = rules_args.get_one::<String>("title").unwrap().clone();
+            match thresholds::get_threshold(&title) {
+                Some(threshold) => {
+                    let mut rules = threshold.rules.clone();
+                    if let Some(state) = rules_args.get_one::<i32>("price_rule") { rules.use_desired_price = *state == 1; }
+                    if let Some(state) = rules_args.get_one::<i32>("all_time_low") { rules.all_time_low = *state == 1; }
+                    if let Some(state) = rules_args.get_one::<i32>("match_all") { rules.match_all = *state == 1; }
+                    if let Some(state) = rules_args.get_one::<i32>("game_pass") { rules.game_pass = *state == 1; }
+                    if let Some(state) = rules_args.get_one::<i32>("ending_soon") { rules.ending_soon = *state == 1; }
+                    if let Some(discount) = rules_args.get_one::<f64>("min_discount") {
+                        rules.min_discount = if *discount > 0.0 { Some(*discount) } else { None };
+                    }
+                    if let Some(percentile) = rules_args.get_one::<f64>("bottom_percentile") {
+                        rules.bottom_percentile = if *percentile > 0.0 { Some(*percentile) } else { None };
+                    }
+                    thresholds::update_rules(&title, rules);
+                },
+                None => println!("\"{}\" does not have a configured threshold.", title)
+            }
+        },
+        Some(("region", region_args)) => {
+            let test_flag = region_args.value_source("test_flag").unwrap();
+            if test_flag == ValueSource::CommandLine { json::enable_test_flag(); }
+
+            let title = region_args.get_one::<String>("title").unwrap().clone();
+            let region = region_args.get_one::<String>("region").unwrap().clone();
+            if region == "default" { thresholds::update_region(&title, ""); }
+            else { thresholds::update_region(&title, &region); }
+        },
+        Some(("limits", limits_args)) => {
+            let test_flag = limits_args.value_source("test_flag").unwrap();
+            if test_flag == ValueSource::CommandLine { json::enable_test_flag(); }
+
+            let store_id = limits_args.get_one::<String>("store").unwrap().clone();
+            let concurrency = limits_args.get_one::<usize>("concurrency").copied();
+            let rate = limits_args.get_one::<f64>("rate").copied();
+            settings::update_store_limits(&store_id, concurrency, rate);
+        },
+        Some(("history", history_args)) => {
+            let test_flag = history_args.value_source("test_flag").unwrap();
+            if test_flag == ValueSource::CommandLine { json::enable_test_flag(); }
+
+            let title = history_args.get_one::<String>("title").unwrap().clone();
+            history::list_history(&title);
+        },
         _ => {
             if cmd.get_flag("test_flag") { json::enable_test_flag(); }
             if cmd.get_flag("thresholds") { thresholds::list_games(); }
             else if cmd.get_flag("selected-stores") { settings::list_selected(); }
             else if cmd.get_flag("cache"){
                 println!("Caching started");
-                steam::update_cached_games().await;
+                let result = if cmd.get_flag("refresh") { steam::refresh_cached_games().await }
+                             else { steam::update_cached_games().await };
+                if let Err(e) = result {
+                    eprintln!("Failed to update cached games: {}", e);
+                }
             }
             else if cmd.get_flag("check-prices") {
+                // Requests are sent live unless HTTP_TRANSPORT=record is set (to save the responses for --offline)
+                if cmd.get_flag("offline") { http::set_transport(Transport::Replay); }
                 let use_html = false;
-                let prices_str = check_prices(use_html).await;
+                let (mut prices_str, failures) = check_prices(use_html).await;
+                if !failures.is_empty() { prices_str.push_str(&get_failures_str(&failures)); }
                 if !prices_str.is_empty() {
                     println!("------------\nCHECK PRICES\n------------\n{}", prices_str);
                 }
             }
             else if cmd.get_flag("email"){
                 let use_html = true;
-                let email_str = check_prices(use_html).await;
-                println!("Email Contents:\n{}\n", email_str);
-                if email_str.is_empty(){ println!("No game(s) on sale at price thresholds"); }
+                let (mut email_str, failures) = check_prices(use_html).await;
+                // Failures alone are not worth an email
+                if email_str.is_empty(){
+                    println!("No game(s) on sale at price thresholds");
+                    if !failures.is_empty() { eprintln!("{}", get_failures_str(&failures).trim_start()); }
+                }
                 else {
+                    if !failures.is_empty() { email_str.push_str(&email::create_failures_html(&failures)); }
+                    println!("Email Contents:\n{}\n", email_str);
                     println!("Sending email...");
                     let to_address = &get_recipient();
                     email::send_html_msg(to_address, "Check Out Which Games Are On Sale", &email_str);
@@ -0,0 +1,51 @@
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub enum StoreError {
+    // Request could not be sent or the store returned an unexpected status
+    Network(String),
+    // Response could not be converted to the expected format
+    Parse(String),
+    // Game/product does not exist on the store
+    NotFound(String),
+    // Game exists but has no price (e.g. free to play or unreleased)
+    NoPrice(String),
+    // Store returned HTTP 429 (along with how long it asked us to wait, if it did)
+    RateLimited(String, Option<Duration>),
+}
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Network(msg) => write!(f, "Network error: {}", msg),
+            StoreError::Parse(msg) => write!(f, "Parse error: {}", msg),
+            StoreError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            StoreError::NoPrice(msg) => write!(f, "No price available: {}", msg),
+            StoreError::RateLimited(msg, _) => write!(f, "Rate limited: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<reqwest::Error> for StoreError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS) { StoreError::RateLimited(e.to_string(), None) }
+        else { StoreError::Network(e.to_string()) }
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(e: serde_json::Error) -> Self {
+        StoreError::Parse(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for StoreError {
+    fn from(e: std::io::Error) -> Self {
+        StoreError::Parse(e.to_string())
+    }
+}
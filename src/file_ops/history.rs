@@ -0,0 +1,125 @@
+use chrono::{Duration, Local, Utc};
+use serde_json::Result;
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use crate::file_ops::{json, settings, thresholds};
+use crate::structs::data::{Money, PriceHistory, PriceRecord, SaleInfo};
+
+static HISTORY_FILENAME : &str = "price_history.json";
+
+pub fn get_path() -> String {
+    let path_buf: PathBuf = [json::get_data_path(), HISTORY_FILENAME.to_string()].iter().collect();
+    let history_path = path_buf.display().to_string();
+    json::get_path(&history_path) //Creates file if it does not exist already
+}
+
+pub fn load_data() -> Result<Vec<PriceHistory>> {
+    let filepath = get_path();
+    let data = read_to_string(filepath).unwrap();
+    serde_json::from_str::<Vec<PriceHistory>>(&data)
+}
+
+pub fn create_record(store_id: &str, info: &SaleInfo) -> PriceRecord {
+    PriceRecord {
+        store_id: store_id.to_string(),
+        timestamp: Utc::now(),
+        original_price: info.original_price.clone(),
+        current_price: info.current_price.clone(),
+        discount_percentage: info.discount_percentage,
+        game_pass: info.subscription.is_included(),
+        sale_ends_at: info.sale_ends_at,
+    }
+}
+
+// Sale was ending within thresholds::ENDING_SOON_HOURS when the price was recorded
+pub fn is_ending_soon_record(record: &PriceRecord) -> bool {
+    record.sale_ends_at.is_some_and(|ends_at| ends_at - record.timestamp <= Duration::hours(thresholds::ENDING_SOON_HOURS))
+}
+
+// Saves every observed price as (threshold title, record) pairs so each record stands for one check
+pub fn add_records(observed: Vec<(String, PriceRecord)>) {
+    if observed.is_empty() { return; }
+    let mut history = load_data().unwrap_or_else(|_e|Vec::new());
+    for (title, record) in observed {
+        match history.iter_mut().find(|game| game.title == title) {
+            Some(game) => game.records.push(record),
+            None => history.push(PriceHistory { title, records: vec![record] }),
+        }
+    }
+    let data_str = serde_json::to_string_pretty(&history).expect("Could not convert price history to string.");
+    json::write_to_file(get_path(), data_str);
+}
+
+// Looks up history using the threshold title or alias
+pub fn get_history(title: &str) -> Option<PriceHistory> {
+    let game_title = match thresholds::get_threshold(title) {
+        Some(threshold) => threshold.title,
+        None => title.to_string(),
+    };
+    let history = load_data().unwrap_or_else(|_e|Vec::new());
+    history.into_iter().find(|game| game.title == game_title)
+}
+
+// Records saved in the currency (records without a currency are assumed to be in it)
+pub fn get_records_in_currency(history: &PriceHistory, currency: &str) -> PriceHistory {
+    PriceHistory {
+        title: history.title.clone(),
+        records: history.records.iter()
+            .filter(|record| record.currency().is_empty() || record.currency() == currency)
+            .cloned()
+            .collect(),
+    }
+}
+
+// Records are compared in minor units so the history should only have prices in one currency (see get_records_in_currency)
+pub fn get_all_time_low(history: &PriceHistory) -> Option<&PriceRecord> {
+    history.records.iter().min_by_key(|record| record.current_price.amount)
+}
+
+pub fn get_average_price(history: &PriceHistory) -> Option<f64> {
+    if history.records.is_empty() { return None; }
+    let total : f64 = history.records.iter().map(|record| record.current_price.to_major()).sum();
+    Some(total / history.records.len() as f64)
+}
+
+// Percentage of recorded prices that are lower than the given price
+pub fn get_percentile_rank(history: &PriceHistory, price: &Money) -> Option<f64> {
+    if history.records.is_empty() { return None; }
+    let lower_count = history.records.iter().filter(|record| record.current_price.amount < price.amount).count();
+    Some(lower_count as f64 * 100.0 / history.records.len() as f64)
+}
+
+fn get_store_name(store_id: &str) -> String {
+    settings::get_proper_store_name(store_id).unwrap_or(store_id.to_string())
+}
+
+// Stats only use the prices recorded in the threshold currency
+pub fn list_history(title: &str) {
+    match get_history(title) {
+        Some(history) => {
+            println!("Price History: {}", history.title);
+            for record in history.records.iter() {
+                println!("  - {} | {} | {} -> {} {} ({}% off)",
+                         record.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                         get_store_name(&record.store_id),
+                         record.original_price, record.current_price, record.currency(),
+                         record.discount_percentage);
+            }
+            let currency = match thresholds::get_threshold(&history.title) {
+                Some(threshold) => thresholds::get_currency(&threshold),
+                None => history.records.last().map(|record| record.currency().to_string()).unwrap_or_default(),
+            };
+            let same_currency = get_records_in_currency(&history, &currency);
+            if let Some(low) = get_all_time_low(&same_currency) {
+                println!("All-time low: {} {} ({}, {})", low.current_price, currency,
+                         get_store_name(&low.store_id),
+                         low.timestamp.with_timezone(&Local).format("%Y-%m-%d"));
+            }
+            if let Some(average) = get_average_price(&same_currency) {
+                println!("Average price: {:.2} {}", average, currency);
+            }
+        },
+        None => println!("No price history found for \"{}\".", title)
+    }
+}
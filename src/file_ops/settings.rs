@@ -3,31 +3,79 @@ use serde_json::Result;
 use serde_json::{Value, json};
 use std::fs::{read_to_string, metadata};
 use std::collections::HashMap;
+use std::env;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use lazy_static::lazy_static;
 
 use crate::file_ops::json;
+use crate::stores::storefront;
+use crate::structs::data::{GameThreshold, Region, StoreLimits};
 
 static CONFIG_FILENAME : &str = "config.json";
 static ALIAS_ENABLED : i32 = 1;
 static ALIAS_DISABLED : i32 = 0;
+static BEST_DEAL_ENABLED : i32 = 1;
+static BEST_DEAL_DISABLED : i32 = 0;
 
 // Store IDs
 pub const STEAM_STORE_ID : &str = "steam";
 pub const GOG_STORE_ID : &str = "gog";
 pub const MICROSOFT_STORE_ID : &str = "microsoft_store";
+pub const HUMBLE_BUNDLE_STORE_ID : &str = "humble_bundle";
+pub const EPIC_STORE_ID : &str = "epic";
+
+// Regions (country code, currency code, locale)
+pub const DEFAULT_REGION : &str = "US";
+static REGIONS : [(&str, &str, &str); 14] = [
+    ("US", "USD", "en-US"),
+    ("CA", "CAD", "en-CA"),
+    ("GB", "GBP", "en-GB"),
+    ("IE", "EUR", "en-IE"),
+    ("DE", "EUR", "de-DE"),
+    ("FR", "EUR", "fr-FR"),
+    ("ES", "EUR", "es-ES"),
+    ("IT", "EUR", "it-IT"),
+    ("NL", "EUR", "nl-NL"),
+    ("PL", "PLN", "pl-PL"),
+    ("AU", "AUD", "en-AU"),
+    ("NZ", "NZD", "en-NZ"),
+    ("BR", "BRL", "pt-BR"),
+    ("JP", "JPY", "ja-JP"),
+];
+
+// Default request limits (used by stores that do not set their own)
+pub const DEFAULT_CONCURRENCY : usize = 4;
+pub const DEFAULT_REQUESTS_PER_SECOND : f64 = 4.0;
+
+// Base URL keys (override with <KEY>_BASE_URL or "base_urls" in settings)
+pub const STEAM_API_URL_KEY : &str = "steam_api";
+pub const STEAM_STORE_URL_KEY : &str = "steam_store";
+pub const GOG_CATALOG_URL_KEY : &str = "gog_catalog";
+pub const GOG_EMBED_URL_KEY : &str = "gog_embed";
+pub const MICROSOFT_STORE_URL_KEY : &str = "microsoft_store";
+pub const HUMBLE_BUNDLE_URL_KEY : &str = "humble_bundle";
+pub const EPIC_URL_KEY : &str = "epic";
+pub const EPIC_PROMOTIONS_URL_KEY : &str = "epic_promotions";
+
+lazy_static! {
+    // Settings loaded on first use (along with the path, modified time and size they were loaded from)
+    static ref SETTINGS_CACHE : Mutex<Option<(String, SystemTime, u64, Value)>> = Mutex::new(None);
+}
 
 // Store Names (Plain text)
 pub const STEAM_STORE_NAME : &str = "Steam";
 pub const GOG_STORE_NAME : &str = "Good Old Games (GOG)";
 pub const MICROSOFT_STORE_NAME : &str = "Microsoft Store (PC)";
+pub const HUMBLE_BUNDLE_STORE_NAME : &str = "Humble Bundle";
+pub const EPIC_STORE_NAME : &str = "Epic Games Store";
 
 fn get_store_map() -> HashMap<String, String> {
-    let store_map = HashMap::from([
-        (STEAM_STORE_ID.to_string(), STEAM_STORE_NAME.to_string()),
-        (GOG_STORE_ID.to_string(), GOG_STORE_NAME.to_string()),
-        (MICROSOFT_STORE_ID.to_string(), MICROSOFT_STORE_NAME.to_string()),
-    ]);
-    store_map
+    storefront::get_storefronts()
+        .iter()
+        .map(|store| (store.id().to_string(), store.name().to_string()))
+        .collect()
 }
 
 pub fn get_available_stores() -> Vec<String> {
@@ -47,14 +95,14 @@ pub fn get_proper_store_name(id: &str) -> Option<String> {
     None
 }
 
-fn get_path() -> String{
+pub fn get_path() -> String{
     let path_buf: PathBuf = [json::get_data_path(), CONFIG_FILENAME.to_string()].iter().collect();
     let config_path = path_buf.display().to_string();
     let path_str = json::get_path(&config_path);  //Creates file if it does not exist already
     match metadata(&path_str){
         Ok(md) => {
             if md.len() == 0 {
-                let settings = json!({"selected_stores": [], "alias_enabled": 1});
+                let settings = json!({"selected_stores": [], "alias_enabled": 1, "best_deal_enabled": 0, "region": DEFAULT_REGION});
                 let settings_str = serde_json::to_string_pretty(&settings);
                 json::write_to_file(config_path.to_string(), settings_str.expect("Initial settings could not be created."));
             }
@@ -64,18 +112,24 @@ fn get_path() -> String{
     path_str
 }
 
+// Only re-reads the file when it has changed since it was last loaded
 pub fn load_data() -> Result<Value> {
     let filepath = get_path();
-    let data = read_to_string(filepath).unwrap();
+    let file_state = metadata(&filepath).ok().and_then(|md| Some((md.modified().ok()?, md.len())));
+    let mut cache = SETTINGS_CACHE.lock().unwrap();
+    if let Some((modified, len)) = file_state && let Some((path, cached_modified, cached_len, body)) = cache.as_ref()
+        && *path == filepath && *cached_modified == modified && *cached_len == len {
+        return Ok(body.clone());
+    }
+    let data = read_to_string(&filepath).unwrap();
     let body : Value = serde_json::from_str(&data)?;
+    *cache = file_state.map(|(modified, len)| (filepath, modified, len, body.clone()));
     Ok(body)
 }
 
 pub fn get_selected_stores() -> Vec<String> {
-    let filepath = get_path();
     let mut stores : Vec<String> = Vec::new();
-    let data = read_to_string(filepath).unwrap();
-    let body : Value = serde_json::from_str(&data).expect("Get selected stores - could not convert to JSON");
+    let body : Value = load_data().expect("Get selected stores - could not convert to JSON");
     let selected = serde_json::to_string(&body["selected_stores"]).unwrap();
     match serde_json::from_str::<Vec<String>>(&selected){
         Ok(data) => stores = data,
@@ -84,11 +138,10 @@ pub fn get_selected_stores() -> Vec<String> {
     stores
 }
 
+#[allow(clippy::needless_bool_assign)]
 pub fn get_alias_state() -> bool {
-    let filepath = get_path();
     let mut state : bool = true;
-    let data = read_to_string(filepath).unwrap();
-    let body : Value = serde_json::from_str(&data).expect("Get alias state - could not convert to JSON");
+    let body : Value = load_data().expect("Get alias state - could not convert to JSON");
     let alias_enabled =serde_json::to_string(&body["alias_enabled"]).unwrap();
     match serde_json::from_str::<i32>(&alias_enabled){
         Ok(state_val) => {
@@ -100,6 +153,101 @@ pub fn get_alias_state() -> bool {
     state
 }
 
+// Each game is only reported once at its cheapest store (disabled by default and for older settings files)
+pub fn get_best_deal_state() -> bool {
+    match load_data() {
+        Ok(body) => body["best_deal_enabled"].as_i64() == Some(BEST_DEAL_ENABLED as i64),
+        Err(_) => false
+    }
+}
+
+pub fn get_available_regions() -> Vec<String> {
+    REGIONS.iter().map(|(country_code, _, _)| country_code.to_string()).collect()
+}
+
+pub fn get_region_info(country_code: &str) -> Option<Region> {
+    let code = country_code.trim().to_uppercase();
+    REGIONS.iter()
+        .find(|(region_code, _, _)| *region_code == code)
+        .map(|(region_code, currency, locale)| Region {
+            country_code: region_code.to_string(),
+            currency: currency.to_string(),
+            locale: locale.to_string(),
+        })
+}
+
+pub fn get_region() -> Region {
+    let mut country_code = DEFAULT_REGION.to_string();
+    if let Ok(body) = load_data() && let Some(code) = body["region"].as_str() {
+        country_code = code.to_string();
+    }
+    get_region_info(&country_code).unwrap_or_else(|| get_region_info(DEFAULT_REGION).unwrap())
+}
+
+// Environment variable takes priority over settings (e.g. to point stores at a local mock server)
+pub fn get_base_url(key: &str, default: &str) -> String {
+    let mut base_url = default.to_string();
+    if let Ok(url) = env::var(format!("{}_BASE_URL", key.to_uppercase())) {
+        base_url = url;
+    }
+    else if let Ok(body) = load_data() && let Some(url) = body["base_urls"][key].as_str() {
+        base_url = url.to_string();
+    }
+    base_url.trim_end_matches('/').to_string()
+}
+
+// Uses the threshold region override if one is set
+pub fn get_threshold_region(threshold: &GameThreshold) -> Region {
+    get_region_info(&threshold.region).unwrap_or_else(get_region)
+}
+
+fn is_valid_concurrency(concurrency: usize) -> bool {
+    concurrency >= 1
+}
+
+fn is_valid_requests_per_second(requests_per_second: f64) -> bool {
+    requests_per_second > 0.0 && requests_per_second.is_finite()
+}
+
+// Uses the limits in settings if set and valid, otherwise the store defaults
+pub fn get_store_limits(store_id: &str) -> Option<StoreLimits> {
+    let store = storefront::get_storefront(store_id)?;
+    let mut limits = store.default_limits();
+    if let Ok(body) = load_data() && let Ok(saved) = serde_json::from_value::<StoreLimits>(body["store_limits"][store_id].clone()) {
+        if is_valid_concurrency(saved.concurrency) && is_valid_requests_per_second(saved.requests_per_second) { limits = saved; }
+        else { eprintln!("Ignoring invalid {} limits in settings, using the defaults", store.name()); }
+    }
+    Some(limits)
+}
+
+pub fn update_store_limits(store_id: &str, concurrency: Option<usize>, requests_per_second: Option<f64>){
+    let mut limits = match get_store_limits(store_id) {
+        Some(limits) => limits,
+        None => {
+            eprintln!("Unsupported store: \"{}\". Supported stores: {}", store_id, get_available_stores().join(", "));
+            return;
+        }
+    };
+    if let Some(value) = concurrency {
+        if !is_valid_concurrency(value) { eprintln!("Concurrency must be at least 1"); return; }
+        limits.concurrency = value;
+    }
+    if let Some(value) = requests_per_second {
+        if !is_valid_requests_per_second(value) { eprintln!("Requests per second must be greater than 0"); return; }
+        limits.requests_per_second = value;
+    }
+    match load_data(){
+        Ok(data) => {
+            let mut settings = data;
+            if !settings["store_limits"].is_object() { settings["store_limits"] = json!({}); }
+            settings["store_limits"][store_id] = json!(limits);
+            let settings_str = serde_json::to_string_pretty(&settings);
+            json::write_to_file(get_path(), settings_str.expect("Cannot update store limits"));
+        },
+        Err(e) => eprintln!("Error: {}", e)
+    }
+}
+
 pub fn update_selected_stores(selected: Vec<String>) {
     match load_data(){
         Ok(data) => {
@@ -130,6 +278,36 @@ pub fn update_alias_state(is_enabled: i32){
     }
 }
 
+pub fn update_best_deal_state(is_enabled: i32){
+    match load_data(){
+        Ok(data) => {
+            let mut settings = data;
+            let enabled_status = if is_enabled == BEST_DEAL_ENABLED || is_enabled == BEST_DEAL_DISABLED { is_enabled } else { BEST_DEAL_DISABLED };
+            settings["best_deal_enabled"] = json!(enabled_status);
+            let settings_str = serde_json::to_string_pretty(&settings);
+            json::write_to_file(get_path(), settings_str.expect("Cannot set state of best deal reports"));
+        },
+        Err(e) => eprintln!("Error: {}", e)
+    }
+}
+
+pub fn update_region(country_code: &str){
+    match get_region_info(country_code) {
+        Some(region) => {
+            match load_data(){
+                Ok(data) => {
+                    let mut settings = data;
+                    settings["region"] = json!(region.country_code);
+                    let settings_str = serde_json::to_string_pretty(&settings);
+                    json::write_to_file(get_path(), settings_str.expect("Cannot update region"));
+                },
+                Err(e) => eprintln!("Error: {}", e)
+            }
+        },
+        None => eprintln!("Unsupported region: \"{}\". Supported regions: {}", country_code, get_available_regions().join(", "))
+    }
+}
+
 pub fn list_selected(){
     let available_stores = get_available_stores();
     let selected = get_selected_stores();
@@ -146,4 +324,14 @@ pub fn list_selected(){
         if is_selected { println!("  [X] {}", proper_name); }
         else { println!("  [ ] {}", proper_name); }
     }
+    let region = get_region();
+    println!("Region: {} ({})", region.country_code, region.currency);
+    println!("Best deal reports: {}", if get_best_deal_state() { "enabled" } else { "disabled" });
+    println!("Request Limits");
+    for a_store in available_stores.iter(){
+        if let Some(limits) = get_store_limits(a_store) {
+            println!("  {} : {} concurrent, {} request(s)/s",
+                     get_proper_store_name(a_store).unwrap(), limits.concurrency, limits.requests_per_second);
+        }
+    }
 }
\ No newline at end of file
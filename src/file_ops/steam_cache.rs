@@ -0,0 +1,244 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, read_to_string};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::error::{StoreError, StoreResult};
+use crate::file_ops::json;
+use crate::structs::steam_response::App;
+use crate::title_match;
+
+static CACHE_FILENAME : &str = "steam_app_cache.json";
+// Cache saved as a pretty printed list of apps (migrated the first time the cache is loaded)
+static LEGACY_CACHE_FILENAME : &str = "steam_game_titles_cache.json";
+static CACHE_VERSION : u32 = 1;
+
+// Entries are saved as [app_id, name, last_modified, price_change_number] to keep the file small
+#[derive(Deserialize, Serialize)]
+struct CacheFile {
+    version: u32,
+    entries: Vec<(usize, String, i64, i64)>,
+}
+
+// Name -> app IDs sharing that name (the lowest app ID is used for lookups)
+type NameIndex = HashMap<String, BTreeSet<usize>>;
+
+#[derive(Debug, Default, Clone)]
+pub struct AppCache {
+    // App ID -> entry
+    entries: HashMap<usize, App>,
+    // Exact names (kept up to date as apps are added and renamed)
+    names: NameIndex,
+    // Normalized names (used when the exact name is not cached). Built on first use since normalizing every title is slow.
+    normalized_names: OnceLock<NameIndex>,
+    // Lowercase names sorted for prefix search (built on first use and cleared when apps change)
+    sorted_names: OnceLock<Vec<(String, usize)>>,
+}
+
+fn add_to_index(index: &mut NameIndex, name: String, app_id: usize) {
+    index.entry(name).or_default().insert(app_id);
+}
+
+fn remove_from_index(index: &mut NameIndex, name: &str, app_id: usize) {
+    if let Some(app_ids) = index.get_mut(name) {
+        app_ids.remove(&app_id);
+        if app_ids.is_empty() { index.remove(name); }
+    }
+}
+
+fn find_in_index(index: &NameIndex, name: &str) -> Option<usize> {
+    index.get(name).and_then(|app_ids| app_ids.first().copied())
+}
+
+impl AppCache {
+    pub fn from_apps(apps: Vec<App>) -> AppCache {
+        let mut cache = AppCache::default();
+        cache.add_new(apps);
+        cache
+    }
+
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    pub fn get(&self, app_id: usize) -> Option<&App> { self.entries.get(&app_id) }
+
+    pub fn find_by_name(&self, name: &str) -> Option<&App> {
+        find_in_index(&self.names, name).and_then(|app_id| self.entries.get(&app_id))
+    }
+
+    // Exact name first and then the normalized name (e.g. "Clair Obscur Expedition 33" finds "Clair Obscur: Expedition 33™")
+    pub fn find_by_title(&self, title: &str) -> Option<&App> {
+        self.find_by_name(title).or_else(|| {
+            find_in_index(self.get_normalized_names(), &title_match::normalize_title(title)).and_then(|app_id| self.entries.get(&app_id))
+        })
+    }
+
+    // Highest cached app ID (new apps are requested after this ID)
+    pub fn last_appid(&self) -> usize {
+        self.entries.keys().max().copied().unwrap_or(0)
+    }
+
+    // Case-insensitive prefix search (results are sorted by name)
+    pub fn search_prefix(&self, prefix: &str) -> Vec<&App> {
+        let prefix = prefix.to_lowercase();
+        let sorted_names = self.get_sorted_names();
+        let start = sorted_names.partition_point(|(name, _)| name.as_str() < prefix.as_str());
+        sorted_names[start..].iter()
+            .take_while(|(name, _)| name.starts_with(&prefix))
+            .filter_map(|(_, app_id)| self.entries.get(app_id))
+            .collect()
+    }
+
+    // Apps matching the filter sorted by app ID
+    pub fn search<F: Fn(&str) -> bool>(&self, filter: F) -> Vec<&App> {
+        let mut apps : Vec<&App> = self.entries.values().filter(|app| filter(&app.name)).collect();
+        apps.sort_by_key(|app| app.app_id);
+        apps
+    }
+
+    fn get_normalized_names(&self) -> &NameIndex {
+        self.normalized_names.get_or_init(|| {
+            let mut index = NameIndex::new();
+            for app in self.entries.values() {
+                let normalized = title_match::normalize_title(&app.name);
+                if !normalized.is_empty() { add_to_index(&mut index, normalized, app.app_id); }
+            }
+            index
+        })
+    }
+
+    fn get_sorted_names(&self) -> &Vec<(String, usize)> {
+        self.sorted_names.get_or_init(|| {
+            let mut sorted_names : Vec<(String, usize)> = self.entries.values().map(|app| (app.name.to_lowercase(), app.app_id)).collect();
+            sorted_names.sort();
+            sorted_names
+        })
+    }
+
+    fn index_name(&mut self, app: &App) {
+        add_to_index(&mut self.names, app.name.clone(), app.app_id);
+        if let Some(normalized_names) = self.normalized_names.get_mut() {
+            let normalized = title_match::normalize_title(&app.name);
+            if !normalized.is_empty() { add_to_index(normalized_names, normalized, app.app_id); }
+        }
+    }
+
+    fn remove_name(&mut self, app: &App) {
+        remove_from_index(&mut self.names, &app.name, app.app_id);
+        if let Some(normalized_names) = self.normalized_names.get_mut() {
+            remove_from_index(normalized_names, &title_match::normalize_title(&app.name), app.app_id);
+        }
+    }
+
+    // Adds apps that are not cached yet (apps without a name are skipped). Returns the number of added apps.
+    pub fn add_new(&mut self, apps: Vec<App>) -> usize {
+        let mut added = 0;
+        for game in apps {
+            if game.name.is_empty() || self.entries.contains_key(&game.app_id) { continue; }
+            self.index_name(&game);
+            self.entries.insert(game.app_id, game);
+            added += 1;
+        }
+        if added > 0 { self.sorted_names.take(); }
+        added
+    }
+
+    // Updates changed apps in place and adds new ones. Returns the number of (added, updated) apps.
+    pub fn merge(&mut self, apps: Vec<App>) -> (usize, usize) {
+        let (mut added, mut updated) = (0, 0);
+        for game in apps {
+            if game.name.is_empty() { continue; }
+            let cached_game = match self.entries.get(&game.app_id) {
+                Some(cached_game) if !is_app_changed(cached_game, &game) => continue,
+                Some(_) => self.entries.remove(&game.app_id),
+                None => None,
+            };
+            match cached_game {
+                Some(cached_game) => {
+                    self.remove_name(&cached_game);
+                    updated += 1;
+                },
+                None => added += 1,
+            }
+            self.index_name(&game);
+            self.entries.insert(game.app_id, game);
+        }
+        if added + updated > 0 { self.sorted_names.take(); }
+        (added, updated)
+    }
+}
+
+fn is_app_changed(cached_game: &App, game: &App) -> bool {
+    game.last_modified != cached_game.last_modified ||
+        game.price_change_number != cached_game.price_change_number ||
+        game.name != cached_game.name
+}
+
+lazy_static! {
+    // Cache loaded on first use (along with the path it was loaded from)
+    static ref APP_CACHE : Mutex<Option<(String, Arc<AppCache>)>> = Mutex::new(None);
+}
+
+fn get_data_file_path(filename: &str) -> String {
+    let path_buf: PathBuf = [json::get_data_path(), filename.to_string()].iter().collect();
+    path_buf.display().to_string()
+}
+
+pub fn read_cache(path: &str) -> StoreResult<AppCache> {
+    let data = read_to_string(path)?;
+    let cache_file = serde_json::from_str::<CacheFile>(&data)?;
+    if cache_file.version != CACHE_VERSION {
+        return Err(StoreError::Parse(format!("Unsupported Steam app cache version {} in {}", cache_file.version, path)));
+    }
+    let apps = cache_file.entries.into_iter()
+        .map(|(app_id, name, last_modified, price_change_number)| App { app_id, name, last_modified, price_change_number })
+        .collect();
+    Ok(AppCache::from_apps(apps))
+}
+
+pub fn write_cache(path: &str, cache: &AppCache) -> StoreResult<()> {
+    let mut entries : Vec<(usize, String, i64, i64)> = cache.entries.values()
+        .map(|app| (app.app_id, app.name.clone(), app.last_modified, app.price_change_number))
+        .collect();
+    entries.sort_by_key(|entry| entry.0);
+    let data_str = serde_json::to_string(&CacheFile { version: CACHE_VERSION, entries })?;
+    fs::write(path, data_str)?;
+    Ok(())
+}
+
+// Converts the legacy cache file to the indexed format and removes it
+pub fn migrate_legacy_cache(legacy_path: &str, path: &str) -> StoreResult<AppCache> {
+    let data = read_to_string(legacy_path)?;
+    let apps = if data.trim().is_empty() { Vec::new() } else { serde_json::from_str::<Vec<App>>(&data)? };
+    let cache = AppCache::from_apps(apps);
+    write_cache(path, &cache)?;
+    fs::remove_file(legacy_path)?;
+    println!("Migrated {} cached Steam apps to {}", cache.len(), path);
+    Ok(cache)
+}
+
+// Returns the cache (loaded from disk the first time it is used)
+pub fn load() -> StoreResult<Arc<AppCache>> {
+    let path = get_data_file_path(CACHE_FILENAME);
+    let mut loaded = APP_CACHE.lock().unwrap();
+    if let Some((loaded_path, cache)) = loaded.as_ref() && *loaded_path == path {
+        return Ok(cache.clone());
+    }
+    let legacy_path = get_data_file_path(LEGACY_CACHE_FILENAME);
+    let cache = if Path::new(&path).is_file() { read_cache(&path)? }
+                else if Path::new(&legacy_path).is_file() { migrate_legacy_cache(&legacy_path, &path)? }
+                else { AppCache::default() };
+    let cache = Arc::new(cache);
+    *loaded = Some((path, cache.clone()));
+    Ok(cache)
+}
+
+pub fn save(cache: AppCache) -> StoreResult<()> {
+    let path = get_data_file_path(CACHE_FILENAME);
+    write_cache(&path, &cache)?;
+    *APP_CACHE.lock().unwrap() = Some((path, Arc::new(cache)));
+    Ok(())
+}
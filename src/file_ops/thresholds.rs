@@ -2,15 +2,21 @@ use std::io::{self, Write};
 use std::path::PathBuf;
 use serde_json::Result;
 use std::fs::read_to_string;
+use chrono::{DateTime, Duration, Utc};
 
-use crate::file_ops::{json, settings};
-use crate::stores::{steam}; //, gog, microsoft_store};
+use crate::file_ops::{json, settings, history};
+use crate::stores::{microsoft_store, steam, storefront};
 use crate::structs::steam_response::App;
 use crate::structs::gog_response::GameInfo as GOGGameInfo;
-use crate::structs::microsoft_store_response::ProductInfo;
-use crate::structs::data::GameThreshold;
+use crate::structs::microsoft_store_response::SKU;
+use crate::structs::humble_bundle_response::ProductInfo as HumbleBundleProductInfo;
+use crate::structs::epic_response::Offer as EpicOffer;
+use crate::title_match;
+use crate::structs::data::{AlertRules, GameThreshold, Money, PriceHistory, SaleInfo, SteamItemType};
 
 static THRESHOLD_FILENAME : &str = "thresholds.json";
+// Sales ending within this many hours are listed first in reports and can trigger the ending soon rule
+pub static ENDING_SOON_HOURS : i64 = 48;
 
 pub fn get_path() -> String {
     let path_buf: PathBuf = [json::get_data_path(), THRESHOLD_FILENAME.to_string()].iter().collect();
@@ -21,23 +27,31 @@ pub fn get_path() -> String {
 pub fn load_data() -> Result<Vec<GameThreshold>> {
     let filepath = get_path();
     let data = read_to_string(filepath).unwrap();
-    let path_str = serde_json::from_str::<Vec<GameThreshold>>(&data);
-    path_str
+    serde_json::from_str::<Vec<GameThreshold>>(&data)
 }
 
+// Titles and aliases are compared after normalization (see title_match::normalize_title)
 fn is_threshold(title: &str, game_thresh: &GameThreshold) -> bool {
-    title == game_thresh.title || title == game_thresh.alias
+    title_match::titles_match(title, &game_thresh.title) || title_match::titles_match(title, &game_thresh.alias)
 }
 
-pub async fn add_steam_game(new_alias: String, app: App, price: f64, client: &reqwest::Client){
+pub fn get_threshold(title: &str) -> Option<GameThreshold> {
+    let thresholds = load_data().unwrap_or_else(|_e|Vec::new());
+    thresholds.into_iter().find(|threshold| is_threshold(title, threshold))
+}
+
+// Each add function returns true if a threshold was added or the store ID was linked to an existing threshold
+pub async fn add_steam_game(new_alias: String, app: App, price: f64, client: &reqwest::Client) -> bool {
     let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
-    match steam::get_price(app.app_id, &client).await {
+    match steam::get_price(app.app_id, &settings::get_region(), client).await {
         Ok(po) => {
             let mut unique : bool = true;
+            let mut linked : bool = false;
             for elem in thresholds.iter() {
                 if is_threshold(&app.name, elem) {
                     unique = false;
                     if elem.steam_id == 0 {
+                        linked = true;
                         update_id(&elem.title, settings::STEAM_STORE_ID, app.app_id);
                     }
                     break;
@@ -47,31 +61,77 @@ pub async fn add_steam_game(new_alias: String, app: App, price: f64, client: &re
                 thresholds.push(GameThreshold {
                     title: app.name.clone(),
                     alias: new_alias,
-                    steam_id: app.app_id.clone(),
+                    steam_id: app.app_id,
                     gog_id: 0,
                     microsoft_store_id: String::new(),
-                    currency: po.currency[1..po.currency.len()-1].to_string(),
-                    desired_price: price
+                    desired_price: Money::from_major(price, &po.currency),
+                    ..Default::default()
                 });
                 let data_str = serde_json::to_string_pretty(&thresholds).unwrap();
                 json::write_to_file(get_path(), data_str);
                 println!("Successfully added Steam game: \"{}\".", app.name);
             }
             //else { println!("Duplicate title: \"{}\".", app.name); }
+            unique || linked
         },
-        Err(e) => println!("{}", e)
+        Err(e) => {
+            println!("{}", e);
+            false
+        }
     }
 }
 
-pub fn add_gog_game(new_alias: String, game: &GOGGameInfo, price: f64){
+// Adds a Steam package or bundle (apps are added with add_steam_game)
+pub async fn add_steam_item(new_alias: String, title: &str, item_type: SteamItemType, id: usize, price: f64, client: &reqwest::Client) -> bool {
+    let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
+    match steam::get_price_details_by_type(id, item_type, &settings::get_region(), client).await {
+        Ok(info) => {
+            let mut unique : bool = true;
+            let mut linked : bool = false;
+            for elem in thresholds.iter() {
+                if is_threshold(title, elem) {
+                    unique = false;
+                    if elem.steam_id == 0 {
+                        linked = true;
+                        update_id_str(&elem.title, settings::STEAM_STORE_ID, &steam::format_item_id(item_type, id));
+                    }
+                    break;
+                }
+            }
+            if unique {
+                thresholds.push(GameThreshold {
+                    title: title.to_string(),
+                    alias: new_alias,
+                    steam_id: id,
+                    steam_type: item_type,
+                    desired_price: Money::from_major(price, info.currency()),
+                    ..Default::default()
+                });
+                let data_str = serde_json::to_string_pretty(&thresholds).unwrap();
+                json::write_to_file(get_path(), data_str);
+                println!("Successfully added Steam {}: \"{}\".", if item_type == SteamItemType::Bundle { "bundle" } else { "package" }, title);
+            }
+            unique || linked
+        },
+        Err(e) => {
+            println!("{}", e);
+            false
+        }
+    }
+}
+
+pub fn add_gog_game(new_alias: String, game: &GOGGameInfo, price: f64, cheapest_edition: bool) -> bool {
     let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
     let mut unique : bool = true;
+    let mut linked : bool = false;
     for elem in thresholds.iter(){
         if is_threshold(&game.title, elem){
             unique = false;
             if elem.gog_id == 0 {
+                linked = true;
                 let game_id = game.id.parse::<u64>().unwrap();
                 update_id(&elem.title, settings::GOG_STORE_ID, game_id as usize);
+                if cheapest_edition { update_gog_cheapest_edition(&elem.title, true); }
             }
             break;
         }
@@ -79,57 +139,133 @@ pub fn add_gog_game(new_alias: String, game: &GOGGameInfo, price: f64){
     if unique { 
         let currency_code = match &game.price {
             Some(price_data) => price_data.base_money.currency.clone(),
-            None => "USD".to_string(),
+            None => settings::get_region().currency,
         };
         thresholds.push(GameThreshold {
             title: game.title.clone(),
             alias: new_alias,
             steam_id: 0,
             gog_id: game.id.parse::<u64>().unwrap() as usize,
+            gog_cheapest_edition: cheapest_edition,
             microsoft_store_id: String::new(),
             //currency: game.price.currency.clone(), // Version 1
-            currency: currency_code,
-            desired_price: price
+            desired_price: Money::from_major(price, &currency_code),
+            ..Default::default()
         });
         let data_str = serde_json::to_string_pretty(&thresholds).unwrap();
         json::write_to_file(get_path(), data_str);
         println!("Successfully added GOG game \"{}\".", game.title);
     }
     //else { println!("Duplicate title: \"{}\".", game.title); }
+    unique || linked
 }
 
-pub fn add_microsoft_store_game(new_alias: String, game: &ProductInfo, price: f64){
+// Tracks one SKU of the product if given (saved as "<product ID>:<SKU ID>" under the SKU's title)
+// Products are games or add-ons (e.g. DLC) added by their product ID
+pub fn add_microsoft_store_game(new_alias: String, product_id: &str, game_title: &str, sku: Option<&SKU>, price: f64) -> bool {
     let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
     let mut unique : bool = true;
+    let mut linked : bool = false;
+    let title = sku.map_or(game_title.to_string(), |sku| microsoft_store::get_sku_game_title(game_title, sku));
+    let game_id = microsoft_store::format_microsoft_store_id(product_id, sku.map(|sku| sku.sku_id.as_str()));
     for elem in thresholds.iter(){
-        if is_threshold(&game.title, elem){
+        if is_threshold(&title, elem){
             unique = false;
             if elem.microsoft_store_id.is_empty() {
-                let game_id = &game.product_id;
-                update_id_str(&elem.title, settings::MICROSOFT_STORE_ID, game_id);
+                linked = true;
+                update_id_str(&elem.title, settings::MICROSOFT_STORE_ID, &game_id);
             }
             break;
         }
     }
     if unique {
         thresholds.push(GameThreshold{
-            title: game.title.clone(),
+            title: title.clone(),
             alias: new_alias,
             steam_id: 0,
             gog_id: 0,
-            microsoft_store_id: game.product_id.clone(),
-            currency: String::new(),
-            desired_price: price
+            microsoft_store_id: game_id,
+            desired_price: Money::from_major(price, &settings::get_region().currency),
+            ..Default::default()
         });
         let data_str = serde_json::to_string_pretty(&thresholds).unwrap();
         json::write_to_file(get_path(), data_str);
-        println!("Successfully added Microsoft Store game \"{}\".", game.title);
+        println!("Successfully added Microsoft Store game \"{}\".", title);
     }
+    unique || linked
 }
 
+pub fn add_humble_bundle_game(new_alias: String, game: &HumbleBundleProductInfo, price: f64) -> bool {
+    let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
+    let mut unique : bool = true;
+    let mut linked : bool = false;
+    for elem in thresholds.iter(){
+        if is_threshold(&game.title, elem){
+            unique = false;
+            if elem.humble_bundle_id.is_empty() {
+                linked = true;
+                update_id_str(&elem.title, settings::HUMBLE_BUNDLE_STORE_ID, &game.machine_name);
+            }
+            break;
+        }
+    }
+    if unique {
+        let currency_code = match &game.full_price {
+            Some(price_data) => price_data.currency.clone(),
+            None => settings::get_region().currency,
+        };
+        thresholds.push(GameThreshold{
+            title: game.title.clone(),
+            alias: new_alias,
+            humble_bundle_id: game.machine_name.clone(),
+            desired_price: Money::from_major(price, &currency_code),
+            ..Default::default()
+        });
+        let data_str = serde_json::to_string_pretty(&thresholds).unwrap();
+        json::write_to_file(get_path(), data_str);
+        println!("Successfully added Humble Bundle game \"{}\".", game.title);
+    }
+    unique || linked
+}
+
+pub fn add_epic_game(new_alias: String, game: &EpicOffer, price: f64) -> bool {
+    let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
+    let mut unique : bool = true;
+    let mut linked : bool = false;
+    let game_id = format!("{}:{}", game.namespace, game.id);
+    for elem in thresholds.iter(){
+        if is_threshold(&game.title, elem){
+            unique = false;
+            if elem.epic_id.is_empty() {
+                linked = true;
+                update_id_str(&elem.title, settings::EPIC_STORE_ID, &game_id);
+            }
+            break;
+        }
+    }
+    if unique {
+        let currency_code = match &game.price {
+            Some(price_data) => price_data.total_price.currency_code.clone(),
+            None => settings::get_region().currency,
+        };
+        thresholds.push(GameThreshold{
+            title: game.title.clone(),
+            alias: new_alias,
+            epic_id: game_id,
+            desired_price: Money::from_major(price, &currency_code),
+            ..Default::default()
+        });
+        let data_str = serde_json::to_string_pretty(&thresholds).unwrap();
+        json::write_to_file(get_path(), data_str);
+        println!("Successfully added Epic Games Store game \"{}\".", game.title);
+    }
+    unique || linked
+}
+
+// Never prompts when games are added non-interactively
 pub fn set_game_alias() -> String {
     let mut alias = "".to_string();
-    if settings::get_alias_state() {
+    if settings::get_alias_state() && storefront::is_interactive() {
         let mut input = String::new();
         print!("Do you want to assign an alias [Y\\N]? ");
         let _ = io::stdout().flush();
@@ -152,8 +288,7 @@ pub fn set_game_alias() -> String {
 pub fn update_alias(title: &str, new_alias: &str){
     let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
     let idx = thresholds.iter().position(|threshold| is_threshold(title, threshold));
-    if !idx.is_none() {
-        let i = idx.unwrap();
+    if let Some(i) = idx {
         thresholds[i].alias = new_alias.to_string();
         let data_str = serde_json::to_string_pretty(&thresholds).expect("Could not convert GOG id update to string.");
         json::write_to_file(get_path(), data_str);
@@ -166,10 +301,10 @@ pub fn update_alias(title: &str, new_alias: &str){
 pub fn update_price(title: &str, price: f64) {
     let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
     let idx = thresholds.iter().position(|threshold| is_threshold(title, threshold));
-    if !idx.is_none() {
-        let i = idx.unwrap();
-        if price != thresholds[i].desired_price{
-            let old_threshold = thresholds[i].desired_price.clone();
+    if let Some(i) = idx {
+        let price = Money::from_major(price, &get_currency(&thresholds[i]));
+        if price != get_desired_price(&thresholds[i]) {
+            let old_threshold = get_desired_price(&thresholds[i]);
             thresholds[i].desired_price = price;
             let data_str = serde_json::to_string_pretty(&thresholds).expect("Could not price update to string.");
             json::write_to_file(get_path(), data_str);
@@ -186,65 +321,184 @@ pub fn update_price(title: &str, price: f64) {
     }
 }
 
-pub fn update_id(title: &str, store_type: &str, id: usize){
+pub fn update_rules(title: &str, rules: AlertRules) {
     let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
     let idx = thresholds.iter().position(|threshold| is_threshold(title, threshold));
-    //println!("{:?}", idx);
-    if !idx.is_none() {
-        let mut updated_id : bool = false;
-        let i = idx.unwrap();
-        let mut store_name = String::new();
-        match store_type{
-            settings::STEAM_STORE_ID => {
-                store_name = settings::get_proper_store_name(settings::STEAM_STORE_ID).unwrap();
-                thresholds[i].steam_id = id;
-                updated_id = true;
-            },
-            settings::GOG_STORE_ID => {
-                store_name = settings::get_proper_store_name(settings::GOG_STORE_ID).unwrap();
-                thresholds[i].gog_id = id;
-                updated_id = true;
-            },
-            _ => eprintln!("Unknown store type: {}", store_type),
+    if let Some(i) = idx {
+        thresholds[i].rules = rules;
+        let data_str = serde_json::to_string_pretty(&thresholds).expect("Could not convert rules update to string.");
+        json::write_to_file(get_path(), data_str);
+        println!("\"{}\": updated alert rules ({})", thresholds[i].title, get_rules_str(&thresholds[i]));
+    }
+    else{
+        println!("\"{}\" does not have a configured threshold.", title);
+    }
+}
+
+pub fn update_region(title: &str, country_code: &str) {
+    let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
+    let idx = thresholds.iter().position(|threshold| is_threshold(title, threshold));
+    if let Some(i) = idx {
+        if country_code.is_empty() { thresholds[i].region = String::new(); }
+        else {
+            match settings::get_region_info(country_code) {
+                Some(region) => thresholds[i].region = region.country_code,
+                None => {
+                    eprintln!("Unsupported region: \"{}\". Supported regions: {}", country_code, settings::get_available_regions().join(", "));
+                    return;
+                }
+            }
         }
-        if updated_id {
-            let update_err = format!("Could not convert the {} id update to a string object.", store_type);
-            let data_str = serde_json::to_string_pretty(&thresholds).expect(&update_err);
-            json::write_to_file(get_path(), data_str);
-            println!("Updated {} ID for \"{}\"", store_name, title);
+        let region = settings::get_threshold_region(&thresholds[i]);
+        if thresholds[i].desired_price.currency != region.currency {
+            // The amount is kept and read in the new currency
+            let desired_price = get_desired_price(&thresholds[i]);
+            println!("\"{}\": price threshold {} is now in {} (was {})", thresholds[i].title, desired_price,
+                                                                    region.currency, desired_price.currency);
+            thresholds[i].desired_price = Money::from_major(desired_price.to_major(), &region.currency);
         }
+        let data_str = serde_json::to_string_pretty(&thresholds).expect("Could not convert region update to string.");
+        json::write_to_file(get_path(), data_str);
+        println!("\"{}\": prices will be checked in region {} ({})", thresholds[i].title, region.country_code, region.currency);
+    }
+    else{
+        println!("\"{}\" does not have a configured threshold.", title);
+    }
+}
+
+// Currency of the price threshold (older thresholds may not have one saved)
+pub fn get_currency(threshold: &GameThreshold) -> String {
+    if threshold.desired_price.currency.is_empty() { settings::get_threshold_region(threshold).currency }
+    else { threshold.desired_price.currency.clone() }
+}
+
+// Price threshold in its currency (thresholds saved without a currency are read in the region currency)
+pub fn get_desired_price(threshold: &GameThreshold) -> Money {
+    if threshold.desired_price.currency.is_empty() { Money::from_major(threshold.desired_price.to_major(), &get_currency(threshold)) }
+    else { threshold.desired_price.clone() }
+}
+
+pub fn is_ending_soon(info: &SaleInfo, now: DateTime<Utc>) -> bool {
+    info.sale_ends_at.is_some_and(|ends_at| ends_at > now && ends_at - now <= Duration::hours(ENDING_SOON_HOURS))
+}
+
+// Time left in the sale (e.g. "ends in 1d 4h"), empty if the end is unknown or has passed
+pub fn get_countdown_str(info: &SaleInfo, now: DateTime<Utc>) -> String {
+    let Some(ends_at) = info.sale_ends_at.filter(|ends_at| *ends_at > now) else { return String::new() };
+    let remaining = ends_at - now;
+    let (days, hours, minutes) = (remaining.num_days(), remaining.num_hours() % 24, remaining.num_minutes() % 60);
+    if days > 0 { format!("ends in {}d {}h", days, hours) }
+    else if hours > 0 { format!("ends in {}h {}m", hours, minutes) }
+    else { format!("ends in {}m", minutes.max(1)) }
+}
+
+// Sales ending soon are moved to the top (earliest end first), the rest keep their order
+pub fn sort_by_sale_end<T, F: Fn(&T) -> &SaleInfo>(items: &mut [T], now: DateTime<Utc>, get_info: F) {
+    items.sort_by_key(|item| {
+        let info = get_info(item);
+        if is_ending_soon(info, now) { (0, info.sale_ends_at) } else { (1, None) }
+    });
+}
+
+pub fn is_same_currency(threshold: &GameThreshold, info: &SaleInfo) -> bool {
+    info.currency().is_empty() || info.currency() == get_currency(threshold)
+}
+
+// Checks the sale against every enabled rule (history should not include the current sale)
+pub fn is_alert(threshold: &GameThreshold, info: &SaleInfo, price_history: Option<&PriceHistory>) -> bool {
+    let rules = &threshold.rules;
+    if !is_same_currency(threshold, info) { return false; }
+    // Only compare against prices recorded in the same currency
+    let same_currency = price_history.map(|game| history::get_records_in_currency(game, info.currency()));
+    let price_history = same_currency.as_ref();
+    let mut results : Vec<bool> = Vec::new();
+    if rules.use_desired_price {
+        // Compared in minor units (prices without a currency are in the threshold currency)
+        results.push(info.current_price.amount <= get_desired_price(threshold).amount);
+    }
+    if rules.all_time_low {
+        let all_time_low = price_history.and_then(history::get_all_time_low);
+        results.push(all_time_low.is_some_and(|low| info.current_price.amount <= low.current_price.amount));
+    }
+    if let Some(min_discount) = rules.min_discount {
+        results.push(info.discount_percentage >= min_discount);
+    }
+    if let Some(percentile) = rules.bottom_percentile {
+        let rank = price_history.and_then(|game| history::get_percentile_rank(game, &info.current_price));
+        results.push(rank.is_some_and(|r| r < percentile));
     }
+    if rules.ending_soon {
+        // Only alert once per sale (an earlier check already saw the same sale ending soon)
+        let was_ending_soon = info.sale_ends_at.is_some_and(|ends_at| price_history.is_some_and(|game| {
+            game.records.iter().any(|record| record.sale_ends_at == Some(ends_at) && history::is_ending_soon_record(record))
+        }));
+        results.push(info.discount_percentage > 0.0 && is_ending_soon(info, Utc::now()) && !was_ending_soon);
+    }
+    if rules.game_pass {
+        // Only the Microsoft Store reports Game Pass so compare against its last recorded check
+        let was_included = price_history
+            .and_then(|game| game.records.iter().rev().find(|record| record.store_id == settings::MICROSOFT_STORE_ID))
+            .is_some_and(|record| record.game_pass);
+        results.push(info.subscription.is_included() && !was_included);
+    }
+    if results.is_empty() { return false; }
+    if rules.match_all { results.iter().all(|passed| *passed) }
+    else { results.iter().any(|passed| *passed) }
+}
+
+pub fn get_rules_str(threshold: &GameThreshold) -> String {
+    let rules = &threshold.rules;
+    let mut rule_list : Vec<String> = Vec::new();
+    if rules.use_desired_price { rule_list.push(format!("price <= {}", get_desired_price(threshold))); }
+    if rules.all_time_low { rule_list.push(String::from("all-time low")); }
+    if let Some(min_discount) = rules.min_discount { rule_list.push(format!("discount >= {}%", min_discount)); }
+    if let Some(percentile) = rules.bottom_percentile { rule_list.push(format!("bottom {}%", percentile)); }
+    if rules.game_pass { rule_list.push(String::from("added to Game Pass")); }
+    if rules.ending_soon { rule_list.push(format!("sale ends within {}h", ENDING_SOON_HOURS)); }
+    if rule_list.is_empty() { return String::from("no rules"); }
+    let separator = if rules.match_all { " and " } else { " or " };
+    rule_list.join(separator)
+}
+
+pub fn update_id(title: &str, store_type: &str, id: usize){
+    update_id_str(title, store_type, &id.to_string());
 }
 
 pub fn update_id_str(title: &str, store_type: &str, id: &str){
     let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
     let idx = thresholds.iter().position(|threshold| is_threshold(title, threshold));
-    if !idx.is_none() {
-        let mut updated_id : bool = false;
-        let i = idx.unwrap();
-        let mut store_name = String::new();
-        match store_type {
-            settings::MICROSOFT_STORE_ID => {
-                store_name = settings::get_proper_store_name(settings::MICROSOFT_STORE_ID).unwrap();
-                thresholds[i].microsoft_store_id = id.to_string();
-                updated_id = true;
-            }
-            _ => eprintln!("Unknown store type: {}", store_type),
+    if let Some(i) = idx {
+        match storefront::get_storefront(store_type) {
+            Some(store) => {
+                if store.set_threshold_id(&mut thresholds[i], id) {
+                    let update_err = format!("Could not convert the {} id update to a string object.", store_type);
+                    let data_str = serde_json::to_string_pretty(&thresholds).expect(&update_err);
+                    json::write_to_file(get_path(), data_str);
+                    println!("Updated {} ID for \"{}\"", store.name(), title);
+                }
+                else { eprintln!("Invalid {} ID: {}", store.name(), id); }
+            },
+            None => eprintln!("Unknown store type: {}", store_type),
         }
-        if updated_id {
-            let update_err = format!("Could not convert the {} id update to a string object.", store_type);
-            let data_str = serde_json::to_string_pretty(&thresholds).expect(&update_err);
-            json::write_to_file(get_path(), data_str);
-            println!("Updated {} ID for \"{}\"", store_name, title);
-        }  
+    }
+}
+
+pub fn update_gog_cheapest_edition(title: &str, cheapest_edition: bool){
+    let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
+    let idx = thresholds.iter().position(|threshold| is_threshold(title, threshold));
+    if let Some(i) = idx {
+        thresholds[i].gog_cheapest_edition = cheapest_edition;
+        let data_str = serde_json::to_string_pretty(&thresholds).unwrap();
+        json::write_to_file(get_path(), data_str);
+        println!("Updated GOG edition tracking for \"{}\"", title);
     }
 }
 
 pub fn remove(title: &str){
     let mut thresholds = load_data().unwrap_or_else(|_e|Vec::new());
     let idx = thresholds.iter().position(|threshold| is_threshold(title, threshold));
-    if !idx.is_none(){
-        thresholds.remove(idx.unwrap());
+    if let Some(i) = idx {
+        thresholds.remove(i);
         let data_str = serde_json::to_string_pretty(&thresholds).unwrap();
         json::write_to_file(get_path(), data_str);
         println!("Successfully removed \"{}\".", title);
@@ -257,9 +511,11 @@ pub fn list_games() {
         Ok(data) => {
             println!("Price Thresholds");
             for threshold in data.iter() {
-                println!("  - {} => {} ({})", threshold.title, 
-                                              threshold.desired_price, 
-                                              threshold.currency);
+                let region = if threshold.region.is_empty() { String::new() } else { format!(", {}", threshold.region) };
+                println!("  - {} => {} ({}{}) [{}]", threshold.title, 
+                                                     get_desired_price(threshold), 
+                                                     get_currency(threshold), region,
+                                                     get_rules_str(threshold));
             }
         },
         Err(e) => println!("Error: {}", e)
@@ -2,17 +2,31 @@ pub mod alerting {
     pub mod email; 
 }
 
+pub mod error;
+pub use error::{StoreError, StoreResult};
+
+pub mod title_match;
+
 pub mod stores {
+    pub mod http;
+    pub mod rate_limit;
+    pub mod storefront;
     pub mod steam;
     pub mod gog;
     pub mod microsoft_store;
+    pub mod humble_bundle;
+    pub mod epic;
+    pub mod compare;
 }
 
 pub mod file_ops {
     pub mod csv;
+    #[cfg_attr(test, allow(dead_code))]
     pub mod json;
     pub mod settings;
     pub mod thresholds;
+    pub mod history;
+    pub mod steam_cache;
 }
 
 pub mod structs {
@@ -20,25 +34,38 @@ pub mod structs {
     pub mod steam_response;
     pub mod gog_response;
     pub mod microsoft_store_response;
+    pub mod humble_bundle_response;
+    pub mod epic_response;
 }
 
+pub use alerting::email;
+pub use stores::{storefront, steam, gog, microsoft_store, humble_bundle, epic, http, rate_limit, compare};
+pub use file_ops::{csv, json, settings, thresholds, history, steam_cache};
+pub use structs::{data, steam_response, gog_response, microsoft_store_response, humble_bundle_response, epic_response};
+
 #[cfg(test)]
 pub mod tests {
     pub mod unit{
         pub mod settings_ops;
         pub mod threshold_ops;
+        pub mod storefront_ops;
+        pub mod history_ops;
+        pub mod rate_limit_ops;
+        pub mod http_ops;
+        pub mod steam_cache_ops;
+        pub mod title_match_ops;
+        pub mod compare_ops;
+        pub mod microsoft_store_ops;
     }
     pub mod api{
         pub mod steam_pc;
         pub mod gog_pc;
         pub mod microsoft_store_pc;
+        pub mod humble_bundle_pc;
+        pub mod epic_pc;
     }
     pub mod functional{
         pub mod commands;
     }
+    pub mod mock_server;
 }
-
-pub use alerting::email;
-pub use stores::{steam, gog, microsoft_store};
-pub use file_ops::{csv, json, settings, thresholds};
-pub use structs::{data, steam_response, gog_response, microsoft_store_response};
\ No newline at end of file
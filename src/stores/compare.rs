@@ -0,0 +1,95 @@
+use crate::file_ops::thresholds;
+use crate::stores::storefront::{self, Storefront};
+use crate::structs::data::{BestDeal, GameThreshold, Money, PriceComparison, SaleInfo, StorePrice};
+
+pub fn to_store_price(store: &dyn Storefront, info: &SaleInfo) -> StorePrice {
+    StorePrice {
+        store_id: store.id().to_string(),
+        store_name: store.name().to_string(),
+        original_price: info.original_price.clone(),
+        current_price: info.current_price.clone(),
+        discount_percentage: info.discount_percentage,
+    }
+}
+
+fn is_comparable(comparison: &PriceComparison, price: &StorePrice) -> bool {
+    let currency = &price.current_price.currency;
+    currency.is_empty() || *currency == comparison.currency
+}
+
+// Lowest price in the threshold currency (the first store wins a tie). Comparable prices are compared in minor units
+pub fn get_cheapest(comparison: &PriceComparison) -> Option<&StorePrice> {
+    comparison.prices.iter()
+        .filter(|price| is_comparable(comparison, price))
+        .fold(None, |cheapest: Option<&StorePrice>, price| match cheapest {
+            Some(cheapest) if cheapest.current_price.amount <= price.current_price.amount => Some(cheapest),
+            _ => Some(price),
+        })
+}
+
+// How much more the store costs than the cheapest store (None if the price is in another currency)
+pub fn get_savings(comparison: &PriceComparison, price: &StorePrice) -> Option<Money> {
+    if !is_comparable(comparison, price) { return None; }
+    get_cheapest(comparison).map(|cheapest| Money::new(price.current_price.amount - cheapest.current_price.amount, &comparison.currency))
+}
+
+// Largest saving from buying at the cheapest store
+pub fn get_max_savings(comparison: &PriceComparison) -> Money {
+    comparison.prices.iter()
+        .filter_map(|price| get_savings(comparison, price))
+        .fold(Money::new(0, &comparison.currency), |max, savings| if savings.amount > max.amount { savings } else { max })
+}
+
+pub fn is_discounted(comparison: &PriceComparison) -> bool {
+    comparison.prices.iter().any(|price| price.discount_percentage > 0.0)
+}
+
+// Keeps the cheapest alert of each game (the first store wins a tie) and lists the other stores' prices with it.
+// Alerts and prices are (threshold index, store name, ...) and the deals are returned in threshold order
+pub fn get_best_deals(thresholds: &[GameThreshold], alerts: Vec<(usize, String, SaleInfo)>,
+                      prices: &[(usize, StorePrice)]) -> Vec<BestDeal> {
+    let mut best : Vec<Option<(String, SaleInfo)>> = thresholds.iter().map(|_| None).collect();
+    for (pos, store_name, info) in alerts {
+        if pos >= best.len() { continue; }
+        // Prices in another currency than the current best deal never replace it
+        let is_cheaper = match &best[pos] {
+            Some((_, cheapest)) => info.current_price < cheapest.current_price,
+            None => true,
+        };
+        if is_cheaper { best[pos] = Some((store_name, info)); }
+    }
+    best.into_iter().enumerate()
+        .filter_map(|(pos, deal)| deal.map(|(store_name, sale)| BestDeal {
+            other_prices: prices.iter()
+                .filter(|(other, price)| *other == pos && price.store_name != store_name)
+                .map(|(_, price)| price.clone())
+                .collect(),
+            store_name,
+            sale,
+        }))
+        .collect()
+}
+
+// Fetches every store each threshold is tracked on and groups the prices per game (in threshold order)
+pub async fn compare_prices(thresholds: &[GameThreshold], client: &reqwest::Client) -> Vec<PriceComparison> {
+    let storefronts = storefront::get_storefronts();
+    let mut comparisons : Vec<PriceComparison> = thresholds.iter()
+        .map(|elem| PriceComparison {
+            title: elem.title.clone(),
+            currency: thresholds::get_currency(elem),
+            ..Default::default()
+        })
+        .collect();
+    let mut results = storefront::get_price_details_all(&storefronts, thresholds, client).await;
+    // Keep the store order for each game
+    results.sort_by_key(|(idx, _, _)| *idx);
+    for (idx, pos, result) in results {
+        let store = storefronts[idx].as_ref();
+        let comparison = &mut comparisons[pos];
+        match result.map(|info| to_store_price(store, &info)) {
+            Ok(price) => comparison.prices.push(price),
+            Err(e) => comparison.failures.push(format!("{}: {}", store.name(), e)),
+        }
+    }
+    comparisons
+}
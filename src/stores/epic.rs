@@ -0,0 +1,261 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+
+use crate::error::{StoreError, StoreResult};
+use crate::file_ops::{settings, thresholds};
+use crate::stores::http;
+use crate::stores::storefront::{self, Storefront};
+use crate::structs::data::{DealKind, GameThreshold, InsertOutcome, Money, Region, SaleInfo, SearchResult, SubscriptionInfo};
+use crate::structs::epic_response::{Offer, OfferPrice, TotalPrice};
+use crate::title_match;
+
+static BASE_URL : &str = "https://graphql.epicgames.com";
+static GRAPHQL_ENDPOINT : &str = "/graphql";
+static PROMOTIONS_BASE_URL : &str = "https://store-site-backend-static-ipv4.ak.epicgames.com";
+static PROMOTIONS_ENDPOINT : &str = "/freeGamesPromotions";
+static STORE_PAGE_URL : &str = "https://store.epicgames.com";
+static SEARCH_CATEGORY : &str = "games/edition/base";
+static SEARCH_COUNT : u32 = 20;
+// Image shown in the report (falls back to the first image of the offer)
+static ICON_IMAGE_TYPE : &str = "Thumbnail";
+
+static OFFER_FIELDS : &str = "title id namespace productSlug urlSlug keyImages { type url } \
+    price(country: $country) { totalPrice { discountPrice originalPrice discount currencyCode currencyInfo { decimals } } \
+    lineOffers { appliedRules { endDate } } }";
+
+fn get_search_query() -> String {
+    format!("query searchStoreQuery($keywords: String, $country: String!, $locale: String, $count: Int, $category: String) \
+        {{ Catalog {{ searchStore(keywords: $keywords, country: $country, locale: $locale, count: $count, category: $category) \
+        {{ elements {{ {} }} }} }} }}", OFFER_FIELDS)
+}
+
+fn get_offer_query() -> String {
+    format!("query catalogOfferQuery($namespace: String!, $id: String!, $country: String!, $locale: String) \
+        {{ Catalog {{ catalogOffer(namespace: $namespace, id: $id, locale: $locale) {{ {} }} }} }}", OFFER_FIELDS)
+}
+
+// The GraphQL API accepts GET requests with the query and variables as parameters
+async fn send_query(query: &str, variables: &Value, http_client: &reqwest::Client) -> StoreResult<Value> {
+    let variables = variables.to_string();
+    let query_string = [
+        ("query", query),
+        ("variables", variables.as_str()),
+    ];
+    let url = format!("{}{}", settings::get_base_url(settings::EPIC_URL_KEY, BASE_URL), GRAPHQL_ENDPOINT);
+    let resp = http::get_text(http_client, &url, &query_string).await?;
+    let body: Value = serde_json::from_str(&resp)?;
+    if let Some(message) = body["errors"][0]["message"].as_str() {
+        return Err(StoreError::Parse(format!("Epic Games Store query failed: {}", message)));
+    }
+    Ok(body["data"]["Catalog"].clone())
+}
+
+pub async fn search_game_by_title(title: &str, region: &Region, http_client: &reqwest::Client) -> StoreResult<Vec<Offer>> {
+    let variables = json!({
+        "keywords": title,
+        "country": region.country_code,
+        "locale": region.locale,
+        "count": SEARCH_COUNT,
+        "category": SEARCH_CATEGORY,
+    });
+    let catalog = send_query(&get_search_query(), &variables, http_client).await?;
+    let mut game_list = serde_json::from_value::<Vec<Offer>>(catalog["searchStore"]["elements"].clone())?;
+    title_match::rank_by_title(title, &mut game_list, |game| game.title.as_str());
+    Ok(game_list)
+}
+
+pub async fn get_offer(namespace: &str, id: &str, region: &Region, http_client: &reqwest::Client) -> StoreResult<Offer> {
+    let variables = json!({
+        "namespace": namespace,
+        "id": id,
+        "country": region.country_code,
+        "locale": region.locale,
+    });
+    let catalog = send_query(&get_offer_query(), &variables, http_client).await?;
+    if catalog["catalogOffer"].is_null() {
+        return Err(StoreError::NotFound(format!("{}:{} on the Epic Games Store", namespace, id)));
+    }
+    Ok(serde_json::from_value::<Offer>(catalog["catalogOffer"].clone())?)
+}
+
+// Epic IDs are saved as "namespace:offer_id"
+pub fn split_epic_id(epic_id: &str) -> Option<(&str, &str)> {
+    match epic_id.split_once(':') {
+        Some((namespace, id)) if !namespace.is_empty() && !id.is_empty() => Some((namespace, id)),
+        _ => None,
+    }
+}
+
+pub fn get_store_page_link(game: &Offer, region: &Region) -> String {
+    let page_slug = game.offer_mappings.as_ref()
+        .and_then(|mappings| mappings.first())
+        .map(|mapping| mapping.page_slug.as_str());
+    let slug = game.product_slug.as_deref()
+        .filter(|slug| !slug.is_empty())
+        .or(page_slug)
+        .or(game.url_slug.as_deref())
+        .unwrap_or_default();
+    let slug = slug.trim_end_matches("/home");
+    format!("{}/{}/p/{}", STORE_PAGE_URL, region.locale, slug)
+}
+
+fn get_icon_link(game: &Offer) -> String {
+    game.key_images.iter()
+        .find(|image| image.image_type == ICON_IMAGE_TYPE)
+        .or(game.key_images.first())
+        .map(|image| image.url.clone())
+        .unwrap_or_default()
+}
+
+fn to_money(price: i64, total_price: &TotalPrice) -> Money {
+    let amount = price as f64 / 10f64.powi(total_price.currency_info.decimals as i32);
+    Money::from_major(amount, &total_price.currency_code)
+}
+
+// Weekly free games are regular offers discounted to 0
+pub fn is_free_game(total_price: &TotalPrice) -> bool {
+    total_price.discount_price == 0 && total_price.original_price > 0
+}
+
+// Earliest end of the discounts applied to the price
+fn get_sale_end(price: &OfferPrice) -> Option<DateTime<Utc>> {
+    price.line_offers.iter()
+        .flat_map(|line_offer| line_offer.applied_rules.iter())
+        .filter_map(|rule| DateTime::parse_from_rfc3339(rule.end_date.as_deref()?).ok())
+        .map(|end_date| end_date.with_timezone(&Utc))
+        .min()
+}
+
+pub fn to_sale_info(game: &Offer, region: &Region) -> StoreResult<SaleInfo> {
+    let price = match &game.price {
+        Some(price) => price,
+        None => return Err(StoreError::NoPrice(format!("\"{}\" on the Epic Games Store", game.title))),
+    };
+    let total_price = &price.total_price;
+    let full = to_money(total_price.original_price, total_price);
+    let current = to_money(total_price.discount_price, total_price);
+    Ok(SaleInfo {
+        icon_link: get_icon_link(game),
+        title: game.title.clone(),
+        discount_percentage: storefront::get_sale_discount(&full, &current),
+        store_page_link: get_store_page_link(game, region),
+        deal: if is_free_game(total_price) { DealKind::FreeGame } else { DealKind::Sale },
+        subscription: SubscriptionInfo::default(),
+        sale_ends_at: if current < full { get_sale_end(price) } else { None },
+        original_price: full,
+        current_price: current,
+    })
+}
+
+// End of the free games promotion running at the given time (None if the game is not free right now)
+fn get_free_promotion_end(game: &Offer, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    game.promotions.as_ref()?.promotional_offers.iter()
+        .flat_map(|group| group.promotional_offers.iter())
+        .filter(|offer| offer.discount_setting.discount_percentage == 0)
+        .filter_map(|offer| {
+            let start_date = DateTime::parse_from_rfc3339(&offer.start_date).ok()?.with_timezone(&Utc);
+            let end_date = DateTime::parse_from_rfc3339(&offer.end_date).ok()?.with_timezone(&Utc);
+            if start_date <= now && now < end_date { Some(end_date) } else { None }
+        })
+        .min()
+}
+
+// Weekly free games (tracked or not) returned as ("namespace:offer_id", sale)
+pub async fn get_free_games(region: &Region, now: DateTime<Utc>, http_client: &reqwest::Client) -> StoreResult<Vec<(String, SaleInfo)>> {
+    let query_string = [
+        ("locale", region.locale.as_str()),
+        ("country", region.country_code.as_str()),
+        ("allowCountries", region.country_code.as_str()),
+    ];
+    let url = format!("{}{}", settings::get_base_url(settings::EPIC_PROMOTIONS_URL_KEY, PROMOTIONS_BASE_URL), PROMOTIONS_ENDPOINT);
+    let resp = http::get_text(http_client, &url, &query_string).await?;
+    let body: Value = serde_json::from_str(&resp)?;
+    let game_list = serde_json::from_value::<Vec<Offer>>(body["data"]["Catalog"]["searchStore"]["elements"].clone())?;
+    let free_games = game_list.iter()
+        .filter_map(|game| {
+            let end_date = get_free_promotion_end(game, now)?;
+            let mut info = to_sale_info(game, region).ok()?;
+            info.deal = DealKind::FreeGame;
+            info.sale_ends_at = Some(end_date);
+            Some((format!("{}:{}", game.namespace, game.id), info))
+        })
+        .collect();
+    Ok(free_games)
+}
+
+pub async fn get_price_details(epic_id: &str, region: &Region, http_client: &reqwest::Client) -> StoreResult<SaleInfo> {
+    let (namespace, id) = split_epic_id(epic_id)
+        .ok_or_else(|| StoreError::Parse(format!("Invalid Epic Games Store ID: {}", epic_id)))?;
+    let game = get_offer(namespace, id, region, http_client).await?;
+    to_sale_info(&game, region)
+}
+
+pub async fn insert_sequence(alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+    let search_list : Vec<Offer> = match search_game_by_title(title, &settings::get_region(), client).await {
+        Ok(data) => data,
+        Err(e) => {
+            println!("Search Epic Games Store Error: {}", e);
+            return InsertOutcome::Skipped(e.to_string());
+        }
+    };
+    if search_list.is_empty() {
+        println!("Could not find a game title matching \"{}\" on the Epic Games Store.", title);
+        return InsertOutcome::Skipped(String::from("No search results"));
+    }
+    let results : Vec<SearchResult> = search_list.iter().map(to_search_result).collect();
+    match storefront::select_search_result("Epic Games Store", title, &results) {
+        Ok(idx) => {
+            let game = &search_list[idx];
+            storefront::get_add_outcome(thresholds::add_epic_game(alias.to_string(), game, price), &game.title)
+        },
+        Err(outcome) => outcome,
+    }
+}
+
+fn to_search_result(game: &Offer) -> SearchResult {
+    let total_price = game.price.as_ref().map(|price| &price.total_price);
+    let base = total_price.map(|total_price| to_money(total_price.original_price, total_price));
+    let current = total_price.map(|total_price| to_money(total_price.discount_price, total_price));
+    SearchResult {
+        id: format!("{}:{}", game.namespace, game.id),
+        title: game.title.clone(),
+        discount_percentage: base.as_ref().zip(current.as_ref()).map(|(base, current)| storefront::get_sale_discount(base, current)),
+        price: base,
+        current_price: current,
+    }
+}
+
+// Storefront
+pub struct Epic;
+
+#[async_trait]
+impl Storefront for Epic {
+    fn id(&self) -> &'static str { settings::EPIC_STORE_ID }
+
+    fn name(&self) -> &'static str { settings::EPIC_STORE_NAME }
+
+    fn get_threshold_id(&self, threshold: &GameThreshold) -> Option<String> {
+        if threshold.epic_id.is_empty() { None } else { Some(threshold.epic_id.clone()) }
+    }
+
+    fn set_threshold_id(&self, threshold: &mut GameThreshold, id: &str) -> bool {
+        if split_epic_id(id).is_none() { return false; }
+        threshold.epic_id = id.to_string();
+        true
+    }
+
+    async fn search(&self, title: &str, client: &reqwest::Client) -> StoreResult<Vec<SearchResult>> {
+        let search_list = search_game_by_title(title, &settings::get_region(), client).await?;
+        Ok(search_list.iter().map(to_search_result).collect())
+    }
+
+    async fn get_price_details(&self, threshold: &GameThreshold, client: &reqwest::Client) -> StoreResult<SaleInfo> {
+        let region = settings::get_threshold_region(threshold);
+        get_price_details(&threshold.epic_id, &region, client).await
+    }
+
+    async fn insert_sequence(&self, alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+        insert_sequence(alias, title, price, client).await
+    }
+}
@@ -1,75 +1,71 @@
-use serde_json::{Result, Value, Error};
+use async_trait::async_trait;
+use serde_json::Value;
 use std::f64;
 
-use crate::structs::data::{SaleInfo};
+use crate::error::{StoreError, StoreResult};
+use crate::file_ops::{settings, thresholds};
+use crate::stores::http;
+use crate::stores::storefront::{self, Storefront};
+use crate::structs::data::{DealKind, GameThreshold, InsertOutcome, Money, Region, SaleInfo, SearchResult, SubscriptionInfo};
 use crate::structs::gog_response::{Game, PriceOverview, GameInfo};
+use crate::title_match;
 
 pub static VERSION: u32 = 2;
 
-pub async fn search_game_by_title(title: &str) -> Result<Vec<Game>> {
+static EMBED_BASE_URL : &str = "https://embed.gog.com";
+static EMBED_ENDPOINT : &str = "/games/ajax/filtered";
+
+async fn search_embed_products(title: &str, http_client: &reqwest::Client) -> StoreResult<Value> {
+    let query_string = [
+        ("mediaType", "game"),
+        ("search", title),
+        ("limit", "30"),
+    ];
+    let url = format!("{}{}", settings::get_base_url(settings::GOG_EMBED_URL_KEY, EMBED_BASE_URL), EMBED_ENDPOINT);
+    let resp = http::get_text(http_client, &url, &query_string).await?;
+    let body : Value = serde_json::from_str(&resp)?;
+    Ok(body["products"].clone())
+}
+
+pub async fn search_game_by_title(title: &str) -> StoreResult<Vec<Game>> {
     let http_client = reqwest::Client::new();
-    let media_type = "game";
-    let limit :i32 = 30;
-    let url = format!("https://embed.gog.com/games/ajax/filtered?mediaType={}&search={}&limit={}", media_type, title, limit);
-    //println!("{}", url);
-    let resp = http_client.get(url)
-        .send()
-        .await
-        .expect("Failed to get response")
-        .text()
-        .await
-        .expect("Failed to get data");
-    let body : Value = serde_json::from_str(&resp).expect("Could not convert to JSON");
-    //println!("{:?}", body);
-    let products = serde_json::to_string(&body["products"]).unwrap();
-    let games_list : Vec<Game> = serde_json::from_str::<Vec<Game>>(&products)?;
+    let products = search_embed_products(title, &http_client).await?;
+    let games_list : Vec<Game> = serde_json::from_value::<Vec<Game>>(products)?;
     Ok(games_list)
 }
 
-pub fn get_price_from_list(title:&str, games_list: Vec<Game>) -> Option<f64> {
+pub fn get_price_from_list(title:&str, games_list: Vec<Game>) -> StoreResult<f64> {
     for game in games_list.iter(){
-        if title == &game.title {
-            let game_price : f64 = game.price.final_amount.parse::<f64>().unwrap();
-            return Ok::<f64, Error>(game_price).ok();
+        if title == game.title {
+            return Money::parse(&game.price.final_amount, &game.price.currency)
+                .map(|price| price.to_major())
+                .ok_or_else(|| StoreError::Parse(format!("GOG price for \"{}\": {}", title, game.price.final_amount)));
         } 
     }
-    None
+    Err(StoreError::NotFound(format!("\"{}\" on GOG", title)))
 }
 
-pub async fn get_price_details(title: &str) -> Option<PriceOverview> {
+pub async fn get_price_details(title: &str) -> StoreResult<PriceOverview> {
     let http_client = reqwest::Client::new();
-    let media_type = "game";
-    let limit_num : i32 = 30;
-    let url = format!("https://embed.gog.com/games/ajax/filtered?mediaType={}&search={}&limit={}", media_type, title, limit_num);
-    //println!("{}", url);
-    let resp = http_client.get(url)
-        .send()
-        .await
-        .expect("Failed to get response")
-        .text()
-        .await
-        .expect("Failed to get data");
-    let body: Value = serde_json::from_str(&resp).expect("Could not convert to JSON");
-    //println!("{:?}", body);
-    if let Some(products) = body["products"].as_array() {
-        for idx in 0..products.len(){
-            let game_title = products[idx]["title"].to_string();
-            if title.to_string() == game_title[1..game_title.len()-1].to_string(){
-                let price = serde_json::to_string(&products[idx]["price"]).unwrap();
-                let price_overview = serde_json::from_str::<PriceOverview>(&price).unwrap();
-                return Ok::<PriceOverview, Error>(price_overview).ok();
+    let products = search_embed_products(title, &http_client).await?;
+    if let Some(products) = products.as_array() {
+        for product in products.iter(){
+            if product["title"].as_str() == Some(title) {
+                let price_overview = serde_json::from_value::<PriceOverview>(product["price"].clone())?;
+                return Ok(price_overview);
             }
         }
     }
-    None
+    Err(StoreError::NotFound(format!("\"{}\" on GOG", title)))
 }
 
 // Version 2
 
 static BASE_URL : &str = "https://catalog.gog.com";
 static CATALOG_ENDPOINT : &str = "/v1/catalog";
+static CHEAPEST_EDITION_OPTION : &str = "Cheapest edition";
 
-pub async fn search_game_by_title_v2(title: &str, http_client: &reqwest::Client) -> Result<Vec<GameInfo>>{
+pub async fn search_game_by_title_v2(title: &str, region: &Region, http_client: &reqwest::Client) -> StoreResult<Vec<GameInfo>>{
     let mut like_title = String::from("like:");
     like_title.push_str(title);
     let query_string = [
@@ -78,27 +74,20 @@ pub async fn search_game_by_title_v2(title: &str, http_client: &reqwest::Client)
         ("order", "desc:score"),
         ("productType", "in:game"),
         ("page", "1"),
-        ("countryCode", "US"),
-        ("locale", "en-US"),
-        ("currencyCode", "USD"),
+        ("countryCode", region.country_code.as_str()),
+        ("locale", region.locale.as_str()),
+        ("currencyCode", region.currency.as_str()),
     ];
-    let url = format!("{}{}", BASE_URL, CATALOG_ENDPOINT);
-    let resp = http_client.get(url)
-        .query(&query_string)
-        .send()
-        .await
-        .expect("Failed to get response")
-        .text()
-        .await
-        .expect("Failed to get data");
-    let body : Value = serde_json::from_str(&resp).expect("Could not convert  search to JSON");
+    let url = format!("{}{}", settings::get_base_url(settings::GOG_CATALOG_URL_KEY, BASE_URL), CATALOG_ENDPOINT);
+    let resp = http::get_text(http_client, &url, &query_string).await?;
+    let body : Value = serde_json::from_str(&resp)?;
     //println!("{:?}", body);
-    let products = serde_json::to_string(&body["products"]).unwrap();
-    let games_list : Vec<GameInfo> = serde_json::from_str::<Vec<GameInfo>>(&products)?;
+    let mut games_list : Vec<GameInfo> = serde_json::from_value::<Vec<GameInfo>>(body["products"].clone())?;
+    title_match::rank_by_title(title, &mut games_list, |game| game.title.as_str());
     Ok(games_list)
 }
 
-pub async fn get_price_details_v2(title: &str, http_client: &reqwest::Client) -> Option<SaleInfo> {
+pub async fn get_price_details_v2(title: &str, region: &Region, http_client: &reqwest::Client) -> StoreResult<SaleInfo> {
     let mut like_title = String::from("like:");
     like_title.push_str(title);
     let query_string = [
@@ -107,36 +96,219 @@ pub async fn get_price_details_v2(title: &str, http_client: &reqwest::Client) ->
         ("order", "desc:score"),
         ("productType", "in:game"),
         ("page", "1"),
-        ("countryCode", "US"),
-        ("locale", "en-US"),
-        ("currencyCode", "USD"),
+        ("countryCode", region.country_code.as_str()),
+        ("locale", region.locale.as_str()),
+        ("currencyCode", region.currency.as_str()),
     ];
-    let url = format!("{}{}", BASE_URL, CATALOG_ENDPOINT);
-    let resp = http_client.get(url)
-        .query(&query_string)
-        .send()
-        .await
-        .expect("Failed to get response")
-        .text()
-        .await
-        .expect("Failed to get data");
-    let body: Value = serde_json::from_str(&resp).expect("Could not convert to JSON");
-    if let Some(products) = body["products"].as_array() {
-        let first_product = serde_json::to_string(&products[0]).unwrap();
-        let data = serde_json::from_str::<GameInfo>(&first_product).unwrap();
-        match data.price {
-            Some(po) => {
-                return Ok::<SaleInfo, Error>(SaleInfo{
-                    title: data.title,
-                    original_price: po.base_money.amount,
-                    current_price: po.final_money.amount, 
-                    discount_percentage: po.final_money.discount[0..po.final_money.discount.len()-3].to_string(),
-                    icon_link: data.c_horizontal,
-                    store_page_link: data.store_link,
-                }).ok();
+    let url = format!("{}{}", settings::get_base_url(settings::GOG_CATALOG_URL_KEY, BASE_URL), CATALOG_ENDPOINT);
+    let resp = http::get_text(http_client, &url, &query_string).await?;
+    let body: Value = serde_json::from_str(&resp)?;
+    let first_product = match body["products"].as_array().and_then(|products| products.first()) {
+        Some(product) => product.clone(),
+        None => return Err(StoreError::NotFound(format!("\"{}\" on GOG", title))),
+    };
+    let data = serde_json::from_value::<GameInfo>(first_product)?;
+    to_sale_info(data)
+}
+
+// Catalog products are requested one ID at a time (the ID filter is not known to support several IDs)
+async fn get_product_by_id(game_id: usize, region: &Region, http_client: &reqwest::Client) -> StoreResult<GameInfo> {
+    let id_filter = format!("in:{}", game_id);
+    let query_string = [
+        ("productId", id_filter.as_str()),
+        ("limit", "1"),
+        ("countryCode", region.country_code.as_str()),
+        ("locale", region.locale.as_str()),
+        ("currencyCode", region.currency.as_str()),
+    ];
+    let url = format!("{}{}", settings::get_base_url(settings::GOG_CATALOG_URL_KEY, BASE_URL), CATALOG_ENDPOINT);
+    let resp = http::get_text(http_client, &url, &query_string).await?;
+    let body: Value = serde_json::from_str(&resp)?;
+    let products = serde_json::from_value::<Vec<GameInfo>>(body["products"].clone())?;
+    // Checked so an ignored filter never returns another product
+    match products.into_iter().find(|product| product.id == game_id.to_string()) {
+        Some(data) => Ok(data),
+        None => Err(StoreError::NotFound(format!("GOG product {} (no longer available on the store)", game_id))),
+    }
+}
+
+// Looks up the tracked product by its ID so similarly named games or editions are never matched
+pub async fn get_price_details_by_id(game_id: usize, region: &Region, http_client: &reqwest::Client) -> StoreResult<SaleInfo> {
+    let data = get_product_by_id(game_id, region, http_client).await?;
+    to_sale_info(data)
+}
+
+// Returns every edition of the game in the order listed by the store
+pub async fn get_editions(game: &GameInfo, region: &Region, http_client: &reqwest::Client) -> StoreResult<Vec<GameInfo>> {
+    let mut editions = Vec::new();
+    for edition in game.editions.iter() {
+        match get_product_by_id(edition.id as usize, region, http_client).await {
+            Ok(data) => editions.push(data),
+            // Editions that are no longer sold are left out
+            Err(StoreError::NotFound(_)) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(editions)
+}
+
+fn get_final_amount(game: &GameInfo) -> Option<Money> {
+    game.price.as_ref().and_then(|po| Money::parse(&po.final_money.amount, &po.final_money.currency))
+}
+
+// Price of the cheapest edition of the product (reported under the edition's title)
+pub async fn get_cheapest_edition_price(game_id: usize, region: &Region, http_client: &reqwest::Client) -> StoreResult<SaleInfo> {
+    let game = get_product_by_id(game_id, region, http_client).await?;
+    let title = game.title.clone();
+    let editions = if game.editions.len() > 1 { get_editions(&game, region, http_client).await? } else { vec![game] };
+    let cheapest = editions.into_iter()
+        .filter_map(|edition| get_final_amount(&edition).map(|amount| (amount, edition)))
+        .min_by_key(|(amount, _)| amount.amount);
+    match cheapest {
+        Some((_, edition)) => to_sale_info(edition),
+        None => Err(StoreError::NoPrice(format!("\"{}\" (any edition) on GOG", title))),
+    }
+}
+
+// The discount is computed from the base and final price (the "discount" of finalMoney is not always a percentage)
+fn to_sale_info(data: GameInfo) -> StoreResult<SaleInfo> {
+    let po = match data.price {
+        Some(po) => po,
+        None => return Err(StoreError::NoPrice(format!("\"{}\" on GOG", data.title))),
+    };
+    let original_price = Money::parse(&po.base_money.amount, &po.base_money.currency);
+    let current_price = Money::parse(&po.final_money.amount, &po.final_money.currency);
+    let (original_price, current_price) = match (original_price, current_price) {
+        (Some(original_price), Some(current_price)) => (original_price, current_price),
+        _ => return Err(StoreError::Parse(format!("GOG price for \"{}\": {} -> {}", data.title, po.base_money.amount, po.final_money.amount))),
+    };
+    Ok(SaleInfo{
+        title: data.title,
+        discount_percentage: storefront::get_sale_discount(&original_price, &current_price),
+        original_price,
+        current_price,
+        icon_link: data.c_horizontal,
+        store_page_link: data.store_link,
+        deal: DealKind::Sale,
+        subscription: SubscriptionInfo::default(),
+        sale_ends_at: None,
+    })
+}
+
+pub async fn insert_sequence(alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+    let search_list : Vec<GameInfo> = match search_game_by_title_v2(title, &settings::get_region(), client).await {
+        Ok(data) => data,
+        Err(e) => {
+            println!("Search GOG Game Error: {}", e);
+            return InsertOutcome::Skipped(e.to_string());
+        }
+    };
+    if search_list.is_empty() {
+        println!("Could not find a game title matching \"{}\" on GOG.", title);
+        return InsertOutcome::Skipped(String::from("No search results"));
+    }
+    let results : Vec<SearchResult> = search_list.iter().map(to_search_result).collect();
+    match storefront::select_search_result("GOG", title, &results) {
+        // Editions are only offered interactively (otherwise the matched product is tracked)
+        Ok(idx) if search_list[idx].editions.len() > 1 && storefront::is_interactive() => {
+            select_edition(alias, &search_list[idx], price, client).await
+        },
+        Ok(idx) => {
+            let game = &search_list[idx];
+            storefront::get_add_outcome(thresholds::add_gog_game(alias.to_string(), game, price, false), &game.title)
+        },
+        Err(outcome) => outcome,
+    }
+}
+
+async fn select_edition(alias: &str, game: &GameInfo, price: f64, client: &reqwest::Client) -> InsertOutcome {
+    let mut editions : Vec<GameInfo> = Vec::new();
+    match get_editions(game, &settings::get_region(), client).await {
+        Ok(data) => editions = data,
+        Err(e) => println!("Search GOG Editions Error: {}", e)
+    }
+    let mut results : Vec<SearchResult> = editions.iter().map(to_search_result).collect();
+    // Last option follows whichever edition is the cheapest when prices are checked
+    results.push(SearchResult { id: game.id.clone(), title: String::from(CHEAPEST_EDITION_OPTION), ..Default::default() });
+    match storefront::select_search_result("GOG editions", &game.title, &results) {
+        Ok(idx) if idx < editions.len() => {
+            let edition = &editions[idx];
+            storefront::get_add_outcome(thresholds::add_gog_game(alias.to_string(), edition, price, false), &edition.title)
+        },
+        Ok(_) => storefront::get_add_outcome(thresholds::add_gog_game(alias.to_string(), game, price, true), &game.title),
+        Err(outcome) => outcome,
+    }
+}
+
+fn to_search_result(game: &GameInfo) -> SearchResult {
+    let (base, current) = match &game.price {
+        Some(po) => (Money::parse(&po.base_money.amount, &po.base_money.currency), get_final_amount(game)),
+        None => (None, None),
+    };
+    SearchResult {
+        id: game.id.clone(),
+        title: game.title.clone(),
+        discount_percentage: base.as_ref().zip(current.as_ref()).map(|(base, current)| storefront::get_sale_discount(base, current)),
+        price: base,
+        current_price: current,
+    }
+}
+
+// Storefront
+pub struct Gog;
+
+#[async_trait]
+impl Storefront for Gog {
+    fn id(&self) -> &'static str { settings::GOG_STORE_ID }
+
+    fn name(&self) -> &'static str { settings::GOG_STORE_NAME }
+
+    fn get_threshold_id(&self, threshold: &GameThreshold) -> Option<String> {
+        if threshold.gog_id == 0 { None } else { Some(threshold.gog_id.to_string()) }
+    }
+
+    fn set_threshold_id(&self, threshold: &mut GameThreshold, id: &str) -> bool {
+        match id.parse::<usize>() {
+            Ok(game_id) => {
+                threshold.gog_id = game_id;
+                true
             },
-            None => (),
+            Err(_) => false
         }
     }
-    None 
+
+    async fn search(&self, title: &str, client: &reqwest::Client) -> StoreResult<Vec<SearchResult>> {
+        let search_list = search_game_by_title_v2(title, &settings::get_region(), client).await?;
+        Ok(search_list.iter().map(to_search_result).collect())
+    }
+
+    async fn get_price_details(&self, threshold: &GameThreshold, client: &reqwest::Client) -> StoreResult<SaleInfo> {
+        if VERSION == 1 {
+            let po = get_price_details(&threshold.title).await?;
+            let (original_price, current_price) = match (Money::parse(&po.base_amount, &po.currency), Money::parse(&po.final_amount, &po.currency)) {
+                (Some(original_price), Some(current_price)) => (original_price, current_price),
+                _ => return Err(StoreError::NoPrice(format!("\"{}\" on GOG", threshold.title))),
+            };
+            return Ok(SaleInfo {
+                icon_link: String::new(),
+                title: threshold.title.clone(),
+                original_price,
+                current_price,
+                discount_percentage: po.discount_percentage as f64,
+                store_page_link: String::new(),
+                deal: DealKind::Sale,
+                subscription: SubscriptionInfo::default(),
+                sale_ends_at: None,
+            });
+        }
+        let region = settings::get_threshold_region(threshold);
+        if threshold.gog_cheapest_edition {
+            return get_cheapest_edition_price(threshold.gog_id, &region, client).await;
+        }
+        get_price_details_by_id(threshold.gog_id, &region, client).await
+    }
+
+    async fn insert_sequence(&self, alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+        insert_sequence(alias, title, price, client).await
+    }
 }
\ No newline at end of file
@@ -0,0 +1,177 @@
+use cfg_if::cfg_if;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use reqwest::{StatusCode, Url};
+use reqwest::header::RETRY_AFTER;
+use serde::Serialize;
+use serde_json::Value;
+use std::env;
+use std::fs::{self, read_to_string};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::error::{StoreError, StoreResult};
+use crate::file_ops::json;
+use crate::structs::data::RecordedResponse;
+
+static TRANSPORT_VAR_NAME : &str = "HTTP_TRANSPORT";
+static FIXTURES_VAR_NAME : &str = "FIXTURES_PATH";
+static FIXTURES_DIRNAME : &str = "fixtures";
+// Query parameters that are never written to fixtures (e.g. API keys)
+static SECRET_PARAMS : [&str; 1] = ["key"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transport {
+    // Send requests to the stores
+    Live,
+    // Send requests to the stores and save each response as a fixture
+    Record,
+    // Return saved fixtures without sending any requests
+    Replay,
+}
+
+lazy_static! {
+    static ref TRANSPORT : Mutex<Transport> = {
+        let transport = match env::var(TRANSPORT_VAR_NAME).unwrap_or_default().to_lowercase().as_str() {
+            "live" => Some(Transport::Live),
+            "record" => Some(Transport::Record),
+            "replay" => Some(Transport::Replay),
+            _ => None,
+        };
+        cfg_if! {
+            if #[cfg(not(test))] { Mutex::new(transport.unwrap_or(Transport::Live)) }
+            else if #[cfg(test)] { Mutex::new(transport.unwrap_or(Transport::Replay)) }
+        }
+    };
+}
+
+pub fn get_transport() -> Transport {
+    *TRANSPORT.lock().unwrap()
+}
+
+pub fn set_transport(transport: Transport) {
+    *TRANSPORT.lock().unwrap() = transport;
+}
+
+pub fn get_fixtures_path() -> String {
+    if let Ok(path) = env::var(FIXTURES_VAR_NAME) { return path; }
+    cfg_if! {
+        if #[cfg(test)] {
+            let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), FIXTURES_DIRNAME].iter().collect();
+        }
+        else {
+            let path: PathBuf = [json::get_data_path(), FIXTURES_DIRNAME.to_string()].iter().collect();
+        }
+    }
+    path.display().to_string()
+}
+
+// Request URL without secret query parameters
+pub fn get_fixture_url(url: &Url) -> String {
+    let pairs : Vec<(String, String)> = url.query_pairs()
+        .filter(|(name, _)| !SECRET_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    let mut fixture_url = url.clone();
+    fixture_url.query_pairs_mut().clear().extend_pairs(pairs);
+    fixture_url.to_string()
+}
+
+// FNV-1a hash (stable across builds, unlike the std hasher)
+fn get_url_hash(url: &str) -> u64 {
+    let mut hash : u64 = 0xcbf29ce484222325;
+    for byte in url.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub fn get_fixture_path(fixture_url: &str) -> String {
+    let host = Url::parse(fixture_url).ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+        .unwrap_or_default();
+    let filename = format!("{}_{:016x}.json", host, get_url_hash(fixture_url));
+    let path: PathBuf = [get_fixtures_path(), filename].iter().collect();
+    path.display().to_string()
+}
+
+fn load_fixture(fixture_url: &str) -> StoreResult<RecordedResponse> {
+    let path = get_fixture_path(fixture_url);
+    if !Path::new(&path).is_file() {
+        return Err(StoreError::Network(format!("No recorded response for {} ({})", fixture_url, path)));
+    }
+    let data = read_to_string(&path)?;
+    Ok(serde_json::from_str::<RecordedResponse>(&data)?)
+}
+
+fn save_fixture(fixture_url: &str, status: StatusCode, body: &str, retry_after: Option<&str>) {
+    let fixtures_path = get_fixtures_path();
+    if !Path::new(&fixtures_path).is_dir() {
+        let _ = fs::create_dir_all(&fixtures_path);
+    }
+    let fixture = RecordedResponse {
+        url: fixture_url.to_string(),
+        status: status.as_u16(),
+        synthetic: false,
+        body: serde_json::from_str::<Value>(body).unwrap_or(Value::String(body.to_string())),
+        retry_after: retry_after.map(|value| value.to_string()),
+    };
+    match serde_json::to_string_pretty(&fixture) {
+        Ok(data_str) => json::write_to_file(get_fixture_path(fixture_url), data_str),
+        Err(e) => eprintln!("Could not record response for {}: {}", fixture_url, e),
+    }
+}
+
+// Retry-After is either a number of seconds or an HTTP date
+pub fn get_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let retry_at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((retry_at - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+}
+
+// Maps unsuccessful HTTP statuses to a StoreError
+fn check_status(status: StatusCode, url: &str, retry_after: Option<&str>) -> StoreResult<()> {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(StoreError::RateLimited(format!("{} returned {}", url, status), retry_after.and_then(get_retry_after)));
+    }
+    if status == StatusCode::NOT_FOUND {
+        return Err(StoreError::NotFound(format!("{} returned {}", url, status)));
+    }
+    if !status.is_success() {
+        return Err(StoreError::Network(format!("{} returned {}", url, status)));
+    }
+    Ok(())
+}
+
+// Sends a GET request (or replays a recorded one) and maps unsuccessful HTTP statuses to a StoreError
+pub async fn get_text<T: Serialize + ?Sized>(client: &reqwest::Client, url: &str, query: &T) -> StoreResult<String> {
+    let request = client.get(url)
+        .query(query)
+        .build()?;
+    let fixture_url = get_fixture_url(request.url());
+    let transport = get_transport();
+    if transport == Transport::Replay {
+        let fixture = load_fixture(&fixture_url)?;
+        let status = StatusCode::from_u16(fixture.status)
+            .map_err(|e| StoreError::Parse(format!("Recorded response for {}: {}", fixture_url, e)))?;
+        check_status(status, url, fixture.retry_after.as_deref())?;
+        return match fixture.body {
+            Value::String(body) => Ok(body),
+            body => Ok(body.to_string()),
+        };
+    }
+    let resp = client.execute(request).await?;
+    let status = resp.status();
+    let retry_after = resp.headers().get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+    let body = resp.text().await?;
+    if transport == Transport::Record { save_fixture(&fixture_url, status, &body, retry_after.as_deref()); }
+    check_status(status, url, retry_after.as_deref())?;
+    Ok(body)
+}
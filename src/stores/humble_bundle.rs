@@ -0,0 +1,150 @@
+use async_trait::async_trait;
+use chrono::DateTime;
+use serde_json::Value;
+
+use crate::error::{StoreError, StoreResult};
+use crate::file_ops::{settings, thresholds};
+use crate::stores::http;
+use crate::stores::storefront::{self, Storefront};
+use crate::structs::data::{DealKind, GameThreshold, InsertOutcome, Money, SaleInfo, SearchResult, SubscriptionInfo};
+use crate::structs::humble_bundle_response::ProductInfo;
+use crate::title_match;
+
+static BASE_URL : &str = "https://www.humblebundle.com";
+static SEARCH_ENDPOINT : &str = "/store/api/search";
+static LOOKUP_ENDPOINT : &str = "/store/api/lookup";
+static STORE_PAGE_ENDPOINT : &str = "/store";
+
+// Humble Bundle picks the currency using the location of the request (there is no region parameter)
+pub async fn search_game_by_title(title: &str, http_client: &reqwest::Client) -> StoreResult<Vec<ProductInfo>> {
+    let query_string = [
+        ("sort", "bestselling"),
+        ("filter", "all"),
+        ("search", title),
+        ("request", "1"),
+        ("page_size", "20"),
+    ];
+    let url = format!("{}{}", settings::get_base_url(settings::HUMBLE_BUNDLE_URL_KEY, BASE_URL), SEARCH_ENDPOINT);
+    let resp = http::get_text(http_client, &url, &query_string).await?;
+    let body: Value = serde_json::from_str(&resp)?;
+    //println!("{:?}", body["results"]);
+    let mut game_list = serde_json::from_value::<Vec<ProductInfo>>(body["results"].clone())?;
+    title_match::rank_by_title(title, &mut game_list, |game| game.title.as_str());
+    Ok(game_list)
+}
+
+// Returns the product with the given machine name (same product data as the search results)
+pub async fn get_product(machine_name: &str, http_client: &reqwest::Client) -> StoreResult<ProductInfo> {
+    let query_string = [
+        ("products[]", machine_name),
+        ("request", "1"),
+    ];
+    let url = format!("{}{}", settings::get_base_url(settings::HUMBLE_BUNDLE_URL_KEY, BASE_URL), LOOKUP_ENDPOINT);
+    let resp = http::get_text(http_client, &url, &query_string).await?;
+    let body: Value = serde_json::from_str(&resp)?;
+    let product_list = match body["result"].as_array() {
+        Some(_) => serde_json::from_value::<Vec<ProductInfo>>(body["result"].clone())?,
+        None => Vec::new(),
+    };
+    product_list.into_iter()
+        .find(|game| game.machine_name == machine_name)
+        .ok_or(StoreError::NotFound(format!("{} on Humble Bundle", machine_name)))
+}
+
+pub fn get_store_page_link(game: &ProductInfo) -> String {
+    format!("{}{}/{}", settings::get_base_url(settings::HUMBLE_BUNDLE_URL_KEY, BASE_URL), STORE_PAGE_ENDPOINT, game.human_url)
+}
+
+pub fn to_sale_info(game: &ProductInfo) -> StoreResult<SaleInfo> {
+    let (current, full) = match (&game.current_price, &game.full_price) {
+        (Some(current), Some(full)) => (current, full),
+        _ => return Err(StoreError::NoPrice(format!("\"{}\" on Humble Bundle", game.title))),
+    };
+    let full_price = Money::from_major(full.amount, &full.currency);
+    let current_price = Money::from_major(current.amount, &current.currency);
+    let discount = storefront::get_sale_discount(&full_price, &current_price);
+    Ok(SaleInfo {
+        icon_link: game.icon_link.clone(),
+        title: game.title.clone(),
+        original_price: full_price,
+        current_price,
+        discount_percentage: discount,
+        store_page_link: get_store_page_link(game),
+        deal: DealKind::Sale,
+        subscription: SubscriptionInfo::default(),
+        sale_ends_at: game.sale_end
+            .filter(|timestamp| *timestamp > 0.0 && discount > 0.0)
+            .and_then(|timestamp| DateTime::from_timestamp(timestamp as i64, 0)),
+    })
+}
+
+pub async fn get_price_details(machine_name: &str, http_client: &reqwest::Client) -> StoreResult<SaleInfo> {
+    let game = get_product(machine_name, http_client).await?;
+    to_sale_info(&game)
+}
+
+pub async fn insert_sequence(alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+    let search_list : Vec<ProductInfo> = match search_game_by_title(title, client).await {
+        Ok(data) => data,
+        Err(e) => {
+            println!("Search Humble Bundle Error: {}", e);
+            return InsertOutcome::Skipped(e.to_string());
+        }
+    };
+    if search_list.is_empty() {
+        println!("Could not find a game title matching \"{}\" on Humble Bundle.", title);
+        return InsertOutcome::Skipped(String::from("No search results"));
+    }
+    let results : Vec<SearchResult> = search_list.iter().map(to_search_result).collect();
+    match storefront::select_search_result("Humble Bundle", title, &results) {
+        Ok(idx) => {
+            let game = &search_list[idx];
+            storefront::get_add_outcome(thresholds::add_humble_bundle_game(alias.to_string(), game, price), &game.title)
+        },
+        Err(outcome) => outcome,
+    }
+}
+
+fn to_search_result(game: &ProductInfo) -> SearchResult {
+    let base = game.full_price.as_ref().map(|price| Money::from_major(price.amount, &price.currency));
+    let current = game.current_price.as_ref().map(|price| Money::from_major(price.amount, &price.currency));
+    SearchResult {
+        id: game.machine_name.clone(),
+        title: game.title.clone(),
+        discount_percentage: base.as_ref().zip(current.as_ref()).map(|(base, current)| storefront::get_sale_discount(base, current)),
+        price: base,
+        current_price: current,
+    }
+}
+
+// Storefront
+pub struct HumbleBundle;
+
+#[async_trait]
+impl Storefront for HumbleBundle {
+    fn id(&self) -> &'static str { settings::HUMBLE_BUNDLE_STORE_ID }
+
+    fn name(&self) -> &'static str { settings::HUMBLE_BUNDLE_STORE_NAME }
+
+    fn get_threshold_id(&self, threshold: &GameThreshold) -> Option<String> {
+        if threshold.humble_bundle_id.is_empty() { None } else { Some(threshold.humble_bundle_id.clone()) }
+    }
+
+    fn set_threshold_id(&self, threshold: &mut GameThreshold, id: &str) -> bool {
+        threshold.humble_bundle_id = id.to_string();
+        true
+    }
+
+    async fn search(&self, title: &str, client: &reqwest::Client) -> StoreResult<Vec<SearchResult>> {
+        let search_list = search_game_by_title(title, client).await?;
+        Ok(search_list.iter().map(to_search_result).collect())
+    }
+
+    async fn get_price_details(&self, threshold: &GameThreshold, client: &reqwest::Client) -> StoreResult<SaleInfo> {
+        get_price_details(&threshold.humble_bundle_id, client).await
+    }
+
+    async fn insert_sequence(&self, alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+        insert_sequence(alias, title, price, client).await
+    }
+}
@@ -1,98 +1,406 @@
-use serde_json::{Result, Value, Error};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use serde_json::Value;
+use std::sync::Mutex;
 
-use crate::structs::data::SaleInfo;
-use crate::structs::microsoft_store_response::{ProductInfo, GameInfo};
+use crate::error::{StoreError, StoreResult};
+use crate::file_ops::{settings, thresholds};
+use crate::stores::http;
+use crate::stores::storefront::{self, Storefront};
+use crate::structs::data::{DealKind, GameThreshold, InsertOutcome, Money, Region, SaleInfo, SearchResult, SubscriptionInfo};
+use crate::structs::microsoft_store_response::{ProductInfo, GameInfo, PriceInfo, SalePrice, SkusSummary, SKU};
+use crate::title_match;
 
 static BASE_URL : &str = "https://apps.microsoft.com";
 static SEARCH_ENDPOINT : &str = "/api/products/search";
 static PDP_ENDPOINT : &str = "/api/pages/pdp";
+static GAMES_MEDIA_TYPE : &str = "games";
+// Also lists the add-ons of games (e.g. DLC)
+static ALL_MEDIA_TYPE : &str = "all";
+// Game Pass plans that include PC games (product ID, name)
+static GAME_PASS_PLANS : [(&str, &str); 2] = [("CFQ7TTC0KGQ8", "PC Game Pass"), ("CFQ7TTC0KHS0", "Game Pass Ultimate")];
+static SUBSCRIPTION_CONDITION : &str = "subscription";
+// Last option when picking a SKU (checks the price shown on the product page)
+static PRODUCT_PRICE_OPTION : &str = "Product price (default SKU)";
 
-pub async fn search_game_by_title(title: &str, http_client: &reqwest::Client) -> Result<Vec<ProductInfo>> {
+lazy_static! {
+    static ref INCLUDE_ADD_ONS : Mutex<bool> = Mutex::new(false);
+}
+
+// Searches for add-ons as well as games when adding or searching titles
+pub fn set_include_add_ons(include_add_ons: bool) {
+    *INCLUDE_ADD_ONS.lock().unwrap() = include_add_ons;
+}
+
+pub fn includes_add_ons() -> bool {
+    *INCLUDE_ADD_ONS.lock().unwrap()
+}
+
+pub async fn search_products(title: &str, include_add_ons: bool, region: &Region, http_client: &reqwest::Client) -> StoreResult<Vec<ProductInfo>> {
+    let media_type = if include_add_ons { ALL_MEDIA_TYPE } else { GAMES_MEDIA_TYPE };
     let query_string = [
         ("query", title),
-        ("mediaType", "games"),
+        ("mediaType", media_type),
         ("age", "all"),
         ("price", "all"),
         ("category", "all"),
         ("subscription", "none"),
-        ("gl", "US"),
-        ("hl", "en-US"),
+        ("gl", region.country_code.as_str()),
+        ("hl", region.locale.as_str()),
     ];
-    let url = format!("{}{}", BASE_URL, SEARCH_ENDPOINT);
-    let resp = http_client.get(url)
-        .query(&query_string)
-        .send()
-        .await
-        .expect("Failed to get response")
-        .text()
-        .await
-        .expect("Failed to get data");
-    let body: Value = serde_json::from_str(&resp).expect("Could not convert Microsoft Store search to JSON");
-    let products = serde_json::to_string(&body["productsList"]).unwrap();
-    //println!("{:?}", products);
-    let game_list = serde_json::from_str::<Vec<ProductInfo>>(&products)?;
+    let url = format!("{}{}", settings::get_base_url(settings::MICROSOFT_STORE_URL_KEY, BASE_URL), SEARCH_ENDPOINT);
+    let resp = http::get_text(http_client, &url, &query_string).await?;
+    let body: Value = serde_json::from_str(&resp)?;
+    //println!("{:?}", body["productsList"]);
+    let mut game_list = serde_json::from_value::<Vec<ProductInfo>>(body["productsList"].clone())?;
+    title_match::rank_by_title(title, &mut game_list, |game| game.title.as_str());
     Ok(game_list)
 }
 
-pub async fn get_price_using_search(title: &str, xbox_id :&str, http_client: &reqwest::Client) -> Option<SaleInfo> {
-    let search_list = search_game_by_title(title, http_client)
-        .await
-        .unwrap_or_else(|_e|Vec::new());
+pub async fn search_game_by_title(title: &str, region: &Region, http_client: &reqwest::Client) -> StoreResult<Vec<ProductInfo>> {
+    search_products(title, false, region, http_client).await
+}
+
+// Discount badges look like "-20%" (other badges such as "Free" are not discounts)
+fn get_badge_discount(badge_text: Option<&str>) -> Option<f64> {
+    badge_text?.trim().trim_start_matches('-').trim_end_matches('%').trim().parse::<f64>().ok()
+}
+
+// Matches a plan ID or name (e.g. "CFQ7TTC0KGQ8" or "Included with PC Game Pass")
+fn get_game_pass_plan(text: &str) -> Option<&'static str> {
+    let text = text.to_lowercase();
+    GAME_PASS_PLANS.iter()
+        .find(|(id, name)| text == id.to_lowercase() || text.contains(&name.to_lowercase()))
+        .map(|(_, name)| *name)
+}
+
+fn is_members_price(sale_price: &SalePrice) -> bool {
+    sale_price.conditions.as_ref()
+        .is_some_and(|conditions| conditions.condition_type.to_lowercase().contains(SUBSCRIPTION_CONDITION))
+}
+
+fn get_included_with(skus_summary: &[Option<SkusSummary>]) -> Vec<String> {
+    skus_summary.iter().flatten()
+        .flat_map(|summary| summary.included_with.iter().flatten())
+        .filter_map(|(key, value)| get_game_pass_plan(key).or_else(|| get_game_pass_plan(value)))
+        .map(String::from)
+        .collect()
+}
+
+fn get_summary_sale_prices(skus_summary: &[Option<SkusSummary>]) -> Vec<&SalePrice> {
+    skus_summary.iter().flatten()
+        .flat_map(|summary| summary.sale_prices.iter().flatten())
+        .collect()
+}
+
+// Sale prices are in the currency of the MSRP
+fn get_sale_price(sale_price: &SalePrice, msrp: &Money) -> Money {
+    Money::from_major(sale_price.price, &msrp.currency)
+}
+
+// Game Pass plans the game is included with and prices only available to subscribers
+pub fn get_subscription_info(included_with: Vec<String>, sale_prices: &[&SalePrice], msrp: &Money) -> SubscriptionInfo {
+    let mut info = SubscriptionInfo { included_with, ..Default::default() };
+    let mut has_public_sale = false;
+    for sale_price in sale_prices.iter() {
+        let price = get_sale_price(sale_price, msrp);
+        if !is_members_price(sale_price) {
+            has_public_sale |= price.amount < msrp.amount;
+            continue;
+        }
+        // Free for subscribers means the game is included with the plan
+        if price.is_zero() {
+            let plan_ids = sale_price.conditions.as_ref().and_then(|conditions| conditions.id.as_ref());
+            info.included_with.extend(plan_ids.into_iter().flatten().filter_map(|id| get_game_pass_plan(id)).map(String::from));
+        }
+        else if info.members_price.as_ref().is_none_or(|members_price| price.amount < members_price.amount) {
+            info.members_price = Some(price);
+        }
+    }
+    info.included_with.sort();
+    info.included_with.dedup();
+    info.members_only = info.members_price.as_ref().is_some_and(|price| price.amount < msrp.amount) && !has_public_sale;
+    info
+}
+
+fn get_public_sale_prices<'a>(sale_prices: &[&'a SalePrice], msrp: &Money) -> Vec<&'a SalePrice> {
+    sale_prices.iter().copied().filter(|sale_price| !is_members_price(sale_price) && get_sale_price(sale_price, msrp).amount < msrp.amount).collect()
+}
+
+// Earliest end of a public sale (subscriber prices are ignored)
+fn get_sale_end(sale_prices: &[&SalePrice], msrp: &Money) -> Option<DateTime<Utc>> {
+    get_public_sale_prices(sale_prices, msrp).into_iter()
+        .filter_map(|sale_price| sale_price.conditions.as_ref()?.end_date.as_deref())
+        .filter_map(|end_date| DateTime::parse_from_rfc3339(end_date).ok())
+        .map(|end_date| end_date.with_timezone(&Utc))
+        .min()
+}
+
+// Subscriber-only discounts are never reported as the current price (they are listed in the subscription info instead)
+fn to_sale_info(title: &str, icon_link: &str, store_page_link: Option<String>, price_info: &PriceInfo,
+                skus_summary: &[Option<SkusSummary>], region: &Region) -> StoreResult<SaleInfo> {
+    let (original_price, price) = match (price_info.msrp, price_info.price) {
+        (Some(msrp), Some(price)) => (Money::from_major(msrp, &region.currency), Money::from_major(price, &region.currency)),
+        _ => return Err(StoreError::NoPrice(format!("\"{}\" on the Microsoft Store", title))),
+    };
+    let sale_prices = get_summary_sale_prices(skus_summary);
+    let subscription = get_subscription_info(get_included_with(skus_summary), &sale_prices, &original_price);
+    let current_price = if subscription.members_only { original_price.clone() } else { price };
+    let discount = if subscription.members_only { 0.0 }
+                   else { get_badge_discount(price_info.badge_text.as_deref()).unwrap_or_else(|| storefront::get_sale_discount(&original_price, &current_price)) };
+    let sale_ends_at = if subscription.members_only { None } else { get_sale_end(&sale_prices, &original_price) };
+    Ok(SaleInfo{
+        icon_link: icon_link.to_string(),
+        title: title.to_string(),
+        original_price,
+        current_price,
+        discount_percentage: discount,
+        store_page_link: store_page_link.unwrap_or_default(),
+        deal: DealKind::Sale,
+        sale_ends_at,
+        subscription,
+    })
+}
+
+// Threshold IDs are the product ID or "<product ID>:<SKU ID>" to check one SKU of the product (e.g. an edition)
+pub fn split_microsoft_store_id(id: &str) -> (&str, Option<&str>) {
+    match id.split_once(':') {
+        Some((product_id, sku_id)) if !sku_id.is_empty() => (product_id, Some(sku_id)),
+        _ => (id.trim_end_matches(':'), None),
+    }
+}
+
+pub fn format_microsoft_store_id(product_id: &str, sku_id: Option<&str>) -> String {
+    match sku_id {
+        Some(sku_id) => format!("{}:{}", product_id, sku_id),
+        None => product_id.to_string(),
+    }
+}
+
+// SKU name shown when picking a SKU and in the threshold title (e.g. "Deluxe Edition")
+pub fn get_sku_title(sku: &SKU) -> String {
+    match sku.title.as_deref().filter(|title| !title.is_empty()) {
+        Some(title) => title.to_string(),
+        None => format!("{} ({})", sku.sku_type, sku.sku_id),
+    }
+}
+
+pub fn get_sku_game_title(game_title: &str, sku: &SKU) -> String {
+    format!("{} - {}", game_title, get_sku_title(sku))
+}
+
+// MSRP and lowest public price of the SKU
+fn get_sku_prices(game_title: &str, sku: &SKU, currency: &str) -> StoreResult<(Money, Money)> {
+    let msrp = match sku.msrp {
+        Some(msrp) => Money::from_major(msrp, currency),
+        None => return Err(StoreError::NoPrice(format!("\"{}\" on the Microsoft Store", get_sku_game_title(game_title, sku)))),
+    };
+    let sale_prices : Vec<&SalePrice> = sku.sale_prices.iter().flatten().collect();
+    let current = get_public_sale_prices(&sale_prices, &msrp).into_iter()
+        .map(|sale_price| get_sale_price(sale_price, &msrp))
+        .min_by_key(|price| price.amount)
+        .unwrap_or_else(|| Money::from_major(sku.price, currency));
+    Ok((msrp, current))
+}
+
+fn to_sku_sale_info(game: &GameInfo, sku: &SKU, region: &Region) -> StoreResult<SaleInfo> {
+    let sale_prices : Vec<&SalePrice> = sku.sale_prices.iter().flatten().collect();
+    let (original_price, current) = get_sku_prices(&game.title, sku, &region.currency)?;
+    let subscription = get_subscription_info(get_included_with(&game.skus_summary), &sale_prices, &original_price);
+    let current_price = if subscription.members_only { original_price.clone() } else { current };
+    let sale_ends_at = if subscription.members_only { None } else { get_sale_end(&sale_prices, &original_price) };
+    Ok(SaleInfo{
+        icon_link: game.box_icon_url.clone(),
+        title: get_sku_game_title(&game.title, sku),
+        discount_percentage: storefront::get_sale_discount(&original_price, &current_price),
+        original_price,
+        current_price,
+        store_page_link: game.redirect_url.clone().unwrap_or_default(),
+        deal: DealKind::Sale,
+        sale_ends_at,
+        subscription,
+    })
+}
+
+pub async fn get_price_using_search(title: &str, xbox_id :&str, region: &Region, http_client: &reqwest::Client) -> StoreResult<SaleInfo> {
+    let (product_id, _) = split_microsoft_store_id(xbox_id);
+    let search_list = search_game_by_title(title, region, http_client).await?;
     for game in search_list {
-        if game.product_id == xbox_id {
-            let mut discount_str = game.price_info.badge_text.unwrap_or_default();
-            discount_str = if !discount_str.is_empty() {
-                discount_str[1..discount_str.len()-1].to_string()
-            }else{
-                String::from("0")
-            };
-            return Ok::<SaleInfo, Error>(SaleInfo{
-                icon_link: game.box_icon_url.clone(),
-                title: game.title.clone(),
-                original_price: format!("{}", game.price_info.msrp.unwrap_or_default()),
-                current_price: format!("{}", game.price_info.price.unwrap_or_default()),
-                discount_percentage: discount_str,
-                store_page_link: game.redirect_url.unwrap_or_default(),
-            }).ok();
+        if game.product_id == product_id {
+            return to_sale_info(&game.title, &game.box_icon_url, game.redirect_url, &game.price_info, &game.skus_summary, region);
         }
     }
-    None
+    Err(StoreError::NotFound(format!("\"{}\" ({}) on the Microsoft Store", title, xbox_id)))
 }
 
-pub async fn get_price_details(xbox_id: &str, http_client: &reqwest::Client) -> Option<SaleInfo> {
+// Product page details (each edition of the product is a SKU)
+pub async fn get_game_info(product_id: &str, region: &Region, http_client: &reqwest::Client) -> StoreResult<GameInfo> {
     let query_string = [
-        ("productId", xbox_id),
-        ("gl", "US"),
-        ("hl", "en-US"),
+        ("productId", product_id),
+        ("gl", region.country_code.as_str()),
+        ("hl", region.locale.as_str()),
     ];
-    let url = format!("{}{}", BASE_URL, PDP_ENDPOINT);
-    let resp = http_client.get(url)
-        .query(&query_string)
-        .send()
-        .await
-        .expect("Failed to get response")
-        .text()
-        .await
-        .expect("Failed to get data");
-    let body: Value = serde_json::from_str(&resp).expect("Could not convert Microsoft Store game data to JSON");
-    if let Some(data_str) = body.as_object() {
-        let game_str = serde_json::to_string(data_str).unwrap();
-        //println!("{:?}", game_str);
-        let game = serde_json::from_str::<GameInfo>(&game_str).unwrap();
-        let mut discount_str = game.price_info.badge_text.unwrap_or_default();
-        discount_str = if !discount_str.is_empty() {
-            discount_str[1..discount_str.len()-1].to_string()
-        }else{
-            String::from("0")
-        };
-        return Some(SaleInfo{
-            icon_link: game.box_icon_url.clone(),
-            title: game.title.clone(),
-            original_price: format!("{}", game.price_info.msrp.unwrap_or_default()),
-            current_price: format!("{}", game.price_info.price.unwrap_or_default()),
-            discount_percentage: discount_str,
-            store_page_link: game.redirect_url.unwrap_or_default(),
-        });
-    }
-    None
+    let url = format!("{}{}", settings::get_base_url(settings::MICROSOFT_STORE_URL_KEY, BASE_URL), PDP_ENDPOINT);
+    let resp = http::get_text(http_client, &url, &query_string).await?;
+    let body: Value = serde_json::from_str(&resp)?;
+    if !body.is_object() {
+        return Err(StoreError::NotFound(format!("{} on the Microsoft Store", product_id)));
+    }
+    Ok(serde_json::from_value::<GameInfo>(body)?)
+}
+
+pub fn get_product_sale_info(game: &GameInfo, region: &Region) -> StoreResult<SaleInfo> {
+    to_sale_info(&game.title, &game.box_icon_url, game.redirect_url.clone(), &game.price_info, &game.skus_summary, region)
+}
+
+pub fn get_sku_sale_info(game: &GameInfo, sku_id: &str, region: &Region) -> StoreResult<SaleInfo> {
+    match game.skus.iter().find(|sku| sku.sku_id == sku_id) {
+        Some(sku) => to_sku_sale_info(game, sku, region),
+        None => Err(StoreError::NotFound(format!("SKU {} of \"{}\" ({}) on the Microsoft Store", sku_id, game.title, game.product_id))),
+    }
+}
+
+pub async fn get_price_details(xbox_id: &str, region: &Region, http_client: &reqwest::Client) -> StoreResult<SaleInfo> {
+    let (product_id, sku_id) = split_microsoft_store_id(xbox_id);
+    let game = get_game_info(product_id, region, http_client).await?;
+    match sku_id {
+        Some(sku_id) => get_sku_sale_info(&game, sku_id, region),
+        None => get_product_sale_info(&game, region),
+    }
+}
+
+// Lists the SKUs of the product with their prices (the last option keeps the product price).
+// Without prompts the full SKU titles are matched to the title instead (the product price option is the product title).
+fn pick_sku(game: &mut GameInfo, title: &str) -> Result<Option<SKU>, InsertOutcome> {
+    if game.skus.len() < 2 { return Ok(None); }
+    let interactive = storefront::is_interactive();
+    let currency = settings::get_region().currency;
+    let mut results : Vec<SearchResult> = game.skus.iter().map(|sku| to_sku_search_result(game, sku, &currency)).collect();
+    if !interactive {
+        for (result, sku) in results.iter_mut().zip(&game.skus) {
+            result.title = get_sku_game_title(&game.title, sku);
+        }
+    }
+    let product_option = if interactive { String::from(PRODUCT_PRICE_OPTION) } else { game.title.clone() };
+    results.push(SearchResult { id: game.product_id.clone(), title: product_option, ..Default::default() });
+    match storefront::select_search_result("Microsoft Store SKUs", title, &results)? {
+        idx if idx < game.skus.len() => Ok(Some(game.skus.swap_remove(idx))),
+        _ => Ok(None),
+    }
+}
+
+async fn select_sku(game: &ProductInfo, title: &str, client: &reqwest::Client) -> Result<Option<SKU>, InsertOutcome> {
+    match get_game_info(&game.product_id, &settings::get_region(), client).await {
+        Ok(mut info) => pick_sku(&mut info, title),
+        Err(e) => {
+            println!("Search Microsoft Store SKUs Error: {}", e);
+            Err(InsertOutcome::Skipped(format!("Could not list the SKUs of \"{}\": {}", game.title, e)))
+        }
+    }
+}
+
+fn add_product(alias: &str, product_id: &str, game_title: &str, sku: Option<&SKU>, price: f64) -> InsertOutcome {
+    let added_title = sku.map_or(game_title.to_string(), |sku| get_sku_game_title(game_title, sku));
+    storefront::get_add_outcome(thresholds::add_microsoft_store_game(alias.to_string(), product_id, game_title, sku, price), &added_title)
+}
+
+// Tracks a game or add-on by its product ID (shown in the store page URL) without searching for its title
+pub async fn insert_by_product_id(alias: &str, product_id: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+    let mut game = match get_game_info(product_id, &settings::get_region(), client).await {
+        Ok(game) => game,
+        Err(e) => {
+            println!("Microsoft Store Product Error: {}", e);
+            return InsertOutcome::Skipped(e.to_string());
+        }
+    };
+    let title = game.title.clone();
+    match pick_sku(&mut game, &title) {
+        Ok(sku) => add_product(alias, &game.product_id, &game.title, sku.as_ref(), price),
+        Err(outcome) => outcome,
+    }
+}
+
+pub async fn insert_sequence(alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+    let region = settings::get_region();
+    let search_list : Vec<ProductInfo> = match search_products(title, includes_add_ons(), &region, client).await {
+        Ok(data) => data,
+        Err(e) => {
+            println!("Search Microsoft Store Error: {}", e);
+            return InsertOutcome::Skipped(e.to_string());
+        }
+    };
+    if search_list.is_empty() {
+        println!("Could not find a game title matching \"{}\" on the Microsoft Store.", title);
+        return InsertOutcome::Skipped(String::from("No search results"));
+    }
+    let results : Vec<SearchResult> = search_list.iter().map(|game| to_search_result(game, &region.currency)).collect();
+    match storefront::select_search_result("Microsoft Store", title, &results) {
+        Ok(idx) => {
+            let game = &search_list[idx];
+            match select_sku(game, title, client).await {
+                Ok(sku) => add_product(alias, &game.product_id, &game.title, sku.as_ref(), price),
+                Err(outcome) => outcome,
+            }
+        },
+        Err(outcome) => outcome,
+    }
+}
+
+// Prices are left unset when the product has no MSRP (the current price is the MSRP if it is missing)
+fn to_search_result(game: &ProductInfo, currency: &str) -> SearchResult {
+    let base = game.price_info.msrp.map(|msrp| Money::from_major(msrp, currency));
+    let current = base.as_ref().map(|base| game.price_info.price.map_or(base.clone(), |price| Money::from_major(price, currency)));
+    SearchResult {
+        id: game.product_id.clone(),
+        title: game.title.clone(),
+        discount_percentage: base.as_ref().zip(current.as_ref()).map(|(base, current)| storefront::get_sale_discount(base, current)),
+        price: base,
+        current_price: current,
+    }
+}
+
+fn to_sku_search_result(game: &GameInfo, sku: &SKU, currency: &str) -> SearchResult {
+    let (msrp, current) = get_sku_prices(&game.title, sku, currency).ok().unzip();
+    SearchResult {
+        id: format_microsoft_store_id(&game.product_id, Some(&sku.sku_id)),
+        title: get_sku_title(sku),
+        discount_percentage: msrp.as_ref().zip(current.as_ref()).map(|(msrp, current)| storefront::get_sale_discount(msrp, current)),
+        price: msrp,
+        current_price: current,
+    }
+}
+
+// Storefront
+pub struct MicrosoftStore;
+
+#[async_trait]
+impl Storefront for MicrosoftStore {
+    fn id(&self) -> &'static str { settings::MICROSOFT_STORE_ID }
+
+    fn name(&self) -> &'static str { settings::MICROSOFT_STORE_NAME }
+
+    fn get_threshold_id(&self, threshold: &GameThreshold) -> Option<String> {
+        if threshold.microsoft_store_id.is_empty() { None } else { Some(threshold.microsoft_store_id.clone()) }
+    }
+
+    fn set_threshold_id(&self, threshold: &mut GameThreshold, id: &str) -> bool {
+        threshold.microsoft_store_id = id.to_string();
+        true
+    }
+
+    async fn search(&self, title: &str, client: &reqwest::Client) -> StoreResult<Vec<SearchResult>> {
+        let region = settings::get_region();
+        let search_list = search_products(title, includes_add_ons(), &region, client).await?;
+        Ok(search_list.iter().map(|game| to_search_result(game, &region.currency)).collect())
+    }
+
+    async fn get_price_details(&self, threshold: &GameThreshold, client: &reqwest::Client) -> StoreResult<SaleInfo> {
+        let region = settings::get_threshold_region(threshold);
+        get_price_details(&threshold.microsoft_store_id, &region, client).await
+    }
+
+    async fn insert_sequence(&self, alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+        insert_sequence(alias, title, price, client).await
+    }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+use tokio::time::{self, Instant};
+
+use crate::error::{StoreError, StoreResult};
+use crate::structs::data::StoreLimits;
+
+pub static MAX_RETRIES : u32 = 4;
+static BASE_BACKOFF_MS : u64 = 1000;
+static MAX_BACKOFF_MS : u64 = 30000;
+// Longest Retry-After that is honoured (so a bad header cannot stall a run)
+static MAX_RETRY_AFTER_MS : u64 = 300000;
+
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    // Starts full so the first few requests are not delayed
+    pub fn new(capacity: f64, refill_per_second: f64) -> TokenBucket {
+        TokenBucket {
+            capacity,
+            tokens: capacity,
+            refill_per_second,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    // Takes a token if one is available, otherwise returns how long to wait for the next one
+    pub fn try_take(&mut self) -> Result<(), Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_second))
+    }
+}
+
+pub struct StoreLimiter {
+    semaphore: Semaphore,
+    bucket: Mutex<TokenBucket>,
+}
+
+impl StoreLimiter {
+    pub fn new(limits: &StoreLimits) -> StoreLimiter {
+        let concurrency = limits.concurrency.max(1);
+        StoreLimiter {
+            semaphore: Semaphore::new(concurrency),
+            bucket: Mutex::new(TokenBucket::new(concurrency as f64, limits.requests_per_second)),
+        }
+    }
+
+    // Waits for a free request slot and a token from the bucket
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        let permit = self.semaphore.acquire().await.expect("Store limiter semaphore closed");
+        loop {
+            let wait = match self.bucket.lock().await.try_take() {
+                Ok(()) => return permit,
+                Err(wait) => wait,
+            };
+            time::sleep(wait).await;
+        }
+    }
+}
+
+// Exponential backoff for the given retry attempt (starting at 0)
+pub fn get_backoff(attempt: u32) -> Duration {
+    let backoff_ms = BASE_BACKOFF_MS.saturating_mul(2u64.saturating_pow(attempt));
+    Duration::from_millis(backoff_ms.min(MAX_BACKOFF_MS))
+}
+
+// Waits for as long as the store asked (Retry-After) or uses exponential backoff
+pub fn get_retry_wait(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    match retry_after {
+        Some(wait) => wait.min(Duration::from_millis(MAX_RETRY_AFTER_MS)),
+        None => get_backoff(attempt),
+    }
+}
+
+// Runs a store request within the limiter and retries with backoff when the store returns HTTP 429
+// (the request slot is released while waiting so other requests to the store are not blocked)
+pub async fn with_retry<T, F, Fut>(limiter: &StoreLimiter, mut request: F) -> StoreResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = StoreResult<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        let permit = limiter.acquire().await;
+        let result = request().await;
+        drop(permit);
+        match result {
+            Err(StoreError::RateLimited(_, retry_after)) if attempt < MAX_RETRIES => {
+                time::sleep(get_retry_wait(attempt, retry_after)).await;
+                attempt += 1;
+            },
+            result => return result,
+        }
+    }
+}
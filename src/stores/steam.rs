@@ -1,312 +1,587 @@
+use async_trait::async_trait;
 use dotenv::dotenv as dotenv_linux;
 use dotenvy::dotenv as dotenv_windows;
-use serde_json::{Result, Value, Error};
-use std::fs::read_to_string;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::fs::{self, read_to_string};
 use regex::Regex;
-use std::io::{self, Write};
 use std::env;
-use std::path::PathBuf;
-use crate::file_ops::{json};
-use crate::structs::data::{SaleInfo};
-use crate::structs::steam_response::{App, PriceOverview};
+use std::path::{Path, PathBuf};
+use crate::error::{StoreError, StoreResult};
+use crate::file_ops::{json, settings, steam_cache, thresholds};
+use crate::file_ops::steam_cache::AppCache;
+use crate::stores::{http, rate_limit};
+use crate::stores::rate_limit::StoreLimiter;
+use crate::stores::storefront::{self, Storefront};
+use crate::structs::data::{DealKind, GameThreshold, InsertOutcome, Money, Region, SaleInfo, SearchResult, SteamCacheSync, SteamItemType, StoreLimits, SubscriptionInfo};
+use crate::structs::steam_response::{App, AppListPage, PriceOverview, StoreItem};
+use crate::title_match::{self, TitleQuery};
 
-static CACHE_FILENAME : &str = "steam_game_titles_cache.json";
+static SYNC_FILENAME : &str = "steam_app_cache_sync.json";
+// Sync file used before the app cache was renamed (moved the first time the sync time is read)
+static LEGACY_SYNC_FILENAME : &str = "steam_game_titles_cache_sync.json";
+static APP_LIST_PAGE_SIZE : usize = 40000;
 
 static API_BASE_URL : &str = "https://api.steampowered.com";
 static STORE_BASE_URL : &str = "https://store.steampowered.com";
 
 static APP_LIST_ENDPOINT : &str = "/IStoreService/GetAppList/v1";
 static DETAILS_ENDPOINT : &str = "/api/appdetails";
+static PACKAGE_DETAILS_ENDPOINT : &str = "/api/packagedetails";
+static BUNDLE_DETAILS_ENDPOINT : &str = "/actions/ajaxresolvebundles";
+static STORE_SEARCH_ENDPOINT : &str = "/api/storesearch/";
+static STORE_PAGE_URL : &str = "https://store.steampowered.com";
+static HEADER_IMAGE_URL : &str = "https://shared.akamai.steamstatic.com/store_item_assets/steam/apps";
+
+// Number of app IDs sent in one appdetails request (only supported with the price_overview filter)
+pub static BULK_CHUNK_SIZE : usize = 100;
+
+// appdetails allows roughly 200 requests every 5 minutes
+static DEFAULT_CONCURRENCY : usize = 2;
+static DEFAULT_REQUESTS_PER_SECOND : f64 = 0.6;
 
 // Secrets
 fn get_api_key() -> String {
     if cfg!(target_os = "windows") { dotenv_windows().ok(); }
     else if cfg!(target_os = "linux") { dotenv_linux().ok(); }
-    let steam_api_token = match env::var("STEAM_API_KEY"){
+    match env::var("STEAM_API_KEY"){
         Ok(token) => token,
+        // Keys are not saved in fixtures so none is needed when replaying
+        Err(_) if http::get_transport() == http::Transport::Replay => String::new(),
         Err(_) => panic!("STEAM_API_KEY environment variable not found"),
-    };
-    steam_api_token
+    }
 }
 
 // Caching Functions
-fn get_cache_path() -> String{
-    let path_buf: PathBuf = [json::get_data_path(), CACHE_FILENAME.to_string()].iter().collect();
-    let cache_file_path = path_buf.display().to_string();
-    json::get_path(&cache_file_path)
+// Loads cached games and creates the cache if it is empty
+async fn load_or_update_cache() -> StoreResult<Arc<AppCache>> {
+    let cache = steam_cache::load()?;
+    if !cache.is_empty() { return Ok(cache); }
+    update_cached_games().await?;
+    steam_cache::load()
 }
 
-pub async fn load_cached_games() -> Result<Vec<App>> {
-    let filepath = get_cache_path();
-    let data = read_to_string(filepath).unwrap();
-    let cached_games = serde_json::from_str::<Vec<App>>(&data);
-    cached_games
+fn get_sync_path() -> String{
+    let path_buf: PathBuf = [json::get_data_path(), SYNC_FILENAME.to_string()].iter().collect();
+    let legacy_path_buf: PathBuf = [json::get_data_path(), LEGACY_SYNC_FILENAME.to_string()].iter().collect();
+    migrate_legacy_sync(&legacy_path_buf.display().to_string(), &path_buf.display().to_string());
+    json::get_path(&path_buf.display().to_string())
 }
 
-pub async fn get_last_appid() -> String {
-    let cached_games = load_cached_games().await.unwrap_or(Vec::new());
-    if cached_games.len() > 0 {
-        if let Some(app) = cached_games.last() {
-            let app_id_str = format!("{}", app.app_id);
-            return String::from(app_id_str);
-        }
+// Keeps the last sync time of the legacy sync file so refreshes stay incremental (an empty sync file is replaced)
+pub fn migrate_legacy_sync(legacy_path: &str, path: &str) {
+    let is_synced = fs::metadata(path).map(|md| md.len() > 0).unwrap_or(false);
+    if is_synced || !Path::new(legacy_path).is_file() { return; }
+    let _ = fs::remove_file(path);
+    if let Err(e) = fs::rename(legacy_path, path) {
+        eprintln!("Could not move {} to {}: {}", legacy_path, path, e);
     }
-    String::from("0")
 }
 
-pub async fn update_cached_games(){
-    let mut games_list : Vec<App> = Vec::new();
-    match load_cached_games().await{
-        Ok(data) => games_list = data,
-        Err(e) => println!("No cached data. {}", e)
-    }
-    let mut temp : Vec<App> = Vec::new();
-    let client = reqwest::Client::new();
-    match get_all_games(&client).await {
-        Ok(success) => {
-            println!("Updating cached game titles (this will take a while)...");
-            let body : Value = serde_json::from_str(&success).expect("Could convert Steam app list to JSON");
-            let app_list = serde_json::to_string(&body["response"]["apps"]).unwrap();
-            let data = serde_json::from_str::<Vec<App>>(&app_list);
-            temp = data.unwrap();
-        }, 
+// Time of the last successful cache update or refresh (None if the cache was never synced)
+pub fn get_last_sync() -> Option<DateTime<Utc>> {
+    let data = read_to_string(get_sync_path()).ok()?;
+    serde_json::from_str::<SteamCacheSync>(&data).ok().map(|sync| sync.last_sync)
+}
+
+fn save_cache(cache: AppCache, sync_time: DateTime<Utc>) -> StoreResult<()> {
+    steam_cache::save(cache)?;
+    let sync_str = serde_json::to_string_pretty(&SteamCacheSync { last_sync: sync_time })?;
+    json::write_to_file(get_sync_path(), sync_str);
+    Ok(())
+}
+
+fn load_cache_for_update() -> AppCache {
+    match steam_cache::load() {
+        Ok(cache) => (*cache).clone(),
         Err(e) => {
-            println!("Error: {}", e);
+            println!("No cached data. {}", e);
+            AppCache::default()
         }
     }
-    for game in temp.iter() {
-        let mut unique = true;
-        for cached_game in games_list.iter() {
-            if game.app_id == cached_game.app_id && game.name != "" {
-                unique = false;
-                break;
-            }
-        }
-        if unique && game.name != "".to_string() {
-            games_list.push(App {
-                name: game.name.clone(),
-                app_id: game.app_id.clone(),
-                last_modified: game.last_modified,
-                price_change_number: game.price_change_number,
-            });
+}
+
+pub async fn update_cached_games() -> StoreResult<()> {
+    let mut cache = load_cache_for_update();
+    let sync_time = Utc::now();
+    let client = reqwest::Client::new();
+    let apps = get_app_list(&client, cache.last_appid(), None).await?;
+    println!("Updating cached game titles...");
+    let added = cache.add_new(apps);
+    save_cache(cache, sync_time)?;
+    println!("Cache update complete ({} added)", added);
+    Ok(())
+}
+
+// Only requests apps modified since the last sync (falls back to a full update if there is none)
+pub async fn refresh_cached_games() -> StoreResult<()> {
+    let last_sync = match get_last_sync() {
+        Some(last_sync) => last_sync,
+        None => {
+            println!("No previous cache sync found. Updating the full cache.");
+            return update_cached_games().await;
         }
-    }
-    let data_str = serde_json::to_string_pretty(&games_list).unwrap();
-    json::write_to_file(get_cache_path(), data_str);
-    println!("Cache update complete")
+    };
+    let mut cache = load_cache_for_update();
+    let sync_time = Utc::now();
+    let client = reqwest::Client::new();
+    let apps = get_app_list(&client, 0, Some(last_sync.timestamp())).await?;
+    let (added, updated) = cache.merge(apps);
+    save_cache(cache, sync_time)?;
+    println!("Cache refresh complete ({} added, {} updated since {})", added, updated, last_sync.format("%Y-%m-%d %H:%M:%S UTC"));
+    Ok(())
 }
 
 // API Functions 
-async fn get_all_games(client: &reqwest::Client) -> Result<String> {
+async fn get_app_list_page(client: &reqwest::Client, last_appid: usize, if_modified_since: Option<i64>) -> StoreResult<AppListPage> {
     let steam_api_key = get_api_key();
-    let last_app_id = get_last_appid().await;
-    //println!("Last appid: {}", last_app_id);
-    let query_string = [
-        ("key", steam_api_key.as_str()),
-        ("max_results", "40000"),
-        ("last_appid", &last_app_id),
-        ("format", "json")
+    let mut query_string = vec![
+        ("key", steam_api_key),
+        ("max_results", APP_LIST_PAGE_SIZE.to_string()),
+        ("last_appid", last_appid.to_string()),
     ];
-    let url = format!("{}{}/", API_BASE_URL, APP_LIST_ENDPOINT);
-    let resp = client.get(url)
-        .query(&query_string)
-        .send()
-        .await
-        .expect("Failed to get response")
-        .text()
-        .await
-        .expect("Failed to get data");
-    Ok(resp)
-}
-
-async fn get_game_data(app_id : usize, client: &reqwest::Client) -> Result<String>{
+    if let Some(timestamp) = if_modified_since {
+        query_string.push(("if_modified_since", timestamp.to_string()));
+    }
+    query_string.push(("format", String::from("json")));
+    let url = format!("{}{}/", settings::get_base_url(settings::STEAM_API_URL_KEY, API_BASE_URL), APP_LIST_ENDPOINT);
+    let resp = http::get_text(client, &url, &query_string).await?;
+    let body : Value = serde_json::from_str(&resp)?;
+    Ok(serde_json::from_value::<AppListPage>(body["response"].clone())?)
+}
+
+// Pages through GetAppList starting after last_appid
+pub async fn get_app_list(client: &reqwest::Client, last_appid: usize, if_modified_since: Option<i64>) -> StoreResult<Vec<App>> {
+    let mut apps : Vec<App> = Vec::new();
+    let mut last_appid = last_appid;
+    loop {
+        let page = get_app_list_page(client, last_appid, if_modified_since).await?;
+        let next_appid = page.last_appid
+            .or(page.apps.last().map(|app| app.app_id))
+            .unwrap_or(last_appid);
+        apps.extend(page.apps);
+        // Stop if the page does not move forward to avoid requesting the same page again
+        if !page.have_more_results || next_appid <= last_appid { break; }
+        last_appid = next_appid;
+    }
+    Ok(apps)
+}
+
+async fn get_game_data(app_id : usize, region: &Region, client: &reqwest::Client) -> StoreResult<Value>{
     let app_id_str = app_id.to_string();
     let query_string = [
         ("appids", app_id_str.as_str()),
         ("filters", "basic,price_overview"),
+        ("cc", region.country_code.as_str()),
     ];
-    let url = format!("{}{}", STORE_BASE_URL, DETAILS_ENDPOINT);
-    let resp = client.get(url)
-        .query(&query_string)
-        .send()
-        .await
-        .expect("Failed to get response")
-        .text()
-        .await
-        .expect("Failed to get data");
-    Ok(resp)
-}
-
-pub async fn get_price(app_id : usize, client: &reqwest::Client) -> Result<PriceOverview>{
-    let mut overview = PriceOverview {
-        currency: String::from(""),
-        discount_percent: 0,
-        initial: 0.0,
-        final_price: 0.0,
-    };
-    match get_game_data(app_id, &client).await {
-        Ok(success) => {
-            let body : Value = serde_json::from_str(&success).expect("Could convert to game data json");
-            let data = body[app_id.to_string()]["success"].clone();
-            match data{
-                Value::Bool(true) => {
-                    let price_overview : &Value = &body[app_id.to_string()]["data"]["price_overview"];
-                    if *price_overview != Value::Null {
-                        overview.final_price = price_overview["final"].as_f64().unwrap()/100.0;
-                        overview.initial = price_overview["initial"].as_f64().unwrap()/100.0;
-                        overview.discount_percent = price_overview["discount_percent"].as_f64().unwrap() as usize;
-                        overview.currency = price_overview["currency"].to_string();
-                    }
-                    else{
-                        eprintln!("Could not find pricing data for {:?}", &body[app_id.to_string()]["data"]["name"]);
-                    }
-                },
-                Value::Bool(false) => {
-                    eprintln!("Error: No data available for game.");
-                    std::process::exit(exitcode::DATAERR);
-                },
-                _ => panic!("Something strange occurred")
-            }
-        },
-        Err(e) => {
-            println!("{}", e);
+    let url = format!("{}{}", settings::get_base_url(settings::STEAM_STORE_URL_KEY, STORE_BASE_URL), DETAILS_ENDPOINT);
+    let resp = http::get_text(client, &url, &query_string).await?;
+    let body : Value = serde_json::from_str(&resp)?;
+    match &body[&app_id_str]["success"] {
+        Value::Bool(true) => Ok(body[&app_id_str]["data"].clone()),
+        Value::Bool(false) => Err(StoreError::NotFound(format!("No data available for Steam app {}", app_id))),
+        _ => Err(StoreError::Parse(format!("Unexpected response for Steam app {}", app_id))),
+    }
+}
+
+fn get_price_number(item: &str, price: &Value, key: &str) -> StoreResult<f64> {
+    price[key].as_f64()
+        .ok_or(StoreError::Parse(format!("Missing \"{}\" in Steam price data for {}", key, item)))
+}
+
+// Apps and packages share the same price fields
+fn parse_price(item: &str, data: &Value, price_key: &str) -> StoreResult<PriceOverview> {
+    let price : &Value = &data[price_key];
+    if price.is_null() {
+        return Err(StoreError::NoPrice(format!("Could not find pricing data for {} ({})", data["name"], item)));
+    }
+    Ok(PriceOverview {
+        currency: price["currency"].as_str().unwrap_or_default().to_string(),
+        discount_percent: get_price_number(item, price, "discount_percent")? as usize,
+        initial: get_price_number(item, price, "initial")?/100.0,
+        final_price: get_price_number(item, price, "final")?/100.0,
+    })
+}
+
+fn parse_price_overview(app_id: usize, data: &Value) -> StoreResult<PriceOverview> {
+    parse_price(&app_id.to_string(), data, "price_overview")
+}
+
+pub async fn get_price(app_id : usize, region: &Region, client: &reqwest::Client) -> StoreResult<PriceOverview>{
+    let data = get_game_data(app_id, region, client).await?;
+    parse_price_overview(app_id, &data)
+}
+
+// Returns the price of each app (keyed by app ID). An error is returned if any request fails.
+pub async fn get_prices_bulk(app_ids: &[usize], region: &Region, client: &reqwest::Client) -> StoreResult<HashMap<usize, StoreResult<PriceOverview>>>{
+    let url = format!("{}{}", settings::get_base_url(settings::STEAM_STORE_URL_KEY, STORE_BASE_URL), DETAILS_ENDPOINT);
+    let mut prices = HashMap::new();
+    for chunk in app_ids.chunks(BULK_CHUNK_SIZE) {
+        let app_ids_str = chunk.iter().map(|app_id| app_id.to_string()).collect::<Vec<String>>().join(",");
+        let query_string = [
+            ("appids", app_ids_str.as_str()),
+            ("filters", "price_overview"),
+            ("cc", region.country_code.as_str()),
+        ];
+        let resp = http::get_text(client, &url, &query_string).await?;
+        let body : Value = serde_json::from_str(&resp)?;
+        for app_id in chunk.iter() {
+            let app_data = &body[app_id.to_string()];
+            let price = match &app_data["success"] {
+                Value::Bool(true) => parse_price_overview(*app_id, &app_data["data"]),
+                Value::Bool(false) => Err(StoreError::NotFound(format!("No data available for Steam app {}", app_id))),
+                _ => Err(StoreError::Parse(format!("Unexpected response for Steam app {}", app_id))),
+            };
+            prices.insert(*app_id, price);
         }
     }
-    Ok(overview)
+    Ok(prices)
+}
+
+pub fn get_header_image(app_id: usize) -> String {
+    format!("{}/{}/header.jpg", HEADER_IMAGE_URL, app_id)
+}
+
+pub fn get_store_page_link(item_type: SteamItemType, id: usize) -> String {
+    match item_type {
+        SteamItemType::App => format!("{}/app/{}", STORE_PAGE_URL, id),
+        SteamItemType::Package => format!("{}/sub/{}", STORE_PAGE_URL, id),
+        SteamItemType::Bundle => format!("{}/bundle/{}", STORE_PAGE_URL, id),
+    }
 }
 
-pub async fn get_price_details(app_id : usize, client: &reqwest::Client) -> Result<SaleInfo>{
-    let mut sale_info = SaleInfo {
-        icon_link: String::new(),
-        title: String::new(),
-        original_price: String::new(),
-        current_price: String::new(),
-        discount_percentage: String::new(),
-        store_page_link: String::new(),
+fn to_sale_info(store_page_link: String, title: String, icon_link: String, overview: PriceOverview) -> SaleInfo {
+    SaleInfo {
+        icon_link,
+        title,
+        original_price: Money::from_major(overview.initial, &overview.currency),
+        current_price: Money::from_major(overview.final_price, &overview.currency),
+        discount_percentage: overview.discount_percent as f64,
+        store_page_link,
+        deal: DealKind::Sale,
+        subscription: SubscriptionInfo::default(),
+        // Steam does not report when a discount ends
+        sale_ends_at: None,
+    }
+}
+
+pub async fn get_price_details(app_id : usize, region: &Region, client: &reqwest::Client) -> StoreResult<SaleInfo>{
+    let data = get_game_data(app_id, region, client).await?;
+    let overview = parse_price_overview(app_id, &data)?;
+    Ok(to_sale_info(get_store_page_link(SteamItemType::App, app_id),
+                    data["name"].as_str().unwrap_or_default().to_string(),
+                    data["header_image"].as_str().unwrap_or_default().to_string(),
+                    overview))
+}
+
+pub async fn get_package_details(package_id: usize, region: &Region, client: &reqwest::Client) -> StoreResult<SaleInfo>{
+    let package_id_str = package_id.to_string();
+    let query_string = [
+        ("packageids", package_id_str.as_str()),
+        ("cc", region.country_code.as_str()),
+    ];
+    let url = format!("{}{}", settings::get_base_url(settings::STEAM_STORE_URL_KEY, STORE_BASE_URL), PACKAGE_DETAILS_ENDPOINT);
+    let resp = http::get_text(client, &url, &query_string).await?;
+    let body : Value = serde_json::from_str(&resp)?;
+    let data = match &body[&package_id_str]["success"] {
+        Value::Bool(true) => &body[&package_id_str]["data"],
+        Value::Bool(false) => return Err(StoreError::NotFound(format!("No data available for Steam package {}", package_id))),
+        _ => return Err(StoreError::Parse(format!("Unexpected response for Steam package {}", package_id))),
     };
-    match get_game_data(app_id, &client).await {
-        Ok(success) => {
-            let body : Value = serde_json::from_str(&success).expect("Could convert to game data json");
-            let data = body[app_id.to_string()]["success"].clone();
-            match data{
-                Value::Bool(true) => {
-                    let data : &Value = &body[app_id.to_string()]["data"];
-                    if *data != Value::Null {
-                        sale_info.icon_link = data["header_image"].as_str().unwrap().to_string();
-                        sale_info.title = data["name"].as_str().unwrap().to_string();
-                        sale_info.original_price = format!("{}", data["price_overview"]["initial"].as_f64().unwrap()/100.0);
-                        sale_info.current_price = format!("{}", data["price_overview"]["final"].as_f64().unwrap()/100.0);
-                        sale_info.discount_percentage = format!("{}", data["price_overview"]["discount_percent"].as_f64().unwrap() as usize);
-                        sale_info.store_page_link = format!("https://store.steampowered.com/app/{}", app_id);
-                    }
-                    else{
-                        eprintln!("Could not find pricing data for {:?}", &body[app_id.to_string()]["data"]["name"]);
-                    }
-                },
-                Value::Bool(false) => {
-                    eprintln!("Error: No data available for game.");
-                    std::process::exit(exitcode::DATAERR);
-                },
-                _ => panic!("Something strange occurred")
-            }
-        },
-        Err(e) => {
-            println!("{}", e);
-        }
+    let overview = parse_price(&format!("package {}", package_id), data, "price")?;
+    Ok(to_sale_info(get_store_page_link(SteamItemType::Package, package_id),
+                    data["name"].as_str().unwrap_or_default().to_string(),
+                    data["header_image"].as_str().unwrap_or_default().to_string(),
+                    overview))
+}
+
+// Bundles have no details API so the endpoint used by the store pages is queried
+pub async fn get_bundle_details(bundle_id: usize, region: &Region, client: &reqwest::Client) -> StoreResult<SaleInfo>{
+    let bundle_id_str = bundle_id.to_string();
+    let query_string = [
+        ("bundleids", bundle_id_str.as_str()),
+        ("cc", region.country_code.as_str()),
+        ("l", "english"),
+    ];
+    let url = format!("{}{}", settings::get_base_url(settings::STEAM_STORE_URL_KEY, STORE_BASE_URL), BUNDLE_DETAILS_ENDPOINT);
+    let resp = http::get_text(client, &url, &query_string).await?;
+    let body : Value = serde_json::from_str(&resp)?;
+    let data = body.as_array()
+        .and_then(|bundles| bundles.iter().find(|bundle| bundle["bundleid"].as_u64() == Some(bundle_id as u64)))
+        .ok_or(StoreError::NotFound(format!("No data available for Steam bundle {}", bundle_id)))?;
+    let item = format!("bundle {}", bundle_id);
+    // Bundle prices do not include a currency code (they are in the currency of the requested region)
+    let overview = PriceOverview {
+        currency: region.currency.clone(),
+        discount_percent: get_price_number(&item, data, "discount_percent")? as usize,
+        initial: get_price_number(&item, data, "initial_price")?/100.0,
+        final_price: get_price_number(&item, data, "final_price")?/100.0,
+    };
+    Ok(to_sale_info(get_store_page_link(SteamItemType::Bundle, bundle_id),
+                    data["name"].as_str().unwrap_or_default().to_string(),
+                    data["header_image_url"].as_str().unwrap_or_default().to_string(),
+                    overview))
+}
+
+pub async fn get_price_details_by_type(id: usize, item_type: SteamItemType, region: &Region, client: &reqwest::Client) -> StoreResult<SaleInfo>{
+    match item_type {
+        SteamItemType::App => get_price_details(id, region, client).await,
+        SteamItemType::Package => get_package_details(id, region, client).await,
+        SteamItemType::Bundle => get_bundle_details(id, region, client).await,
     }
-    Ok(sale_info)
+}
+
+// Threshold IDs are prefixed for packages ("sub:<id>") and bundles ("bundle:<id>")
+pub fn format_item_id(item_type: SteamItemType, id: usize) -> String {
+    match item_type {
+        SteamItemType::App => id.to_string(),
+        SteamItemType::Package => format!("sub:{}", id),
+        SteamItemType::Bundle => format!("bundle:{}", id),
+    }
+}
+
+pub fn parse_item_id(id_str: &str) -> Option<(SteamItemType, usize)> {
+    let (item_type, id) = match id_str.split_once(':') {
+        Some(("sub", id)) => (SteamItemType::Package, id),
+        Some(("bundle", id)) => (SteamItemType::Bundle, id),
+        Some(_) => return None,
+        None => (SteamItemType::App, id_str),
+    };
+    id.parse::<usize>().ok().map(|id| (item_type, id))
 }
 
 // Command Functions
-pub async fn check_game(name: &str) -> Option<App> {
-    let mut games_list : Vec<App> = Vec::new();
-    match load_cached_games().await {
-        Ok(data) => games_list = data,
-        Err(e) => println!("Error: {}", e)
+pub async fn check_game(name: &str) -> StoreResult<App> {
+    let cache = load_or_update_cache().await?;
+    match cache.find_by_title(name) {
+        Some(app) => Ok(app.clone()),
+        None => Err(StoreError::NotFound(format!("\"{}\" is not in the Steam game cache", name))),
     }
-    if games_list.is_empty() {
-        update_cached_games().await;
-        match load_cached_games().await {
-            Ok(data) => games_list = data,
-            Err(e) => println!("Error: {}", e)
-        }
+}
+
+// Search Functions
+// Uses the prefix index first and only scans every title (with the keyphrase as a regex and then fuzzy matching) if nothing starts with it
+// Results are ranked by how well they match the keyphrase
+async fn search_cached_apps(keyphrase: &str) -> StoreResult<Vec<App>> {
+    let cache = load_or_update_cache().await?;
+    let mut matches = cache.search_prefix(keyphrase);
+    if matches.is_empty() && let Ok(re) = Regex::new(keyphrase) {
+        matches = cache.search(|name| re.is_match(name));
     }
-    for elem in games_list.iter(){
-        if name.to_owned() == elem.name {
-            return Ok::<App, Error>(App {
-                name: name.to_owned(),
-                app_id: elem.app_id,
-                last_modified: elem.last_modified,
-                price_change_number: elem.price_change_number,
-            }).ok();
-        }
+    if matches.is_empty() {
+        let query = TitleQuery::new(keyphrase);
+        matches = cache.search(|name| query.score(name) >= title_match::FUZZY_MIN_SCORE);
     }
-    None
+    title_match::rank_by_title(keyphrase, &mut matches, |app| app.name.as_str());
+    Ok(matches.into_iter().cloned().collect())
 }
 
-// Search Functions
-pub async fn search_by_keyphrase(keyphrase: &str) -> Result<Vec<String>>{
-    let mut games_list : Vec<App> = Vec::new();
-    match load_cached_games().await {
-        Ok(data) => games_list = data,
+pub async fn search_by_keyphrase(keyphrase: &str) -> StoreResult<Vec<String>>{
+    let search_list = search_cached_apps(keyphrase).await?
+        .into_iter()
+        .map(|game| game.name)
+        .collect();
+    Ok(search_list)
+}
+
+// Searches the store (unlike the app cache, results include packages and bundles)
+pub async fn search_store(term: &str, region: &Region, client: &reqwest::Client) -> StoreResult<Vec<StoreItem>> {
+    let query_string = [
+        ("term", term),
+        ("cc", region.country_code.as_str()),
+        ("l", "english"),
+    ];
+    let url = format!("{}{}", settings::get_base_url(settings::STEAM_STORE_URL_KEY, STORE_BASE_URL), STORE_SEARCH_ENDPOINT);
+    let resp = http::get_text(client, &url, &query_string).await?;
+    let body : Value = serde_json::from_str(&resp)?;
+    Ok(serde_json::from_value::<Vec<StoreItem>>(body["items"].clone())?)
+}
+
+pub fn get_item_type(item: &StoreItem) -> Option<SteamItemType> {
+    match item.item_type.as_str() {
+        "app" => Some(SteamItemType::App),
+        "sub" => Some(SteamItemType::Package),
+        "bundle" => Some(SteamItemType::Bundle),
+        _ => None,
+    }
+}
+
+// Lists cached apps matching the keyphrase along with packages and bundles from the store search
+pub async fn search_game(keyphrase: &str, client: &reqwest::Client) -> Result<(SteamItemType, usize, String), InsertOutcome>{
+    let mut search_list : Vec<(SteamItemType, usize, String)> = Vec::new();
+    match search_cached_apps(keyphrase).await {
+        Ok(apps) => search_list.extend(apps.into_iter().map(|app| (SteamItemType::App, app.app_id, app.name))),
         Err(e) => println!("Error: {}", e)
     }
-    if games_list.len() == 0 {
-        update_cached_games().await;
-        match load_cached_games().await {
-            Ok(data) => games_list = data,
-            Err(e) => println!("Error: {}", e)
-        }
+    match search_store(keyphrase, &settings::get_region(), client).await {
+        Ok(items) => search_list.extend(items.into_iter().filter_map(|item| match get_item_type(&item) {
+            Some(SteamItemType::App) | None => None,
+            Some(item_type) => Some((item_type, item.id, item.name)),
+        })),
+        Err(e) => println!("Search Steam Store Error: {}", e)
     }
-    let mut search_list : Vec<String> = Vec::new();
-    let re = Regex::new(keyphrase).unwrap();
-    for game in games_list.iter(){
-        let caps = re.captures(&game.name);
-        if !caps.is_none() {
-            search_list.push(game.name.clone());
+    if search_list.is_empty() {
+        println!("Could not find a game title matching \"{}\" on Steam.", keyphrase);
+        return Err(InsertOutcome::Skipped(String::from("No search results")));
+    }
+    let results : Vec<SearchResult> = search_list.iter()
+        .map(|(item_type, id, name)| {
+            let title = match item_type {
+                SteamItemType::App => name.clone(),
+                SteamItemType::Package => format!("{} (Package)", name),
+                SteamItemType::Bundle => format!("{} (Bundle)", name),
+            };
+            SearchResult { id: format_item_id(*item_type, *id), title, ..Default::default() }
+        })
+        .collect();
+    let store_name = settings::get_proper_store_name(settings::STEAM_STORE_ID).unwrap();
+    storefront::select_search_result(&store_name, keyphrase, &results).map(|idx| search_list.swap_remove(idx))
+}
+
+async fn add_app(alias: &str, app: App, price: f64, client: &reqwest::Client) -> InsertOutcome {
+    let name = app.name.clone();
+    storefront::get_add_outcome(thresholds::add_steam_game(alias.to_string(), app, price, client).await, &name)
+}
+
+pub async fn insert_sequence(alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+    match check_game(title).await {
+        Ok(data) => add_app(alias, data, price, client).await,
+        Err(StoreError::NotFound(_)) => {
+            match search_game(title, client).await {
+                Ok((SteamItemType::App, _, t)) => {
+                    match check_game(&t).await {
+                        Ok(data) => add_app(alias, data, price, client).await,
+                        Err(e) => {
+                            eprintln!("Something went wrong. {}", e);
+                            InsertOutcome::Skipped(e.to_string())
+                        }
+                    }
+                },
+                Ok((item_type, id, t)) => {
+                    let added = thresholds::add_steam_item(alias.to_string(), &t, item_type, id, price, client).await;
+                    storefront::get_add_outcome(added, &t)
+                },
+                Err(outcome) => outcome,
+            }
+        },
+        Err(e) => {
+            eprintln!("Steam Error: {}", e);
+            InsertOutcome::Skipped(e.to_string())
         }
     }
-    Ok(search_list)
 }
 
-pub async fn search_game(keyphrase: &str) -> Option<String>{
-    match search_by_keyphrase(keyphrase).await {
-        Ok(search_list) => {
-            if !search_list.is_empty() {
-                println!("Steam search results:");
-                for (idx, game_title) in search_list.iter().enumerate() {
-                    println!("  [{}] {}", idx, game_title);
-                }
-                println!("  [q] SKIP");
-                let mut input = String::new();
-                print!("Type integer corresponding to game title or type \"q\" to quit: ");
-                let _ = io::stdout().flush();
-                io::stdin()
-                    .read_line(&mut input)
-                    .expect("Failed to read user input");
-                if input.trim() == "q" {
-                    eprintln!("Request terminated.");
+// Storefront
+pub struct Steam;
+
+#[async_trait]
+impl Storefront for Steam {
+    fn id(&self) -> &'static str { settings::STEAM_STORE_ID }
+
+    fn name(&self) -> &'static str { settings::STEAM_STORE_NAME }
+
+    fn get_threshold_id(&self, threshold: &GameThreshold) -> Option<String> {
+        if threshold.steam_id == 0 { None } else { Some(format_item_id(threshold.steam_type, threshold.steam_id)) }
+    }
+
+    fn set_threshold_id(&self, threshold: &mut GameThreshold, id: &str) -> bool {
+        match parse_item_id(id) {
+            Some((item_type, item_id)) => {
+                threshold.steam_type = item_type;
+                threshold.steam_id = item_id;
+                true
+            },
+            None => false
+        }
+    }
+
+    // The app cache has no prices so they are requested for the best matches only (in one bulk request)
+    async fn search(&self, title: &str, client: &reqwest::Client) -> StoreResult<Vec<SearchResult>> {
+        let mut apps = search_cached_apps(title).await?;
+        apps.truncate(storefront::MAX_SEARCH_RESULTS);
+        let app_ids : Vec<usize> = apps.iter().map(|app| app.app_id).collect();
+        let mut prices = match get_prices_bulk(&app_ids, &settings::get_region(), client).await {
+            Ok(prices) => prices,
+            Err(e) => {
+                eprintln!("Steam Price Error: {}", e);
+                HashMap::new()
+            }
+        };
+        let search_list = apps.into_iter()
+            .map(|app| {
+                let price = prices.remove(&app.app_id).and_then(|price| price.ok());
+                SearchResult {
+                    id: app.app_id.to_string(),
+                    title: app.name,
+                    price: price.as_ref().map(|po| Money::from_major(po.initial, &po.currency)),
+                    current_price: price.as_ref().map(|po| Money::from_major(po.final_price, &po.currency)),
+                    discount_percentage: price.as_ref().map(|po| po.discount_percent as f64),
                 }
-                else {
-                    match input.trim().parse::<usize>() {
-                        Ok(idx) => {
-                            if idx < search_list.len(){
-                                let title = search_list[idx].clone();
-                                return Ok::<String, Error>(title).ok();
-                            }
-                            else if idx >= search_list.len(){
-                                eprintln!("Integer \"{}\" is invalid. Request terminated.", idx);
-                            }
-                        },
-                        Err(e) => println!("Invalid input: {}\nError: {}", input, e)
-                    }
+            })
+            .collect();
+        Ok(search_list)
+    }
+
+    async fn get_price_details(&self, threshold: &GameThreshold, client: &reqwest::Client) -> StoreResult<SaleInfo> {
+        let region = settings::get_threshold_region(threshold);
+        get_price_details_by_type(threshold.steam_id, threshold.steam_type, &region, client).await
+    }
+
+    fn max_bulk_size(&self) -> usize { BULK_CHUNK_SIZE }
+
+    // Sends one request per region for apps (titles and icons come from the threshold since only price data is returned)
+    // Packages and bundles have no bulk endpoint and are checked one at a time
+    async fn get_price_details_bulk(&self, thresholds: &[&GameThreshold], limiter: &StoreLimiter, client: &reqwest::Client) -> StoreResult<Vec<StoreResult<SaleInfo>>> {
+        let regions : Vec<Region> = thresholds.iter().map(|threshold| settings::get_threshold_region(threshold)).collect();
+        let mut prices : HashMap<(String, usize), StoreResult<PriceOverview>> = HashMap::new();
+        for region in regions.iter() {
+            if prices.keys().any(|(country_code, _)| *country_code == region.country_code) { continue; }
+            // Apps shared by several thresholds are only requested once
+            let mut app_ids : Vec<usize> = Vec::new();
+            for (threshold, threshold_region) in thresholds.iter().zip(regions.iter()) {
+                if threshold.steam_type == SteamItemType::App && threshold_region.country_code == region.country_code &&
+                    !app_ids.contains(&threshold.steam_id) {
+                    app_ids.push(threshold.steam_id);
                 }
             }
-            else {
-                println!("Could not find a game title matching \"{}\" on Steam.", keyphrase);
+            // A failed request only fails the apps of its region
+            match rate_limit::with_retry(limiter, || get_prices_bulk(&app_ids, region, client)).await {
+                Ok(region_prices) => for (app_id, price) in region_prices {
+                    prices.insert((region.country_code.clone(), app_id), price);
+                },
+                Err(e) => for app_id in app_ids {
+                    prices.insert((region.country_code.clone(), app_id), Err(e.clone()));
+                },
             }
-        }, 
-        Err(e) => println!("Error: {}", e)
+        }
+        let mut results = Vec::new();
+        for (threshold, region) in thresholds.iter().zip(regions.iter()) {
+            if threshold.steam_type != SteamItemType::App {
+                results.push(rate_limit::with_retry(limiter, || get_price_details_by_type(threshold.steam_id, threshold.steam_type, region, client)).await);
+                continue;
+            }
+            // Not removed since thresholds can share an app
+            let result = match prices.get(&(region.country_code.clone(), threshold.steam_id)).cloned() {
+                Some(price) => price.map(|overview| to_sale_info(get_store_page_link(SteamItemType::App, threshold.steam_id),
+                                                                 threshold.title.clone(),
+                                                                 get_header_image(threshold.steam_id), overview)),
+                None => Err(StoreError::NotFound(format!("No data available for Steam app {}", threshold.steam_id))),
+            };
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    async fn insert_sequence(&self, alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome {
+        insert_sequence(alias, title, price, client).await
     }
-    None
-}
\ No newline at end of file
+
+    fn default_limits(&self) -> StoreLimits {
+        StoreLimits {
+            concurrency: DEFAULT_CONCURRENCY,
+            requests_per_second: DEFAULT_REQUESTS_PER_SECOND,
+        }
+    }
+}
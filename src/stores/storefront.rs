@@ -0,0 +1,220 @@
+use async_trait::async_trait;
+use futures::future::join_all;
+use lazy_static::lazy_static;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use crate::error::StoreResult;
+use crate::file_ops::settings;
+use crate::stores::{steam, gog, microsoft_store, humble_bundle, epic, rate_limit};
+use crate::stores::rate_limit::StoreLimiter;
+use crate::structs::data::{GameThreshold, InsertOutcome, Money, SaleInfo, SearchResult, StoreLimits, StoreSearchResult};
+use crate::title_match::TitleQuery;
+
+// Minimum match score used by --non-interactive (only the same normalized title is picked)
+pub static DEFAULT_AUTO_MATCH_SCORE : f64 = 1.0;
+// Number of candidate titles listed for an ambiguous search
+static MAX_CANDIDATES : usize = 5;
+// Number of results listed per store by the search command
+pub static MAX_SEARCH_RESULTS : usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectionMode {
+    // Prompt for every search result, edition and alias
+    Interactive,
+    // Pick the best search result if it scores at least the given minimum (never prompts)
+    AutoMatch(f64),
+}
+
+lazy_static! {
+    static ref SELECTION_MODE : Mutex<SelectionMode> = Mutex::new(SelectionMode::Interactive);
+}
+
+pub fn get_selection_mode() -> SelectionMode {
+    *SELECTION_MODE.lock().unwrap()
+}
+
+pub fn set_selection_mode(mode: SelectionMode) {
+    *SELECTION_MODE.lock().unwrap() = mode;
+}
+
+pub fn is_interactive() -> bool {
+    get_selection_mode() == SelectionMode::Interactive
+}
+
+#[async_trait]
+pub trait Storefront: Send + Sync {
+    // Store ID used in settings and thresholds (e.g. "steam")
+    fn id(&self) -> &'static str;
+    // Plain text store name used in reports
+    fn name(&self) -> &'static str;
+    // Store specific ID saved in a threshold (None if game is not tracked on this store)
+    fn get_threshold_id(&self, threshold: &GameThreshold) -> Option<String>;
+    // Returns false if the ID is not valid for this store
+    fn set_threshold_id(&self, threshold: &mut GameThreshold, id: &str) -> bool;
+    async fn search(&self, title: &str, client: &reqwest::Client) -> StoreResult<Vec<SearchResult>>;
+    async fn get_price_details(&self, threshold: &GameThreshold, client: &reqwest::Client) -> StoreResult<SaleInfo>;
+    // Maximum number of thresholds passed to get_price_details_bulk (1 if the store has no bulk endpoint)
+    fn max_bulk_size(&self) -> usize { 1 }
+    // Returns a result for each threshold (in order); an outer error means the whole request failed.
+    // Every request is sent through the store limiter (with retries when rate limited).
+    async fn get_price_details_bulk(&self, thresholds: &[&GameThreshold], limiter: &StoreLimiter, client: &reqwest::Client) -> StoreResult<Vec<StoreResult<SaleInfo>>> {
+        if let [threshold] = thresholds {
+            return Ok(vec![Ok(rate_limit::with_retry(limiter, || self.get_price_details(threshold, client)).await?)]);
+        }
+        let mut results = Vec::new();
+        for threshold in thresholds.iter() {
+            results.push(rate_limit::with_retry(limiter, || self.get_price_details(threshold, client)).await);
+        }
+        Ok(results)
+    }
+    async fn insert_sequence(&self, alias: &str, title: &str, price: f64, client: &reqwest::Client) -> InsertOutcome;
+    // Limits used when none are set in settings
+    fn default_limits(&self) -> StoreLimits {
+        StoreLimits {
+            concurrency: settings::DEFAULT_CONCURRENCY,
+            requests_per_second: settings::DEFAULT_REQUESTS_PER_SECOND,
+        }
+    }
+}
+
+// Registry of supported storefronts (add new stores here)
+pub fn get_storefronts() -> Vec<Box<dyn Storefront>> {
+    vec![
+        Box::new(steam::Steam),
+        Box::new(gog::Gog),
+        Box::new(microsoft_store::MicrosoftStore),
+        Box::new(humble_bundle::HumbleBundle),
+        Box::new(epic::Epic),
+    ]
+}
+
+pub fn get_storefront(id: &str) -> Option<Box<dyn Storefront>> {
+    get_storefronts().into_iter().find(|store| store.id() == id)
+}
+
+// Fetches the price of every threshold on every store it is tracked on (each store has its own rate limits).
+// Thresholds are grouped per store so stores with a bulk endpoint need fewer requests.
+// Returns (store index, threshold index, price); a failed bulk request is returned as an error for each of its thresholds.
+pub async fn get_price_details_all(storefronts: &[Box<dyn Storefront>], thresholds: &[GameThreshold], client: &reqwest::Client)
+    -> Vec<(usize, usize, StoreResult<SaleInfo>)> {
+    let limiters: Vec<StoreLimiter> = storefronts.iter()
+        .map(|store| StoreLimiter::new(&settings::get_store_limits(store.id()).unwrap_or_else(|| store.default_limits())))
+        .collect();
+    // Chunks hold threshold indexes so each result can be matched to its threshold
+    let mut chunks : Vec<(usize, Vec<usize>)> = Vec::new();
+    for (idx, store) in storefronts.iter().enumerate() {
+        let store_thresholds : Vec<usize> = thresholds.iter().enumerate()
+            .filter(|(_, elem)| store.get_threshold_id(elem).is_some())
+            .map(|(pos, _)| pos)
+            .collect();
+        for chunk in store_thresholds.chunks(store.max_bulk_size().max(1)) {
+            chunks.push((idx, chunk.to_vec()));
+        }
+    }
+    let requests = chunks.iter().map(|(idx, chunk)| {
+        let store = &storefronts[*idx];
+        let limiter = &limiters[*idx];
+        async move {
+            let chunk_thresholds : Vec<&GameThreshold> = chunk.iter().map(|pos| &thresholds[*pos]).collect();
+            let result = store.get_price_details_bulk(&chunk_thresholds, limiter, client).await;
+            (*idx, chunk, result)
+        }
+    });
+    let mut results = Vec::new();
+    for (idx, chunk, result) in join_all(requests).await {
+        match result {
+            Ok(infos) => results.extend(chunk.iter().zip(infos).map(|(pos, info)| (idx, *pos, info))),
+            Err(e) => results.extend(chunk.iter().map(|pos| (idx, *pos, Err(e.clone())))),
+        }
+    }
+    results
+}
+
+// Discount percentage (rounded) from the base and current price
+pub fn get_discount(base: f64, current: f64) -> f64 {
+    if base > 0.0 { ((base - current) / base * 100.0).round() } else { 0.0 }
+}
+
+// Discount percentage of a sale for stores that do not send one (0 if the prices are in different currencies)
+pub fn get_sale_discount(base: &Money, current: &Money) -> f64 {
+    if base.currency != current.currency { return 0.0; }
+    get_discount(base.amount as f64, current.amount as f64)
+}
+
+// Searches the stores at once (results are listed in store order). Returns the results and the failed searches.
+pub async fn search_stores(stores: &[Box<dyn Storefront>], title: &str, client: &reqwest::Client) -> (Vec<StoreSearchResult>, Vec<String>) {
+    let searches = stores.iter().map(|store| async move { (store.id(), store.search(title, client).await) });
+    let mut results = Vec::new();
+    let mut failures = Vec::new();
+    for (store_id, result) in join_all(searches).await {
+        match result {
+            Ok(search_list) => results.extend(search_list.into_iter()
+                .take(MAX_SEARCH_RESULTS)
+                .map(|result| StoreSearchResult { store: store_id.to_string(), result })),
+            Err(e) => failures.push(format!("{}: {}", store_id, e)),
+        }
+    }
+    (results, failures)
+}
+
+// Picks the best result for the title if it scores at least min_score and no other result scores the same
+pub fn auto_select(title: &str, search_list: &[SearchResult], min_score: f64) -> Result<usize, InsertOutcome> {
+    let query = TitleQuery::new(title);
+    let mut scores : Vec<(usize, f64)> = search_list.iter().map(|result| query.score(&result.title)).enumerate().collect();
+    // Stable sort so the store order is kept for equal scores
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    match scores.as_slice() {
+        [] => Err(InsertOutcome::Skipped(String::from("No search results"))),
+        [(idx, score), rest @ ..] if *score >= min_score && rest.first().is_none_or(|(_, next)| next < score) => Ok(*idx),
+        _ => Err(InsertOutcome::Ambiguous(scores.iter()
+            .take(MAX_CANDIDATES)
+            .map(|(idx, _)| search_list[*idx].title.clone())
+            .collect())),
+    }
+}
+
+// Outcome for an add_* function in thresholds (false if the game was already tracked on the store or could not be added)
+pub fn get_add_outcome(added: bool, title: &str) -> InsertOutcome {
+    if added { InsertOutcome::Added(title.to_string()) }
+    else { InsertOutcome::Skipped(format!("\"{}\" is already tracked on this store or could not be added", title)) }
+}
+
+// Prompts for a search result (or picks one automatically when the selection mode is AutoMatch)
+pub fn select_search_result(store_name: &str, title: &str, search_list: &[SearchResult]) -> Result<usize, InsertOutcome> {
+    if let SelectionMode::AutoMatch(min_score) = get_selection_mode() {
+        let selection = auto_select(title, search_list, min_score);
+        match &selection {
+            Ok(idx) => println!("{}: matched \"{}\" to \"{}\"", store_name, title, search_list[*idx].title),
+            Err(InsertOutcome::Ambiguous(_)) => println!("{}: no confident match for \"{}\"", store_name, title),
+            Err(_) => (),
+        }
+        return selection;
+    }
+    println!("{} search results:", store_name);
+    for (idx, result) in search_list.iter().enumerate() {
+        match &result.price {
+            Some(price) => println!("  [{}] {} - {} {}", idx, result.title, price, price.currency),
+            None => println!("  [{}] {}", idx, result.title),
+        }
+    }
+    println!("  [q] SKIP");
+    let mut input = String::new();
+    print!("Type integer corresponding to game title or type \"q\" to quit: ");
+    let _ = io::stdout().flush();
+    io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read user input");
+    if input.trim() == "q" {
+        eprintln!("Request terminated.");
+        return Err(InsertOutcome::Skipped(String::from("Skipped by user")));
+    }
+    match input.trim().parse::<usize>() {
+        Ok(idx) => {
+            if idx < search_list.len() { return Ok(idx); }
+            eprintln!("Integer \"{}\" is invalid. Request terminated.", idx);
+        },
+        Err(e) => println!("Invalid input: {}\nError: {}", input, e)
+    }
+    Err(InsertOutcome::Skipped(format!("Invalid selection \"{}\"", input.trim())))
+}
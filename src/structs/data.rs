@@ -1,13 +1,265 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+
+// ISO 4217 currencies without minor units or with 3 decimals (every other currency uses 2 decimals)
+static ZERO_DECIMAL_CURRENCIES : [&str; 17] = ["BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG",
+                                                "RWF", "UGX", "UYI", "VND", "VUV", "XAF", "XOF", "XPF"];
+static THREE_DECIMAL_CURRENCIES : [&str; 7] = ["BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND"];
+// Languages that write prices with a decimal comma (e.g. "1.299,99" in de-DE)
+static DECIMAL_COMMA_LANGUAGES : [&str; 16] = ["cs", "da", "de", "es", "fi", "fr", "it", "nb", "nl", "pl", "pt", "ru",
+                                                "sk", "sv", "tr", "uk"];
 
 #[derive(Debug)]
 pub struct SaleInfo{
     pub icon_link: String,
     pub title: String,
-    pub original_price: String,
-    pub current_price: String,
-    pub discount_percentage: String,
+    pub original_price: Money,
+    pub current_price: Money,
+    // Whole percent (sent by the store or computed from the original and current price)
+    pub discount_percentage: f64,
     pub store_page_link: String,
+    pub deal: DealKind,
+    pub subscription: SubscriptionInfo,
+    // End of the sale (only set when the store reports it)
+    pub sale_ends_at: Option<DateTime<Utc>>,
+}
+
+impl SaleInfo {
+    pub fn currency(&self) -> &str { &self.current_price.currency }
+}
+
+// Price in the smallest unit of the currency (e.g. cents) so every store is rounded and compared the same way
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Money {
+    pub amount: i64,
+    // ISO 4217 code (empty when the store does not report one)
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new(amount: i64, currency: &str) -> Money {
+        Money { amount, currency: currency.to_string() }
+    }
+
+    // Number of decimals of the currency (e.g. 2 for USD, 0 for JPY and 3 for KWD)
+    pub fn get_decimals(currency: &str) -> u32 {
+        let currency = currency.to_uppercase();
+        if ZERO_DECIMAL_CURRENCIES.contains(&currency.as_str()) { 0 }
+        else if THREE_DECIMAL_CURRENCIES.contains(&currency.as_str()) { 3 }
+        else { 2 }
+    }
+
+    // Decimal separator used by the locale (e.g. ',' for "de-DE" and '.' for "en-US")
+    pub fn get_decimal_separator(locale: &str) -> char {
+        let language = locale.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+        if DECIMAL_COMMA_LANGUAGES.contains(&language.as_str()) { ',' } else { '.' }
+    }
+
+    // Rounds to the nearest minor unit (halves away from zero)
+    pub fn from_major(amount: f64, currency: &str) -> Money {
+        let scale = 10f64.powi(Money::get_decimals(currency) as i32);
+        Money::new((amount * scale).round() as i64, currency)
+    }
+
+    // Reads prices sent by the stores (e.g. "1,299.99", "49,99 €" or "$5") using the decimals of the currency.
+    // A separator that appears once is the decimal separator when the currency has decimals and no more digits
+    // than the currency's decimals follow it (so "1,299" is 1299 USD but 1.299 KWD); otherwise it separates thousands
+    pub fn parse(text: &str, currency: &str) -> Option<Money> {
+        let number : String = text.chars().filter(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-')).collect();
+        let decimal_separator = number.rfind(['.', ',']).and_then(|pos| {
+            let separator = number[pos..].chars().next()?;
+            let is_repeated = number.matches(separator).count() > 1;
+            let other_before = number[..pos].contains(|c: char| matches!(c, '.' | ',') && c != separator);
+            let digits = number.len() - pos - 1;
+            let is_decimal = other_before || (!is_repeated && digits <= Money::get_decimals(currency) as usize);
+            if is_decimal { Some(separator) } else { None }
+        });
+        Money::parse_number(&number, decimal_separator, currency)
+    }
+
+    // Reads prices formatted for display in the locale (e.g. "1.299,99 €" in de-DE), where only the locale's
+    // decimal separator can start the decimals
+    pub fn parse_localized(text: &str, currency: &str, locale: &str) -> Option<Money> {
+        let number : String = text.chars().filter(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-')).collect();
+        let separator = Money::get_decimal_separator(locale);
+        let decimal_separator = if Money::get_decimals(currency) > 0 && number.contains(separator) { Some(separator) } else { None };
+        Money::parse_number(&number, decimal_separator, currency)
+    }
+
+    // Digits, '-' and separators (every separator other than the decimal separator is dropped)
+    fn parse_number(number: &str, decimal_separator: Option<char>, currency: &str) -> Option<Money> {
+        let (whole, fraction) = match decimal_separator.and_then(|separator| number.rfind(separator)) {
+            Some(pos) => (&number[..pos], &number[pos + 1..]),
+            None => (number, ""),
+        };
+        let whole : String = whole.chars().filter(|c| c.is_ascii_digit() || *c == '-').collect();
+        if whole.is_empty() && fraction.is_empty() { return None; }
+        let amount = format!("{}.{}", whole, fraction).parse::<f64>().ok()?;
+        Some(Money::from_major(amount, currency))
+    }
+
+    pub fn to_major(&self) -> f64 {
+        self.amount as f64 / 10f64.powi(Money::get_decimals(&self.currency) as i32)
+    }
+
+    pub fn is_zero(&self) -> bool { self.amount == 0 }
+}
+
+// Prices in different currencies cannot be compared
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.currency == other.currency { Some(self.amount.cmp(&other.amount)) } else { None }
+    }
+}
+
+// Amount with the decimals of the currency (without the currency code)
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.*}", Money::get_decimals(&self.currency) as usize, self.to_major())
+    }
+}
+
+// Subscription details (only reported by the Microsoft Store for Game Pass)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubscriptionInfo {
+    // Game Pass plans the game is included with (e.g. "PC Game Pass", "Game Pass Ultimate")
+    pub included_with: Vec<String>,
+    // Lowest price for subscribers (never used as the current price)
+    pub members_price: Option<Money>,
+    // The only discount is for subscribers
+    pub members_only: bool,
+}
+
+impl SubscriptionInfo {
+    pub fn is_included(&self) -> bool { !self.included_with.is_empty() }
+
+    // Shown next to the price so subscriber prices are not mistaken for a public sale (empty without subscription details)
+    pub fn get_label(&self) -> String {
+        let mut labels : Vec<String> = Vec::new();
+        if self.is_included() { labels.push(format!("Included with {}", self.included_with.join(" and "))); }
+        if let Some(price) = &self.members_price {
+            let members_only_str = if self.members_only { " (members-only discount)" } else { "" };
+            labels.push(format!("Game Pass members: {}{}", price, members_only_str));
+        }
+        labels.join(", ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DealKind {
+    // Regular discount (or full price)
+    #[default]
+    Sale,
+    // Game given away for free for a limited time (e.g. Epic's weekly free games)
+    FreeGame,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct SearchResult {
+    pub id: String,
+    pub title: String,
+    // Base (full) price
+    #[serde(rename = "base_price")]
+    pub price: Option<Money>,
+    pub current_price: Option<Money>,
+    pub discount_percentage: Option<f64>,
+}
+
+// Search result listed by the search command
+#[derive(Serialize, Debug)]
+pub struct StoreSearchResult {
+    // Store ID
+    pub store: String,
+    #[serde(flatten)]
+    pub result: SearchResult,
+}
+
+// Result of adding a game on one store
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertOutcome {
+    // Threshold was added (or the store ID was linked to an existing threshold) for the matched title
+    Added(String),
+    // Nothing was added (reason)
+    Skipped(String),
+    // Results were found but none could be picked automatically (candidate titles)
+    Ambiguous(Vec<String>),
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct InsertReportEntry {
+    pub title: String,
+    pub store: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub matched: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub reason: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<String>,
+}
+
+// Printed as JSON after a non-interactive add or bulk insert
+#[derive(Serialize, Debug, Default)]
+pub struct InsertReport {
+    pub added: Vec<InsertReportEntry>,
+    pub skipped: Vec<InsertReportEntry>,
+    pub ambiguous: Vec<InsertReportEntry>,
+}
+
+impl InsertReport {
+    pub fn add(&mut self, title: &str, store: &str, outcome: InsertOutcome) {
+        let mut entry = InsertReportEntry {
+            title: title.to_string(),
+            store: store.to_string(),
+            matched: String::new(),
+            reason: String::new(),
+            candidates: Vec::new(),
+        };
+        match outcome {
+            InsertOutcome::Added(matched) => {
+                entry.matched = matched;
+                self.added.push(entry);
+            },
+            InsertOutcome::Skipped(reason) => {
+                entry.reason = reason;
+                self.skipped.push(entry);
+            },
+            InsertOutcome::Ambiguous(candidates) => {
+                entry.candidates = candidates;
+                self.ambiguous.push(entry);
+            },
+        }
+    }
+}
+
+// Price of a tracked game on one store (used by the compare command)
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StorePrice {
+    pub store_id: String,
+    pub store_name: String,
+    pub original_price: Money,
+    pub current_price: Money,
+    pub discount_percentage: f64,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct PriceComparison {
+    pub title: String,
+    // Currency of the threshold (prices in other currencies are listed but never the cheapest)
+    pub currency: String,
+    // Prices in store order
+    pub prices: Vec<StorePrice>,
+    pub failures: Vec<String>,
+}
+
+// Cheapest alert for a game with the prices on the other stores (used by best deal reports)
+#[derive(Debug)]
+pub struct BestDeal {
+    pub store_name: String,
+    pub sale: SaleInfo,
+    // Prices in store order (without the store of the best deal)
+    pub other_prices: Vec<StorePrice>,
 }
 
 #[derive(Debug)]
@@ -16,13 +268,225 @@ pub struct SimpleGameThreshold {
     pub price: f64,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(from = "SavedGameThreshold")]
 pub struct GameThreshold{
     pub title: String,
     pub alias: String,
     pub steam_id: usize,
+    // Whether steam_id is an app, package (sub ID) or bundle ID
+    #[serde(default)]
+    pub steam_type: SteamItemType,
     pub gog_id: usize,
+    // Check every edition of the GOG product and report the cheapest one
+    #[serde(default)]
+    pub gog_cheapest_edition: bool,
     pub microsoft_store_id: String,
+    #[serde(default)]
+    pub humble_bundle_id: String,
+    // Namespace and offer ID separated by ':'
+    #[serde(default)]
+    pub epic_id: String,
+    // Currency is empty for thresholds saved without one (the region currency is used)
+    pub desired_price: Money,
+    #[serde(default)]
+    pub rules: AlertRules,
+    // Country code (empty to use the region in settings)
+    #[serde(default)]
+    pub region: String,
+}
+
+// Prices saved before they were Money are numbers in the currency saved next to them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedMoney {
+    Money(Money),
+    Major(f64),
+}
+
+impl SavedMoney {
+    fn into_money(self, currency: &str) -> Money {
+        match self {
+            SavedMoney::Money(money) => money,
+            SavedMoney::Major(amount) => Money::from_major(amount, currency),
+        }
+    }
+}
+
+// Thresholds file (older files have the desired price as a number and a separate currency)
+#[derive(Deserialize)]
+struct SavedGameThreshold {
+    title: String,
+    alias: String,
+    steam_id: usize,
+    #[serde(default)]
+    steam_type: SteamItemType,
+    gog_id: usize,
+    #[serde(default)]
+    gog_cheapest_edition: bool,
+    microsoft_store_id: String,
+    #[serde(default)]
+    humble_bundle_id: String,
+    #[serde(default)]
+    epic_id: String,
+    #[serde(default)]
+    currency: String,
+    desired_price: SavedMoney,
+    #[serde(default)]
+    rules: AlertRules,
+    #[serde(default)]
+    region: String,
+}
+
+impl From<SavedGameThreshold> for GameThreshold {
+    fn from(saved: SavedGameThreshold) -> Self {
+        GameThreshold {
+            title: saved.title,
+            alias: saved.alias,
+            steam_id: saved.steam_id,
+            steam_type: saved.steam_type,
+            gog_id: saved.gog_id,
+            gog_cheapest_edition: saved.gog_cheapest_edition,
+            microsoft_store_id: saved.microsoft_store_id,
+            humble_bundle_id: saved.humble_bundle_id,
+            epic_id: saved.epic_id,
+            desired_price: saved.desired_price.into_money(&saved.currency),
+            rules: saved.rules,
+            region: saved.region,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SteamItemType {
+    #[default]
+    App,
+    Package,
+    Bundle,
+}
+
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub country_code: String,
     pub currency: String,
-    pub desired_price: f64,
-}
\ No newline at end of file
+    pub locale: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct StoreLimits {
+    // Maximum number of requests in flight at once
+    pub concurrency: usize,
+    // Average number of requests allowed per second (token bucket refill rate)
+    pub requests_per_second: f64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct AlertRules {
+    // Alert when the current price is at or below the desired price
+    pub use_desired_price: bool,
+    // Alert when the current price is at or below the lowest recorded price
+    pub all_time_low: bool,
+    // Alert when the discount percentage is at least this value
+    pub min_discount: Option<f64>,
+    // Alert when the current price is in the bottom X% of recorded prices
+    pub bottom_percentile: Option<f64>,
+    // Alert when the game is added to Game Pass (Microsoft Store only)
+    pub game_pass: bool,
+    // Alert once when a sale ends within thresholds::ENDING_SOON_HOURS
+    pub ending_soon: bool,
+    // Require every enabled rule to pass instead of any one of them
+    pub match_all: bool,
+}
+
+impl Default for AlertRules {
+    fn default() -> Self {
+        AlertRules {
+            use_desired_price: true,
+            all_time_low: false,
+            min_discount: None,
+            bottom_percentile: None,
+            game_pass: false,
+            ending_soon: false,
+            match_all: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(from = "SavedPriceRecord")]
+pub struct PriceRecord {
+    pub store_id: String,
+    pub timestamp: DateTime<Utc>,
+    // Currency is empty for prices recorded without one
+    pub original_price: Money,
+    pub current_price: Money,
+    pub discount_percentage: f64,
+    // Included with Game Pass when the price was recorded
+    #[serde(default)]
+    pub game_pass: bool,
+    #[serde(default)]
+    pub sale_ends_at: Option<DateTime<Utc>>,
+}
+
+impl PriceRecord {
+    pub fn currency(&self) -> &str { &self.current_price.currency }
+}
+
+// Price history file (older files have the prices as numbers and a separate currency)
+#[derive(Deserialize)]
+struct SavedPriceRecord {
+    store_id: String,
+    timestamp: DateTime<Utc>,
+    original_price: SavedMoney,
+    current_price: SavedMoney,
+    discount_percentage: f64,
+    #[serde(default)]
+    currency: String,
+    #[serde(default)]
+    game_pass: bool,
+    #[serde(default)]
+    sale_ends_at: Option<DateTime<Utc>>,
+}
+
+impl From<SavedPriceRecord> for PriceRecord {
+    fn from(saved: SavedPriceRecord) -> Self {
+        PriceRecord {
+            store_id: saved.store_id,
+            timestamp: saved.timestamp,
+            original_price: saved.original_price.into_money(&saved.currency),
+            current_price: saved.current_price.into_money(&saved.currency),
+            discount_percentage: saved.discount_percentage,
+            game_pass: saved.game_pass,
+            sale_ends_at: saved.sale_ends_at,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PriceHistory {
+    pub title: String,
+    pub records: Vec<PriceRecord>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SteamCacheSync {
+    // Start time of the last successful Steam app cache update or refresh
+    pub last_sync: DateTime<Utc>,
+}
+
+// HTTP response saved by the record transport and returned by the replay transport
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RecordedResponse {
+    pub url: String,
+    pub status: u16,
+    // Hand-written fixture (not a recorded response); recording a fixture again replaces it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub synthetic: bool,
+    // Stored as JSON when possible so fixtures are readable
+    pub body: serde_json::Value,
+    // Retry-After header of rate limited responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<String>,
+}
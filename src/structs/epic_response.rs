@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Offer {
+    pub title: String,
+    // Offer ID (unique within the namespace)
+    pub id: String,
+    pub namespace: String,
+    #[serde(rename = "productSlug", default)]
+    pub product_slug: Option<String>,
+    #[serde(rename = "urlSlug", default)]
+    pub url_slug: Option<String>,
+    #[serde(rename = "keyImages", default)]
+    pub key_images: Vec<KeyImage>,
+    #[serde(default)]
+    pub price: Option<OfferPrice>,
+    // Only sent by the free games promotions endpoint
+    #[serde(rename = "offerMappings", default)]
+    pub offer_mappings: Option<Vec<OfferMapping>>,
+    #[serde(default)]
+    pub promotions: Option<Promotions>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OfferMapping {
+    #[serde(rename = "pageSlug")]
+    pub page_slug: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Promotions {
+    // Promotions that are running now (upcoming promotions are ignored)
+    #[serde(rename = "promotionalOffers", default)]
+    pub promotional_offers: Vec<PromotionalOfferGroup>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PromotionalOfferGroup {
+    #[serde(rename = "promotionalOffers", default)]
+    pub promotional_offers: Vec<PromotionalOffer>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PromotionalOffer {
+    // RFC 3339 start and end time of the promotion
+    #[serde(rename = "startDate")]
+    pub start_date: String,
+    #[serde(rename = "endDate")]
+    pub end_date: String,
+    #[serde(rename = "discountSetting")]
+    pub discount_setting: DiscountSetting,
+}
+
+// Percentage of the original price that is paid (0 for free games)
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DiscountSetting {
+    #[serde(rename = "discountPercentage")]
+    pub discount_percentage: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct KeyImage {
+    #[serde(rename = "type")]
+    pub image_type: String,
+    pub url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OfferPrice {
+    #[serde(rename = "totalPrice")]
+    pub total_price: TotalPrice,
+    #[serde(rename = "lineOffers", default)]
+    pub line_offers: Vec<LineOffer>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct LineOffer {
+    // Discounts applied to the price
+    #[serde(rename = "appliedRules", default)]
+    pub applied_rules: Vec<AppliedRule>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AppliedRule {
+    // RFC 3339 end time of the discount
+    #[serde(rename = "endDate", default)]
+    pub end_date: Option<String>,
+}
+
+// Prices are integers in the smallest unit of the currency (see currency_info.decimals)
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TotalPrice {
+    #[serde(rename = "discountPrice")]
+    pub discount_price: i64,
+    #[serde(rename = "originalPrice")]
+    pub original_price: i64,
+    pub discount: i64,
+    #[serde(rename = "currencyCode")]
+    pub currency_code: String,
+    #[serde(rename = "currencyInfo")]
+    pub currency_info: CurrencyInfo,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CurrencyInfo {
+    pub decimals: u32,
+}
@@ -49,10 +49,11 @@ pub struct GameInfo {
     pub c_horizontal: String,
     #[serde(rename="storeLink")]
     pub store_link: String,
+    // Every edition of the product (each edition is a separate product with its own ID and price)
+    pub editions: Vec<Editions>,
     #[serde(rename="coverVertical")]
     c_vertical: String,
     developers: Vec<String>,
-    editions: Vec<Editions>,
     features: Vec<HashMap<String, String>>,
     genres: Vec<HashMap<String, String>>,
     #[serde(rename="operatingSystems")]
@@ -81,6 +82,7 @@ pub struct GameInfoBuilder {
 }
 
 impl GameInfoBuilder{
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(id_str: String, game_title: String,
                price_info: Price, icon_link: String,
                store_page_link: String) -> GameInfo {
@@ -148,8 +150,8 @@ pub struct FinalMoney {
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Editions{
-    id: u64,
+    pub id: u64,
     #[serde(rename="isRootEdition")]
-    is_root_edition: bool,
-    name: String,
+    pub is_root_edition: bool,
+    pub name: String,
 }
\ No newline at end of file
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ProductInfo {
+    #[serde(rename = "human_name")]
+    pub title: String,
+    // Unique product ID used by the store
+    pub machine_name: String,
+    // Store page slug
+    #[serde(default)]
+    pub human_url: String,
+    pub current_price: Option<Money>,
+    pub full_price: Option<Money>,
+    #[serde(rename = "featured_image_small", default)]
+    pub icon_link: String,
+    #[serde(default)]
+    pub delivery_methods: Vec<String>,
+    #[serde(default)]
+    pub platforms: Vec<String>,
+    // Unix timestamp of the end of the sale
+    #[serde(default)]
+    pub sale_end: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Money {
+    pub amount: f64,
+    pub currency: String,
+}
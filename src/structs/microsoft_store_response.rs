@@ -96,6 +96,7 @@ pub struct ProductInfoBuilder {
 }
 
 impl ProductInfoBuilder {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(id_str: String, game_title: String,
                price: PriceInfo, icon_link: String,
                store_page_link: String) -> ProductInfo {
@@ -261,7 +262,7 @@ pub struct GameInfo{
     allowed_platforms: Vec<String>,
     #[serde(rename = "productType")]
     product_type: String,
-    skus: Vec<SKU>,
+    pub skus: Vec<SKU>,
     #[serde(rename = "catalogSource")]
     catalog_source: String,
     #[serde(rename = "iconUrl")]
@@ -360,14 +361,14 @@ pub struct SkusSummary {
     #[serde(rename = "displayRP")]
     display_msrp: Option<String>,
     #[serde(rename = "salePrices")]
-    sale_prices: Option<Vec<SalePrice>>,
-    included_with: Option<HashMap<String, String>>,
+    pub sale_prices: Option<Vec<SalePrice>>,
+    pub included_with: Option<HashMap<String, String>>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct SalePrice {
-    conditions: Option<Conditions>,
-    price: f64,
+    pub conditions: Option<Conditions>,
+    pub price: f64,
     #[serde(rename = "displayPrice")]
     display_price: String,
     #[serde(rename = "badgeId")]
@@ -377,8 +378,11 @@ pub struct SalePrice {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Conditions {
     #[serde(rename = "type")]
-    condition_type: String,
-    id: Option<Vec<String>>,
+    pub condition_type: String,
+    pub id: Option<Vec<String>>,
+    // RFC 3339 end time of the sale price
+    #[serde(rename = "endDate")]
+    pub end_date: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -484,22 +488,25 @@ pub struct Recommended {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct SKU{
     #[serde(rename = "skuId")]
-    sku_id: String,
+    pub sku_id: String,
+    // Edition name (e.g. "Deluxe Edition"), missing for the default SKU
+    #[serde(default)]
+    pub title: Option<String>,
     actions: Vec<String>,
     #[serde(rename = "availabilityId")]
     availability_id: String,
-    price: f64,
+    pub price: f64,
     #[serde(rename = "displayPrice")]
     display_price: String,
     #[serde(rename = "fulfillmentData")]
     fulfillment_data: Option<String>,
     #[serde(rename = "skuType")]
-    sku_type: String,
-    msrp: Option<f64>,
+    pub sku_type: String,
+    pub msrp: Option<f64>,
     #[serde(rename = "displayMSRP")]
     display_msrp: Option<String>,
     #[serde(rename = "salePrices")]
-    sale_prices: Option<Vec<SalePrice>>,
+    pub sale_prices: Option<Vec<SalePrice>>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
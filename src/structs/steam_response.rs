@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct App{
     #[serde(rename = "appid")]
     pub app_id: usize,
@@ -9,9 +9,30 @@ pub struct App{
     pub price_change_number: i64,
 }
 
+// One page of GetAppList (fields are missing when no apps match)
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct AppListPage{
+    #[serde(default)]
+    pub apps: Vec<App>,
+    #[serde(default)]
+    pub have_more_results: bool,
+    #[serde(default)]
+    pub last_appid: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
 pub struct PriceOverview{
     pub currency: String,
     pub discount_percent: usize,
     pub initial: f64,
     pub final_price: f64,
-}
\ No newline at end of file
+}
+
+// Result from the store search ("type" is "app", "sub" or "bundle")
+#[derive(Deserialize, Serialize, Debug)]
+pub struct StoreItem{
+    #[serde(rename = "type")]
+    pub item_type: String,
+    pub name: String,
+    pub id: usize,
+}
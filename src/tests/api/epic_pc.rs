@@ -0,0 +1,93 @@
+#[cfg(test)]
+use chrono::{DateTime, Utc};
+use crate::data::DealKind;
+use crate::epic;
+use crate::settings;
+
+// Constants
+static GAME_TITLE: &str = "Hades";
+static GAME_ID: &str = "min:fb39bac8278a4126989f0fe12e7353af";
+static FREE_GAME_TITLE: &str = "Totally Reliable Delivery Service";
+// Placeholder ID used by the synthetic fixtures (replace with the real ID before recording, see fixtures/README.md)
+static FREE_GAME_ID: &str = "0c4b2bb3ef3c43e59e9d5b8a5ac8e2c4:8e2e7bd2b4f24b0a9b2b3c1c2b1f0a5e";
+
+#[tokio::test]
+async fn search_game() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    let search_list = epic::search_game_by_title(GAME_TITLE, &region, &client)
+        .await.unwrap_or_else(|_| Vec::new());
+    let mut is_game_present = false;
+    for offer in search_list {
+        if offer.title == GAME_TITLE {
+            is_game_present = true;
+            break;
+        }
+    }
+    assert!(is_game_present, "Could not find game: {}", GAME_TITLE);
+}
+
+#[tokio::test]
+async fn get_price_info() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    match epic::get_price_details(GAME_ID, &region, &client).await {
+        Ok(info) => {
+            assert_eq!(info.title, GAME_TITLE, "{} != {}", info.title, GAME_TITLE);
+            assert!(!info.currency().is_empty(), "Currency field is empty");
+            assert!(info.current_price <= info.original_price, "Current price {} is above the original price {}", info.current_price, info.original_price);
+            assert!((0.0..=100.0).contains(&info.discount_percentage), "Discount % {} is out of range", info.discount_percentage);
+            assert_ne!("", info.icon_link, "Icon link field is empty");
+            assert_ne!("", info.store_page_link, "Store page link field is empty");
+            assert_eq!(info.deal, DealKind::Sale);
+            let sale_ends_at = info.sale_ends_at.map(|ends_at| ends_at.to_rfc3339());
+            assert_eq!(Some(String::from("2026-10-23T15:00:00+00:00")), sale_ends_at, "Sale end should be read from the applied discount");
+        }
+        Err(e) => panic!("Game with id {} could not be checked: {}", GAME_ID, e),
+    }
+}
+
+#[tokio::test]
+async fn get_free_game_info() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    match epic::get_price_details(FREE_GAME_ID, &region, &client).await {
+        Ok(info) => {
+            assert_eq!(info.title, FREE_GAME_TITLE, "{} != {}", info.title, FREE_GAME_TITLE);
+            assert!(info.current_price.is_zero(), "Free game should cost 0 not {}", info.current_price);
+            assert_eq!(info.discount_percentage, 100.0);
+            assert_eq!(info.deal, DealKind::FreeGame);
+            assert!(info.sale_ends_at.is_some(), "Free game should have an end date");
+        }
+        Err(e) => panic!("Game with id {} could not be checked: {}", FREE_GAME_ID, e),
+    }
+}
+
+#[tokio::test]
+async fn get_free_games() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    let now = DateTime::parse_from_rfc3339("2026-10-18T12:00:00Z").unwrap().with_timezone(&Utc);
+    match epic::get_free_games(&region, now, &client).await {
+        Ok(free_games) => {
+            // Upcoming and ended promotions are not included
+            assert_eq!(1, free_games.len(), "Expected 1 free game not {}", free_games.len());
+            let (epic_id, info) = &free_games[0];
+            assert_eq!(FREE_GAME_ID, epic_id, "{} != {}", FREE_GAME_ID, epic_id);
+            assert_eq!(info.title, FREE_GAME_TITLE, "{} != {}", info.title, FREE_GAME_TITLE);
+            assert!(info.current_price.is_zero(), "Free game should cost 0 not {}", info.current_price);
+            assert_eq!(info.deal, DealKind::FreeGame);
+            let end_date = DateTime::parse_from_rfc3339("2026-10-22T15:00:00Z").unwrap().with_timezone(&Utc);
+            assert_eq!(Some(end_date), info.sale_ends_at, "Free game should end when the promotion ends");
+            assert!(info.store_page_link.ends_with("/p/totally-reliable-delivery-service"), "Unexpected store page link: {}", info.store_page_link);
+        }
+        Err(e) => panic!("Could not get the weekly free games: {}", e),
+    }
+}
+
+#[test]
+fn split_epic_id() {
+    assert_eq!(epic::split_epic_id(GAME_ID), Some(("min", "fb39bac8278a4126989f0fe12e7353af")));
+    assert_eq!(epic::split_epic_id("fb39bac8278a4126989f0fe12e7353af"), None);
+    assert_eq!(epic::split_epic_id(":fb39bac8278a4126989f0fe12e7353af"), None);
+}
@@ -1,14 +1,20 @@
 #[cfg(test)]
-use crate::gog;
+use crate::{gog, settings, storefront};
+use crate::error::StoreError;
 
 // Constants
 static GAME_TITLE: &str = "SILENT HILL f";
 static GAME_ID: usize = 1901861030;
+static REMOVED_GAME_ID: usize = 1;
+static EDITIONS_GAME_ID: usize = 1207664643;
+static CHEAPEST_EDITION_TITLE: &str = "The Witcher 3: Wild Hunt - Game of the Year Edition";
+static UNRELEASED_GAME_TITLE: &str = "Upcoming GOG Game";
 
 #[tokio::test]
 async fn search_game() {
     let client = reqwest::Client::new();
-    let search_list = gog::search_game_by_title_v2(GAME_TITLE, &client)
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    let search_list = gog::search_game_by_title_v2(GAME_TITLE, &region, &client)
         .await.unwrap_or_else(|_| Vec::new());
     let mut is_game_present = false;
     for game in search_list {
@@ -23,15 +29,69 @@ async fn search_game() {
 #[tokio::test]
 async fn get_price_info() {
     let client = reqwest::Client::new();
-    match gog::get_price_details_v2(GAME_TITLE, &client).await {
-        Some(info) => {
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    match gog::get_price_details_v2(GAME_TITLE, &region, &client).await {
+        Ok(info) => {
             assert_eq!(info.title, GAME_TITLE, "{} != {}", info.title, GAME_TITLE);
-            assert_ne!("", info.original_price, "Original price field is empty");
-            assert_ne!("", info.current_price, "Current price field is empty");
-            assert_ne!("", info.discount_percentage, "Discount % field is empty");
+            assert!(!info.currency().is_empty(), "Currency field is empty");
+            assert!(info.current_price <= info.original_price, "Current price {} is above the original price {}", info.current_price, info.original_price);
+            assert!((0.0..=100.0).contains(&info.discount_percentage), "Discount % {} is out of range", info.discount_percentage);
             assert_ne!("", info.icon_link, "Icon link field is empty");
             //assert_ne!("", info.store_page_link, "Store page link field is empty");
         }
-        None =>  assert!(false, "Game with id {} does not exist", GAME_ID),
+        Err(e) =>  panic!("Game with id {} does not exist\nError: {}", GAME_ID, e),
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn get_price_info_by_id() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    match gog::get_price_details_by_id(GAME_ID, &region, &client).await {
+        Ok(info) => {
+            assert_eq!(info.title, GAME_TITLE, "{} != {}", info.title, GAME_TITLE);
+            assert!(!info.currency().is_empty(), "Currency field is empty");
+            assert!(info.current_price <= info.original_price, "Current price {} is above the original price {}", info.current_price, info.original_price);
+            assert!((0.0..=100.0).contains(&info.discount_percentage), "Discount % {} is out of range", info.discount_percentage);
+            assert_ne!("", info.icon_link, "Icon link field is empty");
+            assert_ne!("", info.store_page_link, "Store page link field is empty");
+        }
+        Err(e) => panic!("Game with id {} could not be checked: {}", GAME_ID, e),
+    }
+}
+
+#[tokio::test]
+async fn get_price_info_removed_id() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    match gog::get_price_details_by_id(REMOVED_GAME_ID, &region, &client).await {
+        Err(StoreError::NotFound(_)) => (),
+        result => panic!("Game with id {} should not be found: {:?}", REMOVED_GAME_ID, result),
+    }
+}
+
+#[tokio::test]
+async fn get_cheapest_edition_price() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    match gog::get_cheapest_edition_price(EDITIONS_GAME_ID, &region, &client).await {
+        Ok(info) => {
+            assert_eq!(info.title, CHEAPEST_EDITION_TITLE, "{} != {}", info.title, CHEAPEST_EDITION_TITLE);
+            assert_eq!("9.99", info.current_price.to_string(), "Expected the price of the cheapest edition");
+            assert_eq!(80.0, info.discount_percentage, "Discount should be computed from the base and final price");
+        }
+        Err(e) => panic!("Editions of game with id {} could not be checked: {}", EDITIONS_GAME_ID, e),
+    }
+}
+
+#[tokio::test]
+async fn search_unreleased_game() {
+    let client = reqwest::Client::new();
+    let store = storefront::get_storefront(settings::GOG_STORE_ID).unwrap();
+    let search_list = store.search(UNRELEASED_GAME_TITLE, &client).await.unwrap_or_else(|e| panic!("GOG search failed: {}", e));
+    let game = search_list.first().unwrap_or_else(|| panic!("Could not find game: {}", UNRELEASED_GAME_TITLE));
+    // Games without a price should not be listed as free
+    assert_eq!(None, game.price, "Base price should not be set for {}", game.title);
+    assert_eq!(None, game.current_price, "Current price should not be set for {}", game.title);
+    assert_eq!(None, game.discount_percentage, "Discount should not be set for {}", game.title);
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+use crate::error::StoreError;
+use crate::humble_bundle;
+
+// Constants
+static GAME_TITLE: &str = "Hollow Knight";
+static GAME_ID: &str = "hollowknight_storefront";
+
+#[tokio::test]
+async fn search_game() {
+    let client = reqwest::Client::new();
+    let search_list = humble_bundle::search_game_by_title(GAME_TITLE, &client)
+        .await.unwrap_or_else(|_| Vec::new());
+    let mut is_game_present = false;
+    for product in search_list {
+        if product.title == GAME_TITLE {
+            is_game_present = true;
+            break;
+        }
+    }
+    assert!(is_game_present, "Could not find game: {}", GAME_TITLE);
+}
+
+#[tokio::test]
+async fn get_price_info() {
+    let client = reqwest::Client::new();
+    match humble_bundle::get_price_details(GAME_ID, &client).await {
+        Ok(info) => {
+            assert_eq!(info.title, GAME_TITLE, "{} != {}", info.title, GAME_TITLE);
+            assert!(!info.currency().is_empty(), "Currency field is empty");
+            assert!(info.current_price <= info.original_price, "Current price {} is above the original price {}", info.current_price, info.original_price);
+            assert!((0.0..=100.0).contains(&info.discount_percentage), "Discount % {} is out of range", info.discount_percentage);
+            assert_ne!("", info.icon_link, "Icon link field is empty");
+            assert_ne!("", info.store_page_link, "Store page link field is empty");
+        }
+        Err(e) => panic!("Game with id {} could not be checked: {}", GAME_ID, e),
+    }
+}
+
+#[tokio::test]
+async fn get_price_info_not_found() {
+    let client = reqwest::Client::new();
+    let invalid_id = "not_a_real_game_storefront";
+    match humble_bundle::get_price_details(invalid_id, &client).await {
+        Ok(info) => panic!("Game with id {} should not exist: {}", invalid_id, info.title),
+        Err(e) => assert!(matches!(e, StoreError::NotFound(_)), "Expected not found error not: {}", e),
+    }
+}
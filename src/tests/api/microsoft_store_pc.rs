@@ -1,14 +1,20 @@
 #[cfg(test)]
-use crate::microsoft_store;
+use crate::{microsoft_store, settings};
+use crate::data::Money;
 
 // Constants
 static GAME_TITLE: &str = "SILENT HILL f";
 static GAME_ID: &str = "9n5nfrqv2hqq";
+static GAME_PASS_ID: &str = "9ppt8k6gqhrz";
+// Placeholder add-on used by the synthetic fixtures (replace with a real add-on before recording, see fixtures/README.md)
+static ADD_ON_TITLE: &str = "SILENT HILL f - Bonus Costume Pack";
+static ADD_ON_ID: &str = "9ndlcshf0001";
 
 #[tokio::test]
 async fn search_game() {
     let client = reqwest::Client::new();
-    let search_list =  microsoft_store::search_game_by_title(GAME_TITLE, &client)
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    let search_list =  microsoft_store::search_game_by_title(GAME_TITLE, &region, &client)
         .await.unwrap_or_else(|_| Vec::new());
     let mut is_game_present = false;
     for product in search_list {
@@ -20,18 +26,88 @@ async fn search_game() {
     assert!(is_game_present, "Could not find game: {}", GAME_TITLE);
 }
 
+#[tokio::test]
+async fn search_add_ons() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    let search_list = microsoft_store::search_products(GAME_TITLE, true, &region, &client).await
+        .unwrap_or_else(|e| panic!("Could not search for {} add-ons\nError: {}", GAME_TITLE, e));
+    let titles : Vec<&str> = search_list.iter().map(|product| product.title.as_str()).collect();
+    assert!(titles.contains(&GAME_TITLE), "Could not find game: {}", GAME_TITLE);
+    assert!(titles.contains(&ADD_ON_TITLE), "Could not find add-on: {}", ADD_ON_TITLE);
+    // Games are listed first since they match the title best
+    assert_eq!(GAME_TITLE, titles[0], "{} should be the first result not {}", GAME_TITLE, titles[0]);
+}
+
+#[tokio::test]
+async fn get_add_on_price_info() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    let info = microsoft_store::get_price_details(ADD_ON_ID, &region, &client).await
+        .unwrap_or_else(|e| panic!("Add-on with id {} could not be checked: {}", ADD_ON_ID, e));
+    assert_eq!(ADD_ON_TITLE, info.title, "{} != {}", ADD_ON_TITLE, info.title);
+    assert_eq!(Money::new(999, "USD"), info.original_price);
+    assert_eq!(Money::new(799, "USD"), info.current_price);
+    assert_eq!(20.0, info.discount_percentage);
+}
+
 #[tokio::test]
 async fn get_price_info() {
     let client = reqwest::Client::new();
-    match microsoft_store::get_price_details(GAME_ID, &client).await {
-        Some(info) => {
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    match microsoft_store::get_price_details(GAME_ID, &region, &client).await {
+        Ok(info) => {
             assert_eq!(info.title, GAME_TITLE, "{} != {}", info.title, GAME_TITLE);
-            assert_ne!("", info.original_price, "Original price field is empty");
-            assert_ne!("", info.current_price, "Current price field is empty");
-            assert_ne!("", info.discount_percentage, "Discount % field is empty");
+            assert!(!info.currency().is_empty(), "Currency field is empty");
+            assert!(info.current_price <= info.original_price, "Current price {} is above the original price {}", info.current_price, info.original_price);
+            assert!((0.0..=100.0).contains(&info.discount_percentage), "Discount % {} is out of range", info.discount_percentage);
             assert_ne!("", info.icon_link, "Icon link field is empty");
             //assert_ne!("", info.store_page_link, "Store page link field is empty");
         }
-        None =>  assert!(false, "Game with id {} does not exist", GAME_ID),
+        Err(e) =>  panic!("Game with id {} does not exist\nError: {}", GAME_ID, e),
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn get_game_pass_info() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info("GB").unwrap();
+    let info = microsoft_store::get_price_details(GAME_PASS_ID, &region, &client).await
+        .unwrap_or_else(|e| panic!("Game with id {} could not be checked: {}", GAME_PASS_ID, e));
+    assert!(info.subscription.is_included(), "Game should be included with Game Pass");
+    assert_eq!(vec!["Game Pass Ultimate", "PC Game Pass"], info.subscription.included_with);
+    // The only discount is for subscribers so the public price is reported
+    assert!(info.subscription.members_only, "Discount should be members-only");
+    assert_eq!(Some(Money::new(3999, "GBP")), info.subscription.members_price);
+    assert_eq!("49.99", info.current_price.to_string(), "Members price should not be the current price");
+    assert_eq!(0.0, info.discount_percentage, "Members discount should not be the discount");
+    assert_eq!("Included with Game Pass Ultimate and PC Game Pass, Game Pass members: 39.99 (members-only discount)", info.subscription.get_label());
+    // Games outside Game Pass have no subscription details
+    let info = microsoft_store::get_price_details(GAME_ID, &settings::get_region_info(settings::DEFAULT_REGION).unwrap(), &client).await.unwrap();
+    assert!(!info.subscription.is_included(), "{} should not be included with Game Pass", GAME_TITLE);
+    assert_eq!("", info.subscription.get_label());
+}
+
+#[tokio::test]
+async fn get_sku_price_info() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info("GB").unwrap();
+    let sku_id = microsoft_store::format_microsoft_store_id(GAME_PASS_ID, Some("0010"));
+    let info = microsoft_store::get_price_details(&sku_id, &region, &client).await
+        .unwrap_or_else(|e| panic!("SKU {} could not be checked: {}", sku_id, e));
+    assert!(info.title.starts_with("Clair Obscur: Expedition 33 - "), "SKU title should follow the product title: {}", info.title);
+    assert_eq!("49.99", info.original_price.to_string());
+    assert_eq!("49.99", info.current_price.to_string());
+    assert!(info.subscription.is_included(), "Game Pass inclusion should be read from the product");
+    // Unknown SKUs are not found
+    let sku_id = microsoft_store::format_microsoft_store_id(GAME_PASS_ID, Some("9999"));
+    assert!(microsoft_store::get_price_details(&sku_id, &region, &client).await.is_err(), "SKU 9999 should not exist");
+}
+
+#[test]
+fn split_microsoft_store_id() {
+    assert_eq!((GAME_ID, None), microsoft_store::split_microsoft_store_id(GAME_ID));
+    assert_eq!((GAME_ID, Some("0010")), microsoft_store::split_microsoft_store_id(&format!("{}:0010", GAME_ID)));
+    assert_eq!((GAME_ID, None), microsoft_store::split_microsoft_store_id(&format!("{}:", GAME_ID)));
+    assert_eq!(GAME_ID, microsoft_store::format_microsoft_store_id(GAME_ID, None));
+}
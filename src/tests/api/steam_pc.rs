@@ -1,9 +1,18 @@
 #[cfg(test)]
-use crate::steam;
+use crate::{steam, settings, storefront};
+use crate::data::{GameThreshold, SteamItemType, StoreLimits};
+use crate::rate_limit::StoreLimiter;
+use crate::error::StoreError;
 
 // Constants
 static GAME_TITLE: &str = "Half-Life 2";
 static GAME_ID: usize = 220;
+static OTHER_GAME_ID: usize = 70;
+static PACKAGE_TITLE: &str = "The Orange Box";
+static PACKAGE_ID: usize = 469;
+static BUNDLE_TITLE: &str = "Valve Complete Pack";
+static BUNDLE_ID: usize = 232;
+static LAST_SYNC: i64 = 1750000000;
 
 #[tokio::test]
 async fn search_game() {
@@ -19,18 +28,207 @@ async fn search_game() {
     assert!(is_game_present, "Could not find game: {}", GAME_TITLE);
 }
 
+#[tokio::test]
+async fn search_with_prices() {
+    let client = reqwest::Client::new();
+    let store = storefront::get_storefront(settings::STEAM_STORE_ID).unwrap();
+    let search_list = store.search(GAME_TITLE, &client).await.unwrap_or_else(|e| panic!("Steam search failed: {}", e));
+    assert!(search_list.len() <= storefront::MAX_SEARCH_RESULTS, "Expected at most {} results", storefront::MAX_SEARCH_RESULTS);
+    let game = search_list.first().unwrap_or_else(|| panic!("Could not find game: {}", GAME_TITLE));
+    assert_eq!(GAME_ID.to_string(), game.id, "The best match should be {} not {}", GAME_TITLE, game.title);
+    assert!(game.price.is_some(), "Base price should be set for {}", game.title);
+    assert!(game.current_price.is_some(), "Current price should be set for {}", game.title);
+    assert!(game.discount_percentage.is_some(), "Discount should be set for {}", game.title);
+}
+
+#[tokio::test]
+async fn check_game_normalized_title() {
+    match steam::check_game("Clair Obscur Expedition 33").await {
+        Ok(app) => assert_eq!(1903340, app.app_id, "Expected app ID 1903340 not {}", app.app_id),
+        Err(e) => panic!("Normalized title should match the cached title: {}", e),
+    }
+}
+
 #[tokio::test]
 async fn get_price_info() {
     let client = reqwest::Client::new();
-    match steam::get_price_details(GAME_ID, &client).await {
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    match steam::get_price_details(GAME_ID, &region, &client).await {
         Ok(info) => {
             assert_eq!(info.title, GAME_TITLE, "{} != {}", info.title, GAME_TITLE);
-            assert_ne!("", info.original_price, "Original price field is empty");
-            assert_ne!("", info.current_price, "Current price field is empty");
-            assert_ne!("", info.discount_percentage, "Discount % field is empty");
+            assert!(!info.currency().is_empty(), "Currency field is empty");
+            assert!(info.current_price <= info.original_price, "Current price {} is above the original price {}", info.current_price, info.original_price);
+            assert!((0.0..=100.0).contains(&info.discount_percentage), "Discount % {} is out of range", info.discount_percentage);
             assert_ne!("", info.icon_link, "Icon link field is empty");
             //assert_ne!("", info.store_page_link, "Store page link field is empty");
         }
-        Err(e) =>  assert!(false, "Game with id {} does not exist\nError: {}", GAME_ID, e),
+        Err(e) =>  panic!("Game with id {} does not exist\nError: {}", GAME_ID, e),
+    }
+}
+#[tokio::test]
+async fn get_prices_bulk() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    let invalid_id : usize = 1;
+    let app_ids = [GAME_ID, OTHER_GAME_ID, invalid_id];
+    match steam::get_prices_bulk(&app_ids, &region, &client).await {
+        Ok(prices) => {
+            assert_eq!(app_ids.len(), prices.len(), "Expected {} prices not {}", app_ids.len(), prices.len());
+            for app_id in [GAME_ID, OTHER_GAME_ID] {
+                match &prices[&app_id] {
+                    Ok(overview) => assert_ne!("", overview.currency, "Currency field is empty for {}", app_id),
+                    Err(e) => panic!("Could not get price for {}\nError: {}", app_id, e),
+                }
+            }
+            assert!(prices[&invalid_id].is_err(), "App id {} should not have a price", invalid_id);
+        }
+        Err(e) => panic!("Bulk price request failed\nError: {}", e),
+    }
+}
+
+#[tokio::test]
+async fn get_price_details_bulk_shared_app() {
+    let client = reqwest::Client::new();
+    let store = storefront::get_storefront(settings::STEAM_STORE_ID).unwrap();
+    let limiter = StoreLimiter::new(&StoreLimits { concurrency: 2, requests_per_second: 1000.0 });
+    let invalid_id : usize = 1;
+    let threshold = |title: &str, steam_type: SteamItemType, steam_id: usize| GameThreshold {
+        title: title.to_string(),
+        steam_type,
+        steam_id,
+        region: String::from(settings::DEFAULT_REGION),
+        ..Default::default()
+    };
+    // Two thresholds track the same app (e.g. under different aliases)
+    let thresholds = [threshold(GAME_TITLE, SteamItemType::App, GAME_ID),
+                      threshold("Other Game", SteamItemType::App, OTHER_GAME_ID),
+                      threshold("HL2", SteamItemType::App, GAME_ID),
+                      threshold("Invalid Game", SteamItemType::App, invalid_id),
+                      threshold(PACKAGE_TITLE, SteamItemType::Package, PACKAGE_ID)];
+    let threshold_refs : Vec<&GameThreshold> = thresholds.iter().collect();
+    let results = store.get_price_details_bulk(&threshold_refs, &limiter, &client).await
+        .unwrap_or_else(|e| panic!("Bulk price request failed\nError: {}", e));
+    assert_eq!(thresholds.len(), results.len(), "Expected {} results not {}", thresholds.len(), results.len());
+    for (threshold, result) in thresholds.iter().zip(results.iter()) {
+        if threshold.steam_id == invalid_id {
+            assert!(result.is_err(), "App id {} should not have a price", invalid_id);
+            continue;
+        }
+        match result {
+            Ok(info) => assert_eq!(threshold.title, info.title, "{} != {}", threshold.title, info.title),
+            Err(e) => panic!("Could not get price for {}\nError: {}", threshold.title, e),
+        }
+    }
+}
+
+#[tokio::test]
+async fn get_price_details_bulk_failed_region() {
+    let client = reqwest::Client::new();
+    let store = storefront::get_storefront(settings::STEAM_STORE_ID).unwrap();
+    let limiter = StoreLimiter::new(&StoreLimits { concurrency: 2, requests_per_second: 1000.0 });
+    // No response is recorded for the JP request so it fails
+    let failed_region = "JP";
+    // Recorded together with the other US apps
+    let invalid_id : usize = 1;
+    let threshold = |title: &str, steam_type: SteamItemType, steam_id: usize, region: &str| GameThreshold {
+        title: title.to_string(),
+        steam_type,
+        steam_id,
+        region: region.to_string(),
+        ..Default::default()
+    };
+    let thresholds = [threshold(GAME_TITLE, SteamItemType::App, GAME_ID, settings::DEFAULT_REGION),
+                      threshold("Other Game", SteamItemType::App, OTHER_GAME_ID, settings::DEFAULT_REGION),
+                      threshold("Invalid Game", SteamItemType::App, invalid_id, settings::DEFAULT_REGION),
+                      threshold("HL2", SteamItemType::App, GAME_ID, failed_region),
+                      threshold(PACKAGE_TITLE, SteamItemType::Package, PACKAGE_ID, settings::DEFAULT_REGION)];
+    let threshold_refs : Vec<&GameThreshold> = thresholds.iter().collect();
+    let results = store.get_price_details_bulk(&threshold_refs, &limiter, &client).await
+        .unwrap_or_else(|e| panic!("A failed region should not fail the other prices\nError: {}", e));
+    for (threshold, result) in thresholds.iter().zip(results.iter()) {
+        if threshold.steam_id == invalid_id { continue; }
+        match result {
+            Ok(info) if threshold.region != failed_region => assert_eq!(threshold.title, info.title, "{} != {}", threshold.title, info.title),
+            Ok(_) => panic!("{} price should have failed", threshold.title),
+            Err(e) if threshold.region != failed_region => panic!("Could not get price for {}\nError: {}", threshold.title, e),
+            Err(_) => (),
+        }
+    }
+}
+
+#[tokio::test]
+async fn search_store() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    let search_list = steam::search_store("Orange Box", &region, &client)
+        .await.unwrap_or_else(|_| Vec::new());
+    let package = search_list.iter().find(|item| item.name == PACKAGE_TITLE);
+    match package {
+        Some(item) => {
+            assert_eq!(Some(SteamItemType::Package), steam::get_item_type(item), "{} should be a package", PACKAGE_TITLE);
+            assert_eq!(PACKAGE_ID, item.id, "Expected {} not {}", PACKAGE_ID, item.id);
+        },
+        None => panic!("Could not find package: {}", PACKAGE_TITLE),
+    }
+}
+
+#[tokio::test]
+async fn get_package_info() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    match steam::get_price_details_by_type(PACKAGE_ID, SteamItemType::Package, &region, &client).await {
+        Ok(info) => {
+            assert_eq!(info.title, PACKAGE_TITLE, "{} != {}", info.title, PACKAGE_TITLE);
+            assert_eq!("5.99", info.current_price.to_string(), "Expected the final package price");
+            assert_eq!(80.0, info.discount_percentage, "Expected the package discount");
+            assert_eq!("USD", info.currency(), "Expected the package currency");
+            assert!(info.store_page_link.ends_with("/sub/469"), "Unexpected store page: {}", info.store_page_link);
+        }
+        Err(e) => panic!("Package with id {} could not be checked\nError: {}", PACKAGE_ID, e),
+    }
+    match steam::get_package_details(1, &region, &client).await {
+        Err(StoreError::NotFound(_)) => (),
+        result => panic!("Package with id 1 should not be found: {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn get_bundle_info() {
+    let client = reqwest::Client::new();
+    let region = settings::get_region_info(settings::DEFAULT_REGION).unwrap();
+    match steam::get_price_details_by_type(BUNDLE_ID, SteamItemType::Bundle, &region, &client).await {
+        Ok(info) => {
+            assert_eq!(info.title, BUNDLE_TITLE, "{} != {}", info.title, BUNDLE_TITLE);
+            assert_eq!("13.99", info.current_price.to_string(), "Expected the final bundle price");
+            assert_eq!(90.0, info.discount_percentage, "Expected the bundle discount");
+            assert_eq!(region.currency, info.currency(), "Expected the region currency");
+            assert!(info.store_page_link.ends_with("/bundle/232"), "Unexpected store page: {}", info.store_page_link);
+        }
+        Err(e) => panic!("Bundle with id {} could not be checked\nError: {}", BUNDLE_ID, e),
+    }
+}
+
+#[test]
+fn item_ids() {
+    assert_eq!(Some((SteamItemType::App, GAME_ID)), steam::parse_item_id("220"));
+    assert_eq!(Some((SteamItemType::Package, PACKAGE_ID)), steam::parse_item_id("sub:469"));
+    assert_eq!(Some((SteamItemType::Bundle, BUNDLE_ID)), steam::parse_item_id("bundle:232"));
+    assert_eq!(None, steam::parse_item_id("app:220"));
+    assert_eq!(None, steam::parse_item_id("sub:abc"));
+    assert_eq!("sub:469", steam::format_item_id(SteamItemType::Package, PACKAGE_ID));
+    assert_eq!("220", steam::format_item_id(SteamItemType::App, GAME_ID));
+}
+
+#[tokio::test]
+async fn get_modified_app_list() {
+    let client = reqwest::Client::new();
+    // Fixtures split the modified apps across two pages
+    match steam::get_app_list(&client, 0, Some(LAST_SYNC)).await {
+        Ok(apps) => {
+            let app_ids : Vec<usize> = apps.iter().map(|app| app.app_id).collect();
+            assert_eq!(vec![GAME_ID, 3240220], app_ids, "Expected apps from both pages not {:?}", app_ids);
+        },
+        Err(e) => panic!("Could not get modified apps\nError: {}", e),
     }
-}
\ No newline at end of file
+}
+
@@ -1,15 +1,14 @@
 #[cfg(test)]
 use std::collections::HashMap;
-use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Command, Output};
 use regex::Regex;
-use dotenv::dotenv as dotenv_linux;
-use dotenvy::dotenv as dotenv_windows;
 
-use crate::data::{GameThreshold, SimpleGameThreshold};
+use crate::data::{GameThreshold, Money, SimpleGameThreshold};
 use crate::{json, csv, thresholds};
-use crate::settings::{self, GOG_STORE_ID, MICROSOFT_STORE_ID, STEAM_STORE_ID};
+use crate::http;
+use crate::tests::mock_server::MockServer;
+use crate::settings::{self, EPIC_STORE_ID, GOG_STORE_ID, HUMBLE_BUNDLE_STORE_ID, MICROSOFT_STORE_ID, STEAM_STORE_ID};
 
 // Sample Game Data IDs
 static E33_GAME_TITLE: &str = "Clair Obscur: Expedition 33";
@@ -59,8 +58,8 @@ fn add_threshold(alias: &str, title: &str, steam_id: usize, gog_id: usize, ms_id
         steam_id,
         gog_id,
         microsoft_store_id: String::from(ms_id),
-        currency: String::from("USD"),
-        desired_price: price,
+        desired_price: Money::from_major(price, "USD"),
+        ..Default::default()
     };
     let mut thresholds = load_thresholds();
     let mut unique = true;
@@ -75,20 +74,6 @@ fn add_threshold(alias: &str, title: &str, steam_id: usize, gog_id: usize, ms_id
     json::write_to_file(thresholds::get_path(), data_str);
 }
 
-fn get_sample_csv(filename: &str) -> String {
-    let thresholds = vec![
-        SimpleGameThreshold{ name: String::from("Hollow Knight"), price: 9.99 },
-        SimpleGameThreshold{ name: String::from("Stardew Valley"), price: 7.99 },
-    ];
-    if cfg!(target_os = "windows") { dotenv_windows().ok(); }
-    else if cfg!(target_os = "linux") { dotenv_linux().ok(); }
-    let test_path = std::env::var("TEST_PATH").unwrap_or(String::from("."));
-    let path_buf: PathBuf = [&test_path, "data", filename].iter().collect();
-    let csv_path = path_buf.display().to_string();
-    csv::generate_csv(&csv_path, thresholds);
-    csv_path
-}
-
 #[test]
 fn config_cmd() {
     clear_settings();
@@ -123,82 +108,11 @@ fn config_cmd() {
         else if store_name == GOG_STORE_ID { gog_present = true; }
         else if store_name == MICROSOFT_STORE_ID { ms_present = true; }
     }
-    assert_eq!(true, steam_present, "Steam should be a selected store");
-    assert_eq!(true, gog_present, "Gog should be a selected store");
-    assert_ne!(true, ms_present, "MSC should not be a selected store");
+    assert!(steam_present, "Steam should be a selected store");
+    assert!(gog_present, "Gog should be a selected store");
+    assert!(!ms_present, "MSC should not be a selected store");
     let are_aliases_enabled = load_alias_state();
-    assert_eq!(false, are_aliases_enabled, "Aliases should not be enabled in settings" );
-}
-
-//#[tokio::test]
-async fn add_cmd() {
-    clear_settings();
-    clear_thresholds();
-
-    // Check that add fails without config setup
-    let price_str = "19.99";
-    // let add_wo_config = Command::new("cargo")
-    //     .args(["run","--","add","-t",E33_GAME_TITLE,"-p",price_str,"-a","0","--test_flag"])
-    //     .output()
-    //     .expect("failed to execute proces");
-    //
-    // let config_err_msg = "Please configure which stores to query";
-    // let result_err = str::from_utf8(&add_wo_config.stderr).unwrap_or_default();
-    // assert!(result_err.contains(config_err_msg), "Code did not throw error {} for not having settings configured.", config_err_msg);
-
-    // Update settings
-    let _ = Command::new("cargo")
-        .args(["run","--","config","-a","-i","1","--test_flag"])
-        .output()
-        .expect("failed to execute proces");
-
-    // Add value
-    // let mut add_process = Command::new("cargo")
-    //     .args(["run","--","add","-t",E33_GAME_TITLE,"-p",price_str,"--test_flag"])
-    //     .stdin(Stdio::piped())
-    //     .stdout(Stdio::piped())
-    //     .spawn()
-    //     .expect("failed to execute process");
-    //
-    // let mut stdin = add_process.stdin.take().expect("failed to open stdin");
-    // let mut stdout = add_process.stdout.take().expect("failed to open stdout");
-    // stdin.write_all(b"0\n").unwrap();
-    //
-    // let mut output = Vec::new();
-    // stdout.read_to_end(&mut output).expect("Failed to read from stdout");
-    // println!("{:?}", output);
-
-    //let exit_status = add_process.wait().expect("Child process wasn't running");
-}
-
-//#[tokio::test]
-async fn bulk_insert_cmd() {
-    clear_settings();
-    clear_thresholds();
-    let filename = "bulk-insert-test.csv";
-    let csv_path = get_sample_csv(filename);
-
-    // Update settings
-    let _ = Command::new("cargo")
-        .args(["run","--","config","-a","-i","0","--test_flag"])
-        .output()
-        .expect("failed to execute proces");
-
-    // let bi_process = if cfg!(target_os = "windows") {
-    //     Command::new("cmd")
-    //         .args(["/C","cargo","run","--","bulk-insert","-f",&csv_path,"--test_flag"])
-    //         .stdin(Stdio::piped())
-    //         .stdout(Stdio::piped())
-    //         .spawn()
-    //         .expect("failed to execute process")
-    // } else {
-    //     Command::new("cargo")
-    //         .args(["run","--","bulk-insert","-f",&csv_path,"--test_flag"])
-    //         .stdin(Stdio::piped())
-    //         .stdout(Stdio::piped())
-    //         .spawn()
-    //         .expect("failed to execute process")
-    // };
+    assert!(!are_aliases_enabled, "Aliases should not be enabled in settings" );
 }
 
 #[test]
@@ -225,7 +139,7 @@ fn update_price_cmd() {
     let mut thresholds = load_thresholds();
     assert_eq!(1, thresholds.len(), "There should only be 1 threshold");
     assert_eq!(title, thresholds[0].title, "The game title should be {title} not {}", thresholds[0].title);
-    assert_eq!(new_price.parse::<f64>().unwrap(), thresholds[0].desired_price, "The desired price should be {} not {}", new_price, thresholds[0].desired_price);
+    assert_eq!(new_price.parse::<f64>().unwrap(), thresholds[0].desired_price.to_major(), "The desired price should be {} not {}", new_price, thresholds[0].desired_price);
 
     // update price using alias
     new_price = "34.99";
@@ -243,7 +157,7 @@ fn update_price_cmd() {
     thresholds = load_thresholds();
     assert_eq!(1, thresholds.len(), "There should only be 1 threshold");
     assert_eq!(alias, thresholds[0].alias, "The game alias should be {alias} not {}", thresholds[0].alias);
-    assert_eq!(new_price.parse::<f64>().unwrap(), thresholds[0].desired_price, "The desired price should be {} not {}", new_price, thresholds[0].desired_price);
+    assert_eq!(new_price.parse::<f64>().unwrap(), thresholds[0].desired_price.to_major(), "The desired price should be {} not {}", new_price, thresholds[0].desired_price);
 }
 
 #[test]
@@ -319,18 +233,19 @@ fn list_selected_stores_cmd() {
     for(_, [choice, store_name]) in re.captures_iter(output).map(|c| c.extract() ){
         results.push((choice, store_name));
     }
-    let expected = vec![
+    let expected = [
         (" ", settings::get_proper_store_name(STEAM_STORE_ID).unwrap()),
         (" ", settings::get_proper_store_name(GOG_STORE_ID).unwrap()),
         ("X", settings::get_proper_store_name(MICROSOFT_STORE_ID).unwrap()),
+        (" ", settings::get_proper_store_name(HUMBLE_BUNDLE_STORE_ID).unwrap()),
+        (" ", settings::get_proper_store_name(EPIC_STORE_ID).unwrap()),
     ];
     for result in results {
         let idx = expected.iter().position(|threshold| result.1 == threshold.1);
-        if !idx.is_none() {
-            let i = idx.unwrap();
+        if let Some(i) = idx {
             assert_eq!(expected[i].0, result.0, "The box for {} should be [{}] not [{}]", result.1, expected[i].0, result.0);
         } else{
-            assert!(false, "Something when wrong with option -> [{}] {}", result.0, result.1);
+            panic!("Something when wrong with option -> [{}] {}", result.0, result.1);
         }
     }
 }
@@ -361,70 +276,226 @@ fn list_thresholds_cmd() {
     for(_, [game_title, price]) in re.captures_iter(output).map(|c| c.extract() ){
         results.push((game_title, price));
     }
-    let expected = vec![
+    let expected = [
         (title, price),
     ];
     assert_eq!(expected[0].0, results[0].0, "The game title should be \'{}\' not \'{}\'", expected[0].0, results[0].0);
     assert_eq!(expected[0].1, results[0].1.parse::<f64>().unwrap(), "The game price should be \'{}\' not \'{}\'", expected[0].1, results[0].1);
 }
 
+fn get_games_by_store(output: &str) -> HashMap<String, Vec<String>> {
+    let store_names : Vec<String> = [STEAM_STORE_ID, GOG_STORE_ID, MICROSOFT_STORE_ID].iter()
+        .map(|id| settings::get_proper_store_name(id).unwrap())
+        .collect();
+    let mut games_by_store: HashMap<String, Vec<String>> = store_names.iter()
+        .map(|name| (name.clone(), Vec::new()))
+        .collect();
+    let re = Regex::new(PRICE_CHECK_PTRN).unwrap();
+    let mut curr_store = "";
+    for line in output.split("\n").skip(3) {
+        if let Some(store_name) = store_names.iter().find(|name| line.contains(name.as_str())) { curr_store = store_name; }
+        else if line.is_empty() { continue; }
+        else{
+            for(_, [game_title]) in re.captures_iter(line).map(|c| c.extract() ){
+                if let Some(games) = games_by_store.get_mut(curr_store){
+                    games.push(game_title.to_string());
+                }
+            }
+        }
+    }
+    games_by_store
+}
+
+fn check_e33_on_sale(games_by_store: &HashMap<String, Vec<String>>) {
+    for id in [STEAM_STORE_ID, GOG_STORE_ID, MICROSOFT_STORE_ID] {
+        let store_name = settings::get_proper_store_name(id).unwrap();
+        let games = games_by_store.get(&store_name).unwrap();
+        assert!(!games.is_empty(), "{} -> Game title should be {} but no games were found", store_name, E33_GAME_TITLE);
+        assert_eq!(E33_GAME_TITLE, games[0], "{} -> Game title should be {} not {}", store_name, E33_GAME_TITLE, games[0]);
+    }
+}
+
 #[tokio::test]
 async fn check_prices() {
     clear_thresholds();
     add_threshold("E33", E33_GAME_TITLE, E33_STEAM_ID, E33_GOG_ID, E33_MS_ID, 9999.99);
+    let fixtures_path = http::get_fixtures_path();
     let cp_out = if cfg!(target_os = "windows") {
         Command::new("cmd")
-            .args(["/C","cargo","run","--","--check-prices","--test_flag"])
+            .args(["/C","cargo","run","--","--check-prices","--offline","--test_flag"])
+            .env("FIXTURES_PATH", &fixtures_path)
             .output()
             .expect("failed to execute process")
     } else{
         Command::new("cargo")
-            .args(["run","--","--check-prices","--test_flag"])
+            .args(["run","--","--check-prices","--offline","--test_flag"])
+            .env("FIXTURES_PATH", &fixtures_path)
             .output()
             .expect("failed to execute process")
     };
     println!("{:?}", cp_out);
     let output = str::from_utf8(&cp_out.stdout).unwrap_or_default();
-    let lines = output.split("\n").collect::<Vec<&str>>();
-    let mut curr_store = "";
-    let mut games_by_store: HashMap<&str, Vec<&str>> = HashMap::new();
-    let steam_proper = settings::get_proper_store_name(STEAM_STORE_ID).unwrap();
-    games_by_store.insert(&steam_proper, Vec::new());
-    let gog_proper = settings::get_proper_store_name(GOG_STORE_ID).unwrap();
-    games_by_store.insert(&gog_proper, Vec::new());
-    let ms_proper = settings::get_proper_store_name(MICROSOFT_STORE_ID).unwrap();
-    games_by_store.insert(&ms_proper, Vec::new());
+    check_e33_on_sale(&get_games_by_store(output));
+}
 
-    let re = Regex::new(PRICE_CHECK_PTRN).unwrap();
+// Runs the binary with the given arguments and environment variables
+fn run_cli(args: &[&str], env: &[(String, String)]) -> Output {
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C","cargo","run","--"]);
+        cmd
+    } else {
+        let mut cmd = Command::new("cargo");
+        cmd.args(["run","--"]);
+        cmd
+    };
+    cmd.args(args)
+        .envs(env.iter().cloned())
+        .output()
+        .expect("failed to execute process")
+}
 
-    for i in 3..lines.len() {
-        if lines[i].contains(&steam_proper) { curr_store = &steam_proper; }
-        else if lines[i].contains(&gog_proper) { curr_store = &gog_proper; }
-        else if lines[i].contains(&ms_proper) { curr_store = &ms_proper; }
-        else if lines[i].is_empty() { continue; }
-        else{
-            for(_, [game_title]) in re.captures_iter(lines[i]).map(|c| c.extract() ){
-                if let Some(games) =games_by_store.get_mut(curr_store){
-                    games.push(&game_title);
-                }
-            }
-        }
-    }
-    //print!("{:?}", games_by_store);
+// Points every store at the mock server and saves data in the given directory
+fn get_mock_env(server: &MockServer, test_path: &Path) -> Vec<(String, String)> {
+    let mut env = server.get_env_vars();
+    env.push((String::from("TEST_PATH"), test_path.display().to_string()));
+    env
+}
+
+#[test]
+fn check_prices_mock_server() {
+    let server = MockServer::start();
+    // Separate data directory so other tests do not change the thresholds used here
+    let test_path: PathBuf = [std::env::temp_dir(), PathBuf::from("game_sales_scrapper_mock")].iter().collect();
+    let data_path = test_path.join("data");
+    let _ = std::fs::create_dir_all(&data_path);
+    let game_thresh = GameThreshold{
+        title: String::from(E33_GAME_TITLE),
+        alias: String::from("E33"),
+        steam_id: E33_STEAM_ID,
+        gog_id: E33_GOG_ID,
+        microsoft_store_id: String::from(E33_MS_ID),
+        desired_price: Money::from_major(9999.99, "USD"),
+        ..Default::default()
+    };
+    let data_str = serde_json::to_string_pretty(&vec![game_thresh]).unwrap();
+    json::write_to_file(data_path.join("thresholds.json").display().to_string(), data_str);
+    let mut env = get_mock_env(&server, &test_path);
+    env.push((String::from("FIXTURES_PATH"), data_path.join("fixtures").display().to_string()));
+    let cp_out = run_cli(&["--check-prices","--test_flag"], &env);
+    println!("{:?}", cp_out);
+    let output = str::from_utf8(&cp_out.stdout).unwrap_or_default();
+    check_e33_on_sale(&get_games_by_store(output));
+}
 
-    let expected = HashMap::from([
-        (steam_proper.as_str(), vec![E33_GAME_TITLE]),
-        (gog_proper.as_str(), vec![E33_GAME_TITLE]),
-        (ms_proper.as_str(), vec![E33_GAME_TITLE]),
+#[test]
+fn bulk_insert_non_interactive_mock_server() {
+    let server = MockServer::start();
+    // Separate data directory so other tests do not change the settings and thresholds used here
+    let test_path: PathBuf = [std::env::temp_dir(), PathBuf::from("game_sales_scrapper_bulk_insert")].iter().collect();
+    let data_path = test_path.join("data");
+    let _ = std::fs::remove_dir_all(&test_path);
+    let _ = std::fs::create_dir_all(&data_path);
+    let csv_path = data_path.join("bulk-insert-test.csv").display().to_string();
+    csv::generate_csv(&csv_path, vec![
+        SimpleGameThreshold{ name: String::from("Clair Obscur Expedition 33"), price: 29.99 },
+        SimpleGameThreshold{ name: String::from("Clair Obscur"), price: 29.99 },
+        SimpleGameThreshold{ name: String::from("Unknown Game Title"), price: 9.99 },
     ]);
+    let env = get_mock_env(&server, &test_path);
+    run_cli(&["config","-g","--test_flag"], &env);
+    let bi_out = run_cli(&["bulk-insert","-f",&csv_path,"--non-interactive","--test_flag"], &env);
+    println!("{:?}", bi_out);
+    let output = str::from_utf8(&bi_out.stdout).unwrap_or_default();
+    let report : serde_json::Value = serde_json::from_str(output.lines().last().unwrap_or_default())
+        .expect("The last line of output should be the JSON report");
+    assert_eq!(E33_GAME_TITLE, report["added"][0]["matched"], "Expected {} to be added: {}", E33_GAME_TITLE, report);
+    assert_eq!("Clair Obscur", report["ambiguous"][0]["title"], "Partial title should be ambiguous: {}", report);
+    assert_eq!("Unknown Game Title", report["skipped"][0]["title"], "Unknown title should be skipped: {}", report);
+
+    let data = std::fs::read_to_string(data_path.join("thresholds.json")).unwrap_or_default();
+    let thresholds : Vec<GameThreshold> = serde_json::from_str(&data).unwrap_or_default();
+    assert_eq!(1, thresholds.len(), "Only 1 game should be added not {}", thresholds.len());
+    assert_eq!(E33_GOG_ID, thresholds[0].gog_id, "Expected GOG ID {} not {}", E33_GOG_ID, thresholds[0].gog_id);
+}
 
-    let mut expected_title = expected.get(steam_proper.as_str()).unwrap()[0];
-    let mut actual_title = games_by_store.get(steam_proper.as_str()).unwrap()[0];
-    assert_eq!(expected_title, actual_title, "{} -> Game title should be {} not {}", steam_proper, expected_title, actual_title);
-    expected_title = expected.get(gog_proper.as_str()).unwrap()[0];
-    actual_title = games_by_store.get(gog_proper.as_str()).unwrap()[0];
-    assert_eq!(expected_title, actual_title, "{} -> Game title should be {} not {}", gog_proper, expected_title, actual_title);
-    expected_title = expected.get(ms_proper.as_str()).unwrap()[0];
-    actual_title = games_by_store.get(ms_proper.as_str()).unwrap()[0];
-    assert_eq!(expected_title, actual_title, "{} -> Game title should be {} not {}", ms_proper, expected_title, actual_title);
-}
\ No newline at end of file
+#[test]
+fn add_microsoft_store_id_mock_server() {
+    let server = MockServer::start();
+    let test_path: PathBuf = [std::env::temp_dir(), PathBuf::from("game_sales_scrapper_add_ms_id")].iter().collect();
+    let data_path = test_path.join("data");
+    let _ = std::fs::remove_dir_all(&test_path);
+    let _ = std::fs::create_dir_all(&data_path);
+    let env = get_mock_env(&server, &test_path);
+    run_cli(&["config","-m","--test_flag"], &env);
+    // Add-ons are tracked by their product ID without searching for a title
+    let add_out = run_cli(&["add","--microsoft-store-id","9ndlcshf0001","-p","5.99","--non-interactive","--test_flag"], &env);
+    println!("{:?}", add_out);
+    let output = str::from_utf8(&add_out.stdout).unwrap_or_default();
+    let report : serde_json::Value = serde_json::from_str(output.lines().last().unwrap_or_default())
+        .expect("The last line of output should be the JSON report");
+    assert_eq!("SILENT HILL f - Bonus Costume Pack", report["added"][0]["matched"], "Expected the add-on to be added: {}", report);
+
+    let data = std::fs::read_to_string(data_path.join("thresholds.json")).unwrap_or_default();
+    let thresholds : Vec<GameThreshold> = serde_json::from_str(&data).unwrap_or_default();
+    assert_eq!(1, thresholds.len(), "Only the add-on should be added not {}", thresholds.len());
+    assert_eq!("9NDLCSHF0001", thresholds[0].microsoft_store_id, "Expected the add-on product ID not {}", thresholds[0].microsoft_store_id);
+}
+
+#[test]
+fn search_mock_server() {
+    let server = MockServer::start();
+    let test_path: PathBuf = [std::env::temp_dir(), PathBuf::from("game_sales_scrapper_search")].iter().collect();
+    let _ = std::fs::remove_dir_all(&test_path);
+    let _ = std::fs::create_dir_all(test_path.join("data"));
+    let env = get_mock_env(&server, &test_path);
+    run_cli(&["config","-g","-m","--test_flag"], &env);
+    let search_out = run_cli(&["search","SILENT HILL f","--json","--test_flag"], &env);
+    println!("{:?}", search_out);
+    let output = str::from_utf8(&search_out.stdout).unwrap_or_default();
+    let results : Vec<serde_json::Value> = serde_json::from_str(output.trim()).expect("Search output should be JSON");
+    let stores : Vec<&str> = results.iter().filter_map(|result| result["store"].as_str()).collect();
+    assert_eq!(vec![GOG_STORE_ID, MICROSOFT_STORE_ID], stores, "Expected one result per store not {:?}", stores);
+    assert_eq!("SILENT HILL f", results[0]["title"]);
+    assert_eq!(6999, results[1]["base_price"]["amount"]);
+    assert_eq!("USD", results[1]["base_price"]["currency"]);
+    assert!(!results[0]["discount_percentage"].is_null(), "Discount should be set: {}", results[0]);
+
+    let data = std::fs::read_to_string(test_path.join("data").join("thresholds.json")).unwrap_or_default();
+    let thresholds : Vec<GameThreshold> = serde_json::from_str(&data).unwrap_or_default();
+    assert!(thresholds.is_empty(), "Search should not add thresholds");
+
+    let table_out = run_cli(&["search","SILENT HILL f","--test_flag"], &env);
+    let table = str::from_utf8(&table_out.stdout).unwrap_or_default();
+    assert!(table.contains("Store") && table.contains("9N5NFRQV2HQQ"), "Expected a table with the Microsoft Store ID:\n{}", table);
+}
+
+#[test]
+fn compare_mock_server() {
+    let server = MockServer::start();
+    let test_path: PathBuf = [std::env::temp_dir(), PathBuf::from("game_sales_scrapper_compare")].iter().collect();
+    let data_path = test_path.join("data");
+    let _ = std::fs::remove_dir_all(&test_path);
+    let _ = std::fs::create_dir_all(&data_path);
+    let game_thresh = GameThreshold{
+        title: String::from(E33_GAME_TITLE),
+        steam_id: E33_STEAM_ID,
+        gog_id: E33_GOG_ID,
+        microsoft_store_id: String::from(E33_MS_ID),
+        desired_price: Money::from_major(9.99, "USD"),
+        ..Default::default()
+    };
+    let data_str = serde_json::to_string_pretty(&vec![game_thresh]).unwrap();
+    json::write_to_file(data_path.join("thresholds.json").display().to_string(), data_str);
+    let env = get_mock_env(&server, &test_path);
+    let compare_out = run_cli(&["compare","--test_flag"], &env);
+    println!("{:?}", compare_out);
+    let output = str::from_utf8(&compare_out.stdout).unwrap_or_default();
+    let header = output.lines().find(|line| line.starts_with("Title")).unwrap_or_default();
+    for store_name in ["Steam", "GOG", "Microsoft Store"] {
+        assert!(header.contains(store_name), "Expected a {} column:\n{}", store_name, output);
+    }
+    let row = output.lines().find(|line| line.starts_with(E33_GAME_TITLE))
+        .unwrap_or_else(|| panic!("Expected a row for {}:\n{}", E33_GAME_TITLE, output));
+    assert!(row.contains('*'), "The cheapest store should be marked:\n{}", output);
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+use std::collections::HashMap;
+use std::fs::{self, read_to_string};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use reqwest::Url;
+use serde_json::Value;
+
+use crate::data::RecordedResponse;
+use crate::http;
+use crate::settings::{EPIC_PROMOTIONS_URL_KEY, EPIC_URL_KEY, GOG_CATALOG_URL_KEY, GOG_EMBED_URL_KEY, HUMBLE_BUNDLE_URL_KEY, MICROSOFT_STORE_URL_KEY,
+                      STEAM_API_URL_KEY, STEAM_STORE_URL_KEY};
+
+// Serves the recorded fixtures (matched on path and query) so the binary can be run against a local server
+pub struct MockServer {
+    pub base_url: String,
+}
+
+impl MockServer {
+    pub fn start() -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Could not start mock server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let responses = load_responses();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle_request(stream, &responses);
+            }
+        });
+        MockServer { base_url }
+    }
+
+    // Environment variables that point every store at the mock server
+    pub fn get_env_vars(&self) -> Vec<(String, String)> {
+        [STEAM_API_URL_KEY, STEAM_STORE_URL_KEY, GOG_CATALOG_URL_KEY, GOG_EMBED_URL_KEY,
+         MICROSOFT_STORE_URL_KEY, HUMBLE_BUNDLE_URL_KEY, EPIC_URL_KEY, EPIC_PROMOTIONS_URL_KEY]
+            .iter()
+            .map(|key| (format!("{}_BASE_URL", key.to_uppercase()), self.base_url.clone()))
+            .collect()
+    }
+}
+
+fn get_request_target(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+fn load_responses() -> HashMap<String, RecordedResponse> {
+    let mut responses = HashMap::new();
+    let entries = fs::read_dir(http::get_fixtures_path()).expect("Could not read fixtures");
+    for entry in entries.flatten() {
+        let data = read_to_string(entry.path()).unwrap_or_default();
+        if let Ok(response) = serde_json::from_str::<RecordedResponse>(&data) &&
+           let Ok(url) = Url::parse(&response.url) {
+            responses.insert(get_request_target(&url), response);
+        }
+    }
+    responses
+}
+
+fn handle_request(mut stream: TcpStream, responses: &HashMap<String, RecordedResponse>) {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() { return; }
+    // Skip headers (GET requests have no body)
+    let mut header = String::new();
+    while reader.read_line(&mut header).is_ok() && header != "\r\n" && !header.is_empty() {
+        header.clear();
+    }
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = match responses.get(target) {
+        Some(response) => {
+            let body = match &response.body {
+                Value::String(body) => body.clone(),
+                body => body.to_string(),
+            };
+            (response.status, body)
+        },
+        None => (404, String::from("{}")),
+    };
+    let resp = format!("HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                       status, body.len(), body);
+    let _ = stream.write_all(resp.as_bytes());
+}
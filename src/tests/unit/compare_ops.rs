@@ -0,0 +1,105 @@
+#[cfg(test)]
+use crate::compare;
+use crate::data::{DealKind, GameThreshold, Money, PriceComparison, SaleInfo, StorePrice, SubscriptionInfo};
+use crate::settings::{EPIC_STORE_ID, GOG_STORE_ID, HUMBLE_BUNDLE_STORE_ID, STEAM_STORE_ID};
+
+fn store_price(store_id: &str, original_price: f64, current_price: f64, currency: &str) -> StorePrice {
+    StorePrice {
+        store_id: store_id.to_string(),
+        store_name: store_id.to_string(),
+        original_price: Money::from_major(original_price, currency),
+        current_price: Money::from_major(current_price, currency),
+        discount_percentage: ((original_price - current_price) / original_price * 100.0).round(),
+    }
+}
+
+fn test_comparison() -> PriceComparison {
+    PriceComparison {
+        title: String::from("Clair Obscur: Expedition 33"),
+        currency: String::from("USD"),
+        prices: vec![
+            store_price(STEAM_STORE_ID, 49.99, 49.99, "USD"),
+            store_price(GOG_STORE_ID, 49.99, 39.99, "USD"),
+            // Cheaper but in another currency
+            store_price(HUMBLE_BUNDLE_STORE_ID, 49.99, 29.99, "EUR"),
+            store_price(EPIC_STORE_ID, 49.99, 39.99, "USD"),
+        ],
+        failures: Vec::new(),
+    }
+}
+
+#[test]
+fn get_cheapest() {
+    let comparison = test_comparison();
+    let cheapest = compare::get_cheapest(&comparison).unwrap();
+    assert_eq!(GOG_STORE_ID, cheapest.store_id, "Expected the first of the cheapest stores in the same currency not {}", cheapest.store_id);
+    assert!(compare::get_cheapest(&PriceComparison::default()).is_none());
+}
+
+#[test]
+fn get_savings() {
+    let comparison = test_comparison();
+    let steam = &comparison.prices[0];
+    let humble_bundle = &comparison.prices[2];
+    let savings = compare::get_savings(&comparison, steam).unwrap();
+    assert_eq!(Money::new(1000, "USD"), savings, "Expected 10.00 not {}", savings);
+    assert!(compare::get_savings(&comparison, humble_bundle).is_none(), "Prices in another currency should not be compared");
+    assert_eq!(Money::new(1000, "USD"), compare::get_max_savings(&comparison));
+}
+
+#[test]
+fn is_discounted() {
+    let mut comparison = test_comparison();
+    assert!(compare::is_discounted(&comparison));
+    comparison.prices.retain(|price| price.store_id == STEAM_STORE_ID);
+    assert!(!compare::is_discounted(&comparison), "Steam price is not discounted");
+}
+
+fn sale_info(title: &str, current_price: f64, deal: DealKind) -> SaleInfo {
+    SaleInfo {
+        icon_link: String::new(),
+        title: title.to_string(),
+        original_price: Money::from_major(49.99, "USD"),
+        current_price: Money::from_major(current_price, "USD"),
+        discount_percentage: 0.0,
+        store_page_link: String::new(),
+        deal,
+        subscription: SubscriptionInfo::default(),
+        sale_ends_at: None,
+    }
+}
+
+#[test]
+fn get_best_deals() {
+    let thresholds = vec![
+        GameThreshold { title: String::from("Hades"), ..Default::default() },
+        GameThreshold { title: String::from("Celeste"), ..Default::default() },
+        GameThreshold { title: String::from("Clair Obscur: Expedition 33"), ..Default::default() },
+    ];
+    let (hades, celeste, clair_obscur) = (0, 1, 2);
+    let alerts = vec![
+        (clair_obscur, String::from(STEAM_STORE_ID), sale_info("Clair Obscur: Expedition 33", 39.99, DealKind::Sale)),
+        (clair_obscur, String::from(GOG_STORE_ID), sale_info("Clair Obscur: Expedition 33", 34.99, DealKind::Sale)),
+        (clair_obscur, String::from(EPIC_STORE_ID), sale_info("Clair Obscur: Expedition 33", 34.99, DealKind::Sale)),
+        (hades, String::from(STEAM_STORE_ID), sale_info("Hades", 12.49, DealKind::Sale)),
+        (hades, String::from(EPIC_STORE_ID), sale_info("Hades", 0.0, DealKind::FreeGame)),
+    ];
+    let prices = vec![
+        (clair_obscur, store_price(STEAM_STORE_ID, 49.99, 39.99, "USD")),
+        (clair_obscur, store_price(GOG_STORE_ID, 49.99, 34.99, "USD")),
+        (clair_obscur, store_price(HUMBLE_BUNDLE_STORE_ID, 49.99, 44.99, "USD")),
+        (clair_obscur, store_price(EPIC_STORE_ID, 49.99, 34.99, "USD")),
+        (hades, store_price(STEAM_STORE_ID, 24.99, 12.49, "USD")),
+        (hades, store_price(EPIC_STORE_ID, 24.99, 0.0, "USD")),
+        (celeste, store_price(STEAM_STORE_ID, 19.99, 19.99, "USD")),
+    ];
+    let deals = compare::get_best_deals(&thresholds, alerts, &prices);
+    // Games without an alert are not reported and the rest are in threshold order
+    let titles : Vec<&str> = deals.iter().map(|deal| deal.sale.title.as_str()).collect();
+    assert_eq!(vec!["Hades", "Clair Obscur: Expedition 33"], titles);
+    assert_eq!(EPIC_STORE_ID, deals[0].store_name, "Free game should be the best deal");
+    assert_eq!(GOG_STORE_ID, deals[1].store_name, "Expected the first of the cheapest stores not {}", deals[1].store_name);
+    // Other stores are listed whether or not they met the threshold
+    let other_stores : Vec<&str> = deals[1].other_prices.iter().map(|price| price.store_id.as_str()).collect();
+    assert_eq!(vec![STEAM_STORE_ID, HUMBLE_BUNDLE_STORE_ID, EPIC_STORE_ID], other_stores);
+}
@@ -0,0 +1,135 @@
+#[cfg(test)]
+use chrono::Utc;
+use serde_json::json;
+use crate::history;
+use crate::thresholds;
+use crate::json as json_data;
+use crate::settings::{GOG_STORE_ID, STEAM_STORE_ID};
+use crate::data::{DealKind, GameThreshold, Money, PriceHistory, PriceRecord, SaleInfo, SubscriptionInfo};
+
+fn delete_history() {
+    json_data::delete_file(history::get_path());
+}
+
+fn add_simple_threshold(game_title: &str, game_alias: &str) {
+    let data = json!([GameThreshold {
+        title: game_title.to_string(),
+        alias: game_alias.to_string(),
+        steam_id: 123,
+        gog_id: 456,
+        microsoft_store_id: String::new(),
+        desired_price: Money::from_major(10.0, "USD"),
+        ..Default::default()
+    }]);
+    if let Ok(data) = serde_json::to_string(&data) {
+        json_data::write_to_file(thresholds::get_path(), data);
+    }
+}
+
+fn test_record(store_id: &str, price: f64) -> PriceRecord {
+    PriceRecord {
+        store_id: store_id.to_string(),
+        timestamp: Utc::now(),
+        original_price: Money::from_major(40.0, "USD"),
+        current_price: Money::from_major(price, "USD"),
+        discount_percentage: 0.0,
+        game_pass: false,
+        sale_ends_at: None,
+    }
+}
+
+#[test]
+fn create_record() {
+    let info = SaleInfo {
+        icon_link: String::new(),
+        title: String::from("Random Game"),
+        original_price: Money::from_major(39.99, "USD"),
+        current_price: Money::from_major(19.99, "USD"),
+        discount_percentage: 50.0,
+        store_page_link: String::new(),
+        deal: DealKind::Sale,
+        subscription: SubscriptionInfo::default(),
+        sale_ends_at: None,
+    };
+    let record = history::create_record(STEAM_STORE_ID, &info);
+    assert_eq!(STEAM_STORE_ID, record.store_id, "Store ID should be {} not {}", STEAM_STORE_ID, record.store_id);
+    assert_eq!(Money::new(3999, "USD"), record.original_price, "Original price should be 39.99 not {}", record.original_price);
+    assert_eq!(Money::new(1999, "USD"), record.current_price, "Current price should be 19.99 not {}", record.current_price);
+    assert_eq!(50.0, record.discount_percentage, "Discount should be 50 not {}", record.discount_percentage);
+}
+
+#[test]
+fn add_records() {
+    delete_history();
+    let game_title = String::from("Random Game");
+    add_simple_threshold(&game_title, "rg");
+    history::add_records(vec![
+        (game_title.clone(), test_record(STEAM_STORE_ID, 30.0)),
+        (game_title.clone(), test_record(GOG_STORE_ID, 20.0)),
+    ]);
+    history::add_records(vec![(game_title.clone(), test_record(STEAM_STORE_ID, 10.0))]);
+
+    // Check that records are grouped by title and can be found by alias
+    match history::get_history("rg") {
+        Some(game) => {
+            assert_eq!(game_title, game.title, "Title should be {} not {}", game_title, game.title);
+            assert_eq!(3, game.records.len(), "There should be 3 records not {}", game.records.len());
+        },
+        None => panic!("Could not find price history for {}", game_title)
+    }
+}
+
+#[test]
+fn add_records_keeps_every_check() {
+    delete_history();
+    let game_title = String::from("Random Game");
+    history::add_records(vec![(game_title.clone(), test_record(STEAM_STORE_ID, 30.0))]);
+    history::add_records(vec![
+        (game_title.clone(), test_record(STEAM_STORE_ID, 30.0)),
+        (game_title.clone(), test_record(GOG_STORE_ID, 30.0)),
+    ]);
+    let game = history::get_history(&game_title).unwrap();
+    assert_eq!(3, game.records.len(), "Unchanged Steam price should still be recorded not {} records", game.records.len());
+}
+
+#[test]
+fn load_saved_records() {
+    // Prices recorded before they were Money are numbers with a separate currency (which may be missing)
+    let data = r#"[{"title": "Random Game", "records": [
+        {"store_id": "steam", "timestamp": "2025-01-01T00:00:00Z", "original_price": 39.99, "current_price": 19.99, "discount_percentage": 50.0, "currency": "USD"},
+        {"store_id": "gog", "timestamp": "2025-01-02T00:00:00Z", "original_price": 39.99, "current_price": 29.99, "discount_percentage": 25.0}
+    ]}]"#;
+    let saved = serde_json::from_str::<Vec<PriceHistory>>(data).unwrap();
+    let records = &saved[0].records;
+    assert_eq!(Money::new(3999, "USD"), records[0].original_price);
+    assert_eq!(Money::new(1999, "USD"), records[0].current_price);
+    assert_eq!(Money::new(2999, ""), records[1].current_price);
+    assert_eq!("", records[1].currency());
+    let loaded = serde_json::from_str::<Vec<PriceHistory>>(&serde_json::to_string(&saved).unwrap()).unwrap();
+    assert_eq!(records[0].current_price, loaded[0].records[0].current_price);
+}
+
+#[test]
+fn all_time_low_and_average() {
+    delete_history();
+    let game_title = String::from("Random Game");
+    history::add_records(vec![
+        (game_title.clone(), test_record(STEAM_STORE_ID, 30.0)),
+        (game_title.clone(), test_record(GOG_STORE_ID, 15.0)),
+        (game_title.clone(), test_record(STEAM_STORE_ID, 45.0)),
+    ]);
+    let game = history::get_history(&game_title).unwrap();
+    let low = history::get_all_time_low(&game).unwrap();
+    assert_eq!(Money::new(1500, "USD"), low.current_price, "All-time low should be 15 not {}", low.current_price);
+    assert_eq!(GOG_STORE_ID, low.store_id, "All-time low should be from {} not {}", GOG_STORE_ID, low.store_id);
+    let average = history::get_average_price(&game).unwrap();
+    assert_eq!(30.0, average, "Average price should be 30 not {}", average);
+    // Prices recorded in another currency are left out
+    let mut record = test_record(GOG_STORE_ID, 5.0);
+    record.current_price = Money::from_major(5.0, "EUR");
+    history::add_records(vec![(game_title.clone(), record)]);
+    let game = history::get_records_in_currency(&history::get_history(&game_title).unwrap(), "USD");
+    assert_eq!(3, game.records.len(), "EUR price should not be in the USD records");
+    let low = history::get_all_time_low(&game).unwrap();
+    assert_eq!(Money::new(1500, "USD"), low.current_price, "All-time low should be 15 not {}", low.current_price);
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+use chrono::{Duration as ChronoDuration, Utc};
+use reqwest::Url;
+use std::time::Duration;
+use crate::error::StoreError;
+use crate::http::{self, Transport};
+
+#[test]
+fn get_fixture_url() {
+    let url = Url::parse("https://api.steampowered.com/IStoreService/GetAppList/v1/?key=secret&max_results=10&format=json").unwrap();
+    let fixture_url = http::get_fixture_url(&url);
+    assert!(!fixture_url.contains("secret"), "API key should not be in fixture url: {}", fixture_url);
+    assert_eq!("https://api.steampowered.com/IStoreService/GetAppList/v1/?max_results=10&format=json", fixture_url);
+}
+
+#[test]
+fn get_fixture_path() {
+    let url = "https://store.steampowered.com/api/appdetails?appids=220&cc=US";
+    let path = http::get_fixture_path(url);
+    assert_eq!(path, http::get_fixture_path(url), "Fixture path should be the same for the same url");
+    assert!(path.contains("store.steampowered.com_"), "Fixture path should contain the host: {}", path);
+    assert_ne!(path, http::get_fixture_path("https://store.steampowered.com/api/appdetails?appids=220&cc=GB"));
+}
+
+#[tokio::test]
+async fn replay_missing_fixture() {
+    // Only applies when replaying (e.g. not when recording with HTTP_TRANSPORT=record)
+    if http::get_transport() != Transport::Replay { return; }
+    let client = reqwest::Client::new();
+    let query = [("appids", "0")];
+    match http::get_text(&client, "https://store.steampowered.com/api/appdetails", &query).await {
+        Ok(_) => panic!("No response should be recorded for app id 0"),
+        Err(e) => assert!(matches!(e, StoreError::Network(_)), "Expected network error not {}", e),
+    }
+}
+
+#[test]
+fn get_retry_after() {
+    assert_eq!(Some(Duration::from_secs(120)), http::get_retry_after("120"));
+    // Check that HTTP dates are converted to the time left
+    let retry_at = (Utc::now() + ChronoDuration::seconds(60)).format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+    let wait = http::get_retry_after(&retry_at).unwrap();
+    assert!(wait > Duration::from_secs(50) && wait <= Duration::from_secs(60), "Wait should be about 60s not {:?}", wait);
+    // Check that dates in the past do not wait
+    assert_eq!(Some(Duration::ZERO), http::get_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"));
+    assert_eq!(None, http::get_retry_after("soon"));
+}
@@ -0,0 +1,109 @@
+#[cfg(test)]
+use serde_json::{json, Value};
+use std::fs::read_to_string;
+use crate::data::Money;
+use crate::error::StoreError;
+use crate::http;
+use crate::microsoft_store;
+use crate::microsoft_store_response::GameInfo;
+use crate::settings;
+
+static PDP_URL: &str = "https://apps.microsoft.com/api/pages/pdp?productId=9ppt8k6gqhrz&gl=GB&hl=en-GB";
+
+fn get_product_page() -> Value {
+    let data = read_to_string(http::get_fixture_path(PDP_URL)).unwrap();
+    serde_json::from_str::<Value>(&data).unwrap()["body"].take()
+}
+
+// Product page fixture with its SKUs replaced (the store page has a single SKU)
+fn get_game_info(skus: Value) -> GameInfo {
+    let mut body = get_product_page();
+    body["skus"] = skus;
+    serde_json::from_value::<GameInfo>(body).unwrap()
+}
+
+// Made-up SKUs: a standard SKU with Game Pass prices and a Deluxe Edition on sale
+fn get_test_skus() -> Value {
+    json!([
+        {
+            "skuId": "0010",
+            "actions": ["Purchase"],
+            "availabilityId": "TESTAVAIL001",
+            "price": 49.99,
+            "displayPrice": "£49.99",
+            "fulfillmentData": null,
+            "skuType": "full",
+            "msrp": 49.99,
+            "displayMSRP": "£49.99",
+            "salePrices": [
+                {
+                    "conditions": { "type": "subscription", "id": ["CFQ7TTC0KGQ8", "CFQ7TTC0KHS0"] },
+                    "price": 39.99,
+                    "displayPrice": "£39.99",
+                    "badgeId": "GamePassDiscount"
+                }
+            ]
+        },
+        {
+            "skuId": "0011",
+            "title": "Deluxe Edition",
+            "actions": ["Purchase"],
+            "availabilityId": "TESTAVAIL002",
+            "price": 47.99,
+            "displayPrice": "£47.99",
+            "fulfillmentData": null,
+            "skuType": "full",
+            "msrp": 59.99,
+            "displayMSRP": "£59.99",
+            "salePrices": [
+                {
+                    "conditions": { "type": "sale", "id": null, "endDate": "2030-01-01T07:59:59.9999999Z" },
+                    "price": 47.99,
+                    "displayPrice": "£47.99",
+                    "badgeId": "Sale"
+                }
+            ]
+        }
+    ])
+}
+
+#[test]
+fn get_sku_sale_info() {
+    let game = get_game_info(get_test_skus());
+    let region = settings::get_region_info("GB").unwrap();
+    let info = microsoft_store::get_sku_sale_info(&game, "0011", &region).unwrap();
+    assert_eq!("Clair Obscur: Expedition 33 - Deluxe Edition", info.title);
+    assert_eq!("59.99", info.original_price.to_string());
+    assert_eq!("47.99", info.current_price.to_string());
+    assert_eq!(20.0, info.discount_percentage);
+    assert!(info.sale_ends_at.is_some(), "Sale end should be read from the SKU sale price");
+    // Subscriber prices of the standard SKU are not its current price
+    let info = microsoft_store::get_sku_sale_info(&game, "0010", &region).unwrap();
+    assert_eq!("49.99", info.current_price.to_string());
+    assert_eq!(Some(Money::new(3999, "GBP")), info.subscription.members_price);
+    assert_eq!(None, info.sale_ends_at);
+    // Unknown SKUs are not found
+    match microsoft_store::get_sku_sale_info(&game, "9999", &region) {
+        Err(StoreError::NotFound(_)) => (),
+        other => panic!("SKU 9999 should not exist: {:?}", other.map(|info| info.title)),
+    }
+}
+
+#[test]
+fn missing_price() {
+    let region = settings::get_region_info("GB").unwrap();
+    let mut body = get_product_page();
+    body["priceInfo"]["price"] = Value::Null;
+    let game = serde_json::from_value::<GameInfo>(body).unwrap();
+    match microsoft_store::get_product_sale_info(&game, &region) {
+        Err(StoreError::NoPrice(_)) => (),
+        other => panic!("Product without a price should have no price: {:?}", other.map(|info| info.current_price)),
+    }
+    let mut skus = get_test_skus();
+    skus[1]["msrp"] = Value::Null;
+    let game = get_game_info(skus);
+    match microsoft_store::get_sku_sale_info(&game, "0011", &region) {
+        Err(StoreError::NoPrice(_)) => (),
+        other => panic!("SKU without an MSRP should have no price: {:?}", other.map(|info| info.current_price)),
+    }
+}
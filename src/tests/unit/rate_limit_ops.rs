@@ -0,0 +1,95 @@
+#[cfg(test)]
+use std::cell::Cell;
+use std::time::Duration;
+use crate::error::StoreError;
+use tokio::time::{self, Instant};
+use crate::rate_limit::{self, StoreLimiter, TokenBucket, MAX_RETRIES};
+use crate::data::StoreLimits;
+
+fn test_limits() -> StoreLimits {
+    StoreLimits { concurrency: 2, requests_per_second: 1000.0 }
+}
+
+#[test]
+fn token_bucket() {
+    let mut bucket = TokenBucket::new(2.0, 1.0);
+    assert!(bucket.try_take().is_ok(), "First token should be available");
+    assert!(bucket.try_take().is_ok(), "Second token should be available");
+    match bucket.try_take() {
+        Ok(()) => panic!("Bucket should be empty"),
+        Err(wait) => assert!(wait <= Duration::from_secs(1), "Wait should be at most 1s not {:?}", wait),
+    }
+}
+
+#[test]
+fn get_backoff() {
+    assert_eq!(Duration::from_secs(1), rate_limit::get_backoff(0));
+    assert_eq!(Duration::from_secs(2), rate_limit::get_backoff(1));
+    assert_eq!(Duration::from_secs(4), rate_limit::get_backoff(2));
+    // Check that backoff is capped
+    assert_eq!(Duration::from_secs(30), rate_limit::get_backoff(10));
+}
+
+#[tokio::test(start_paused = true)]
+async fn retry_when_rate_limited() {
+    let limiter = StoreLimiter::new(&test_limits());
+    let attempts = Cell::new(0);
+    let result = rate_limit::with_retry(&limiter, || {
+        attempts.set(attempts.get() + 1);
+        let attempt = attempts.get();
+        async move {
+            if attempt < 3 { Err(StoreError::RateLimited(String::from("429"), None)) }
+            else { Ok(attempt) }
+        }
+    }).await;
+    assert_eq!(3, result.unwrap(), "Request should succeed on the third attempt");
+}
+
+#[tokio::test(start_paused = true)]
+async fn retry_limit() {
+    let limiter = StoreLimiter::new(&test_limits());
+    let attempts = Cell::new(0);
+    let result : Result<(), StoreError> = rate_limit::with_retry(&limiter, || {
+        attempts.set(attempts.get() + 1);
+        async { Err(StoreError::RateLimited(String::from("429"), None)) }
+    }).await;
+    assert!(matches!(result, Err(StoreError::RateLimited(..))), "Request should still be rate limited");
+    assert_eq!(MAX_RETRIES + 1, attempts.get(), "Request should be attempted {} times", MAX_RETRIES + 1);
+    // Check that other errors are not retried
+    attempts.set(0);
+    let result : Result<(), StoreError> = rate_limit::with_retry(&limiter, || {
+        attempts.set(attempts.get() + 1);
+        async { Err(StoreError::NotFound(String::from("404"))) }
+    }).await;
+    assert!(matches!(result, Err(StoreError::NotFound(_))), "Request should not be found");
+    assert_eq!(1, attempts.get(), "Request should only be attempted once");
+}
+
+#[tokio::test(start_paused = true)]
+async fn retry_after() {
+    // Only one request slot so waiting with it held would block the other request
+    let limiter = StoreLimiter::new(&StoreLimits { concurrency: 1, requests_per_second: 1000.0 });
+    let attempts = Cell::new(0);
+    let start = Instant::now();
+    let retried = rate_limit::with_retry(&limiter, || {
+        attempts.set(attempts.get() + 1);
+        let attempt = attempts.get();
+        async move {
+            if attempt < 2 { Err(StoreError::RateLimited(String::from("429"), Some(Duration::from_secs(10)))) }
+            else { Ok(Instant::now()) }
+        }
+    });
+    let other = async {
+        // Starts once the first attempt has been rate limited
+        time::sleep(Duration::from_secs(1)).await;
+        let _permit = limiter.acquire().await;
+        Instant::now()
+    };
+    let (retried, other) = tokio::join!(retried, other);
+    let retried = retried.unwrap();
+    assert!(retried - start >= Duration::from_secs(10), "Retry should wait for Retry-After not {:?}", retried - start);
+    assert!(other - start < Duration::from_secs(10), "Request slot should be free while waiting to retry not after {:?}", other - start);
+    // Check that Retry-After is capped
+    assert_eq!(Duration::from_secs(300), rate_limit::get_retry_wait(0, Some(Duration::from_secs(3600))));
+    assert_eq!(rate_limit::get_backoff(2), rate_limit::get_retry_wait(2, None));
+}
@@ -1,8 +1,13 @@
 #[cfg(test)]
 use std::collections::HashMap;
+use serde_json::json;
+use crate::json as json_data;
+use crate::storefront;
 use crate::settings::{self, STEAM_STORE_ID, STEAM_STORE_NAME,
                           GOG_STORE_ID, GOG_STORE_NAME,
-                          MICROSOFT_STORE_ID, MICROSOFT_STORE_NAME};
+                          MICROSOFT_STORE_ID, MICROSOFT_STORE_NAME,
+                          HUMBLE_BUNDLE_STORE_ID, HUMBLE_BUNDLE_STORE_NAME,
+                          EPIC_STORE_ID, EPIC_STORE_NAME};
 
 // Constants
 static DEFAULT_ALIAS_STATE : bool = true;
@@ -22,13 +27,15 @@ fn get_available_stores() {
     for store in &available_stores {
         if store.as_str() != STEAM_STORE_ID &&
             store.as_str() != GOG_STORE_ID &&
-            store.as_str() != MICROSOFT_STORE_ID {
+            store.as_str() != MICROSOFT_STORE_ID &&
+            store.as_str() != HUMBLE_BUNDLE_STORE_ID &&
+            store.as_str() != EPIC_STORE_ID {
             invalid_store = store.as_str();
             all_stores_valid = false;
             break;
         }
     }
-    assert_eq!(true, all_stores_valid, "Could not find store: {}", invalid_store);
+    assert!(all_stores_valid, "Could not find store: {}", invalid_store);
     invalid_store = "fake_store";
     for store in available_stores {
         assert_ne!(store, invalid_store, "\'{}\' should not be a valid store", invalid_store);
@@ -44,6 +51,10 @@ fn get_proper_store_name() {
     assert_eq!(GOG_STORE_NAME, store_name, "{} != {}", GOG_STORE_NAME, store_name);
     store_name = settings::get_proper_store_name(MICROSOFT_STORE_ID).unwrap();
     assert_eq!(MICROSOFT_STORE_NAME, store_name, "{} != {}", MICROSOFT_STORE_NAME, store_name);
+    store_name = settings::get_proper_store_name(HUMBLE_BUNDLE_STORE_ID).unwrap();
+    assert_eq!(HUMBLE_BUNDLE_STORE_NAME, store_name, "{} != {}", HUMBLE_BUNDLE_STORE_NAME, store_name);
+    store_name = settings::get_proper_store_name(EPIC_STORE_ID).unwrap();
+    assert_eq!(EPIC_STORE_NAME, store_name, "{} != {}", EPIC_STORE_NAME, store_name);
     // Test invalid store id
     store_name = settings::get_proper_store_name("fake_store").unwrap_or_default();
     assert_eq!("", store_name, "\'{}\' is not a valid store id", store_name);
@@ -64,16 +75,16 @@ fn get_selected_stores() {
         else if store == GOG_STORE_ID { is_gog_selected = true }
         if store == MICROSOFT_STORE_ID { is_ms_store_selected = true }
     }
-    assert_eq!(true, is_steam_selected, "{} should be selected", STEAM_STORE_ID);
-    assert_eq!(true, is_gog_selected, "{} should be selected", GOG_STORE_ID);
-    assert_eq!(false, is_ms_store_selected, "{} should not be selected", MICROSOFT_STORE_ID);
+    assert!(is_steam_selected, "{} should be selected", STEAM_STORE_ID);
+    assert!(is_gog_selected, "{} should be selected", GOG_STORE_ID);
+    assert!(!is_ms_store_selected, "{} should not be selected", MICROSOFT_STORE_ID);
 }
 
 #[test]
 fn get_alias_state() {
     default_settings();
     let are_aliases_enabled = settings::get_alias_state();
-    assert_eq!(true, are_aliases_enabled, "Aliases should be enabled.");
+    assert!(are_aliases_enabled, "Aliases should be enabled.");
 }
 
 #[test]
@@ -117,13 +128,77 @@ fn update_alias_state(){
     // Check that aliases are disabled
     settings::update_alias_state(ALIAS_DISABLED);
     are_aliases_enabled = settings::get_alias_state();
-    assert_eq!(false, are_aliases_enabled, "Aliases should not be enabled.");
+    assert!(!are_aliases_enabled, "Aliases should not be enabled.");
     // Check that behavior of values that are not 1 or 0
     for i in -10..10 {
         if i != ALIAS_ENABLED && i != ALIAS_DISABLED {
             settings::update_alias_state(i);
             let are_aliases_enabled = settings::get_alias_state();
-            assert_eq!(false, are_aliases_enabled, "Aliases should not be enabled given input: {}.", i);
+            assert!(!are_aliases_enabled, "Aliases should not be enabled given input: {}.", i);
         }
     }
-}
\ No newline at end of file
+}
+#[test]
+fn update_best_deal_state(){
+    settings::update_best_deal_state(1);
+    assert!(settings::get_best_deal_state(), "Best deal reports should be enabled.");
+    // Check that values that are not 1 or 0 disable best deal reports
+    settings::update_best_deal_state(5);
+    assert!(!settings::get_best_deal_state(), "Best deal reports should not be enabled given input: 5.");
+    settings::update_best_deal_state(0);
+    assert!(!settings::get_best_deal_state(), "Best deal reports should be disabled.");
+}
+#[test]
+fn update_region() {
+    settings::update_region(settings::DEFAULT_REGION);
+    let mut region = settings::get_region();
+    assert_eq!("USD", region.currency, "Currency should be USD not {}", region.currency);
+    // Check that country code is case insensitive
+    settings::update_region("de");
+    region = settings::get_region();
+    assert_eq!("DE", region.country_code, "Region should be DE not {}", region.country_code);
+    assert_eq!("EUR", region.currency, "Currency should be EUR not {}", region.currency);
+    // Check that unsupported regions are ignored
+    settings::update_region("XX");
+    region = settings::get_region();
+    assert_eq!("DE", region.country_code, "Region should still be DE not {}", region.country_code);
+    settings::update_region(settings::DEFAULT_REGION);
+}
+#[test]
+fn update_store_limits() {
+    // Check that unsupported stores have no limits
+    assert!(settings::get_store_limits("fake_store").is_none(), "\'fake_store\' should not have limits");
+    settings::update_store_limits(GOG_STORE_ID, Some(3), Some(1.5));
+    let mut limits = settings::get_store_limits(GOG_STORE_ID).unwrap();
+    assert_eq!(3, limits.concurrency, "Concurrency should be 3 not {}", limits.concurrency);
+    assert_eq!(1.5, limits.requests_per_second, "Rate should be 1.5 not {}", limits.requests_per_second);
+    // Check that only the given limit is changed
+    settings::update_store_limits(GOG_STORE_ID, Some(5), None);
+    limits = settings::get_store_limits(GOG_STORE_ID).unwrap();
+    assert_eq!(5, limits.concurrency, "Concurrency should be 5 not {}", limits.concurrency);
+    assert_eq!(1.5, limits.requests_per_second, "Rate should still be 1.5 not {}", limits.requests_per_second);
+    // Check that invalid limits are ignored
+    settings::update_store_limits(GOG_STORE_ID, Some(0), None);
+    settings::update_store_limits(GOG_STORE_ID, None, Some(-1.0));
+    limits = settings::get_store_limits(GOG_STORE_ID).unwrap();
+    assert_eq!(5, limits.concurrency, "Concurrency should still be 5 not {}", limits.concurrency);
+    assert_eq!(1.5, limits.requests_per_second, "Rate should still be 1.5 not {}", limits.requests_per_second);
+    // Check that invalid limits saved in settings fall back to the store defaults
+    let defaults = storefront::get_storefront(GOG_STORE_ID).unwrap().default_limits();
+    for saved in [json!({"concurrency": 0, "requests_per_second": 1.5}), json!({"concurrency": 5, "requests_per_second": 0.0}),
+                  json!({"concurrency": 5, "requests_per_second": -1.0})] {
+        let mut data = settings::load_data().unwrap();
+        data["store_limits"][GOG_STORE_ID] = saved.clone();
+        json_data::write_to_file(settings::get_path(), serde_json::to_string_pretty(&data).unwrap());
+        limits = settings::get_store_limits(GOG_STORE_ID).unwrap();
+        assert_eq!(defaults.concurrency, limits.concurrency, "Concurrency should be the default for {}", saved);
+        assert_eq!(defaults.requests_per_second, limits.requests_per_second, "Rate should be the default for {}", saved);
+    }
+    settings::update_store_limits(GOG_STORE_ID, Some(settings::DEFAULT_CONCURRENCY), Some(settings::DEFAULT_REQUESTS_PER_SECOND));
+}
+#[test]
+fn get_base_url() {
+    // Check that the default is used when no override is set
+    let base_url = settings::get_base_url("unused_store", "https://example.com/");
+    assert_eq!("https://example.com", base_url, "Base URL should be https://example.com not {}", base_url);
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+use std::env;
+use std::fs;
+use std::path::Path;
+use crate::steam;
+use crate::steam_cache::{self, AppCache};
+use crate::steam_response::App;
+
+fn test_app(app_id: usize, name: &str, last_modified: i64, price_change_number: i64) -> App {
+    App { app_id, name: name.to_string(), last_modified, price_change_number }
+}
+
+fn test_cache() -> AppCache {
+    AppCache::from_apps(vec![
+        test_app(70, "Half-Life", 1745368572, 27681063),
+        test_app(220, "Half-Life 2", 1745368567, 27681063),
+        test_app(380, "Half-Life 2: Episode One", 1745368595, 27681063),
+        test_app(400, "Portal", 1745368600, 27681063),
+        test_app(1, "", 1745368600, 27681063),
+    ])
+}
+
+fn get_temp_dir(name: &str) -> String {
+    let path = env::temp_dir().join(format!("game_sales_scrapper_{}", name));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+    path.display().to_string()
+}
+
+#[test]
+fn find_by_name() {
+    let cache = test_cache();
+    assert_eq!(4, cache.len(), "Apps without a name should not be cached");
+    assert_eq!(Some(220), cache.find_by_name("Half-Life 2").map(|app| app.app_id));
+    assert!(cache.find_by_name("half-life 2").is_none(), "Exact lookup should be case sensitive");
+    assert_eq!(400, cache.last_appid(), "Expected the highest app ID");
+}
+
+#[test]
+fn search_prefix() {
+    let cache = test_cache();
+    let names : Vec<&str> = cache.search_prefix("half-life 2").iter().map(|app| app.name.as_str()).collect();
+    assert_eq!(vec!["Half-Life 2", "Half-Life 2: Episode One"], names);
+    assert!(cache.search_prefix("Episode").is_empty(), "Only titles starting with the prefix should match");
+}
+
+#[test]
+fn add_new_and_merge() {
+    let mut cache = test_cache();
+    let added = cache.add_new(vec![test_app(220, "Half-Life 2 (Updated)", 1755000000, 29500000), test_app(420, "Half-Life 2: Episode Two", 1745368606, 27681063)]);
+    assert_eq!(1, added, "Only new apps should be added");
+    assert_eq!("Half-Life 2", cache.get(220).unwrap().name, "Cached apps should not be changed");
+
+    let (added, updated) = cache.merge(vec![
+        test_app(220, "Half-Life 2 (Updated)", 1755000000, 29500000),
+        test_app(70, "Half-Life", 1745368572, 27681063),
+        test_app(3240220, "Grand Theft Auto V Enhanced", 1756000000, 29600000),
+    ]);
+    assert_eq!((1, 1), (added, updated), "Expected 1 added and 1 updated app not {} and {}", added, updated);
+    assert_eq!(29500000, cache.get(220).unwrap().price_change_number, "Price change number should be updated");
+    assert!(cache.find_by_name("Half-Life 2").is_none(), "Old name should be removed from the index");
+    assert_eq!(Some(220), cache.find_by_name("Half-Life 2 (Updated)").map(|app| app.app_id));
+    assert_eq!(1, cache.search_prefix("grand theft").len(), "New app should be in the prefix index");
+}
+
+#[test]
+fn shared_names() {
+    let mut cache = test_cache();
+    cache.add_new(vec![test_app(1000, "Portal", 1745368600, 27681063), test_app(2000, "Half Life 2", 1745368600, 27681063)]);
+    assert_eq!(Some(400), cache.find_by_name("Portal").map(|app| app.app_id), "The lowest app ID should be used for shared names");
+    assert_eq!(Some(220), cache.find_by_title("Half Life 2!").map(|app| app.app_id), "The lowest app ID should be used for shared normalized names");
+    // Check that the other app is found once the shared name is changed
+    cache.merge(vec![test_app(400, "Portal (Legacy)", 1755000000, 29500000), test_app(220, "Half-Life 2 (Legacy)", 1755000000, 29500000)]);
+    assert_eq!(Some(1000), cache.find_by_name("Portal").map(|app| app.app_id));
+    assert_eq!(Some(2000), cache.find_by_title("Half Life 2!").map(|app| app.app_id));
+    assert_eq!(Some(400), cache.find_by_title("Portal Legacy").map(|app| app.app_id), "Renamed app should be in the normalized index");
+}
+
+#[test]
+fn write_and_read_cache() {
+    let dir = get_temp_dir("steam_cache_rw");
+    let path = Path::new(&dir).join("steam_app_cache.json").display().to_string();
+    let cache = test_cache();
+    steam_cache::write_cache(&path, &cache).unwrap();
+    let loaded = steam_cache::read_cache(&path).unwrap();
+    assert_eq!(cache.len(), loaded.len(), "Expected {} apps not {}", cache.len(), loaded.len());
+    assert_eq!(cache.get(380), loaded.get(380));
+}
+
+#[test]
+fn migrate_legacy_cache() {
+    let dir = get_temp_dir("steam_cache_migration");
+    let legacy_path = Path::new(&dir).join("steam_game_titles_cache.json").display().to_string();
+    let path = Path::new(&dir).join("steam_app_cache.json").display().to_string();
+    let apps = vec![test_app(70, "Half-Life", 1745368572, 27681063), test_app(220, "Half-Life 2", 1745368567, 27681063)];
+    fs::write(&legacy_path, serde_json::to_string_pretty(&apps).unwrap()).unwrap();
+
+    let cache = steam_cache::migrate_legacy_cache(&legacy_path, &path).unwrap();
+    assert_eq!(2, cache.len(), "Expected 2 migrated apps not {}", cache.len());
+    assert!(!Path::new(&legacy_path).exists(), "Legacy cache should be removed");
+    assert_eq!(2, steam_cache::read_cache(&path).unwrap().len(), "Migrated cache should be saved");
+}
+
+#[test]
+fn migrate_legacy_sync() {
+    let dir = get_temp_dir("steam_sync_migration");
+    let legacy_path = Path::new(&dir).join("steam_game_titles_cache_sync.json").display().to_string();
+    let path = Path::new(&dir).join("steam_app_cache_sync.json").display().to_string();
+    let sync_str = "{\"last_sync\": \"2026-10-01T00:00:00Z\"}";
+    fs::write(&legacy_path, sync_str).unwrap();
+    // Empty sync files (e.g. created before the migration) are replaced
+    fs::write(&path, "").unwrap();
+
+    steam::migrate_legacy_sync(&legacy_path, &path);
+    assert!(!Path::new(&legacy_path).exists(), "Legacy sync file should be moved");
+    assert_eq!(sync_str, fs::read_to_string(&path).unwrap(), "Last sync time should be kept");
+    // Check that an existing sync time is not overwritten
+    fs::write(&legacy_path, "{}").unwrap();
+    steam::migrate_legacy_sync(&legacy_path, &path);
+    assert_eq!(sync_str, fs::read_to_string(&path).unwrap(), "Existing sync file should not be replaced");
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+use crate::storefront;
+use crate::settings::{self, EPIC_STORE_ID, GOG_STORE_ID, HUMBLE_BUNDLE_STORE_ID, MICROSOFT_STORE_ID, STEAM_STORE_ID};
+use crate::data::{GameThreshold, InsertOutcome, InsertReport, Money, SearchResult};
+
+fn empty_threshold() -> GameThreshold {
+    GameThreshold {
+        title: String::from("Random Game"),
+        alias: String::new(),
+        steam_id: 0,
+        gog_id: 0,
+        microsoft_store_id: String::new(),
+        desired_price: Money::from_major(10.0, "USD"),
+        ..Default::default()
+    }
+}
+
+fn search_results(titles: &[&str]) -> Vec<SearchResult> {
+    titles.iter().enumerate()
+        .map(|(idx, title)| SearchResult { id: idx.to_string(), title: title.to_string(), ..Default::default() })
+        .collect()
+}
+
+#[test]
+fn registry_matches_settings() {
+    let available_stores = settings::get_available_stores();
+    let storefronts = storefront::get_storefronts();
+    assert_eq!(available_stores.len(), storefronts.len(), "Every registered storefront should be an available store");
+    for store in storefronts {
+        let store_name = settings::get_proper_store_name(store.id()).unwrap_or_default();
+        assert_eq!(store.name(), store_name, "{} != {}", store.name(), store_name);
+    }
+    assert!(storefront::get_storefront("fake_store").is_none(), "\'fake_store\' should not be a registered storefront");
+}
+
+#[test]
+fn threshold_ids() {
+    let mut threshold = empty_threshold();
+    for id in [STEAM_STORE_ID, GOG_STORE_ID, MICROSOFT_STORE_ID, HUMBLE_BUNDLE_STORE_ID] {
+        let store = storefront::get_storefront(id).unwrap();
+        assert!(store.get_threshold_id(&threshold).is_none(), "{} ID should not be set", store.name());
+        assert!(store.set_threshold_id(&mut threshold, "123"), "{} ID should be updated", store.name());
+        assert_eq!(Some(String::from("123")), store.get_threshold_id(&threshold), "{} ID should be 123", store.name());
+    }
+    let steam = storefront::get_storefront(STEAM_STORE_ID).unwrap();
+    assert!(!steam.set_threshold_id(&mut threshold, "abc"), "\'abc\' should not be a valid Steam ID");
+    assert_eq!(123, threshold.steam_id, "Steam ID should still be 123 not {}", threshold.steam_id);
+    let epic = storefront::get_storefront(EPIC_STORE_ID).unwrap();
+    assert!(!epic.set_threshold_id(&mut threshold, "123"), "\'123\' should not be a valid Epic Games Store ID");
+    assert!(epic.set_threshold_id(&mut threshold, "abc:123"), "Epic Games Store ID should be updated");
+    assert_eq!(Some(String::from("abc:123")), epic.get_threshold_id(&threshold), "Epic Games Store ID should be abc:123");
+}
+
+#[test]
+fn auto_select() {
+    let results = search_results(&["Clair Obscur: Expedition 33 - Deluxe Edition", "Clair Obscur: Expedition 33™", "Expedition"]);
+    assert_eq!(Ok(1), storefront::auto_select("Clair Obscur Expedition 33", &results, 1.0), "Expected the exact normalized title");
+    assert_eq!(Ok(0), storefront::auto_select("Clair Obscur Expedition 33 Deluxe Edition", &results, 1.0));
+
+    match storefront::auto_select("Clair Obscur", &results, 1.0) {
+        Err(InsertOutcome::Ambiguous(candidates)) => assert_eq!(3, candidates.len(), "Expected 3 candidates not {:?}", candidates),
+        selection => panic!("Partial title should be ambiguous not {:?}", selection),
+    }
+    let prefix_results = search_results(&["Expedition", "Clair Obscur: Expedition 33"]);
+    assert_eq!(Ok(1), storefront::auto_select("Clair Obscur", &prefix_results, 0.9), "Prefix match should be picked with a lower minimum");
+
+    let duplicates = search_results(&["Half-Life 2", "HALF-LIFE II"]);
+    assert!(matches!(storefront::auto_select("Half-Life 2", &duplicates, 0.5), Err(InsertOutcome::Ambiguous(_))),
+            "Results with the same score should be ambiguous");
+    assert!(matches!(storefront::auto_select("Half-Life 2", &[], 0.5), Err(InsertOutcome::Skipped(_))));
+}
+
+#[test]
+fn insert_report() {
+    let mut report = InsertReport::default();
+    report.add("E33", STEAM_STORE_ID, InsertOutcome::Added(String::from("Clair Obscur: Expedition 33")));
+    report.add("E33", GOG_STORE_ID, InsertOutcome::Skipped(String::from("No search results")));
+    report.add("Clair", EPIC_STORE_ID, InsertOutcome::Ambiguous(vec![String::from("Clair Obscur: Expedition 33")]));
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!("Clair Obscur: Expedition 33", json["added"][0]["matched"]);
+    assert_eq!("No search results", json["skipped"][0]["reason"]);
+    assert_eq!(GOG_STORE_ID, json["skipped"][0]["store"]);
+    assert_eq!(1, json["ambiguous"][0]["candidates"].as_array().unwrap().len());
+    assert!(json["added"][0].get("reason").is_none(), "Empty fields should not be in the report");
+}
+
+#[test]
+fn money() {
+    // Store formats are read into minor units of the currency
+    assert_eq!(Some(Money::new(4999, "USD")), Money::parse("49.99", "USD"));
+    assert_eq!(Some(Money::new(129999, "USD")), Money::parse("$1,299.99", "USD"));
+    assert_eq!(Some(Money::new(4999, "EUR")), Money::parse("49,99 €", "EUR"));
+    assert_eq!(Some(Money::new(129900, "EUR")), Money::parse("1.299,00", "EUR"));
+    assert_eq!(Some(Money::new(1980, "JPY")), Money::parse("¥1,980", "JPY"));
+    assert_eq!(Some(Money::new(500, "USD")), Money::parse("5", "USD"));
+    assert_eq!(None, Money::parse("Free", "USD"));
+    // A single separator followed by more digits than the currency's decimals separates thousands
+    assert_eq!(Some(Money::new(129900, "USD")), Money::parse("1,299", "USD"));
+    assert_eq!(Some(Money::new(1299, "KWD")), Money::parse("1.299", "KWD"));
+    assert_eq!(Some(Money::new(123456700, "USD")), Money::parse("1,234,567", "USD"));
+    // Display prices use the separators of the locale
+    assert_eq!(Some(Money::new(129999, "EUR")), Money::parse_localized("1.299,99 €", "EUR", "de-DE"));
+    assert_eq!(Some(Money::new(129900, "EUR")), Money::parse_localized("1.299 €", "EUR", "de-DE"));
+    assert_eq!(Some(Money::new(129999, "GBP")), Money::parse_localized("£1,299.99", "GBP", "en-GB"));
+    assert_eq!(Some(Money::new(1980, "JPY")), Money::parse_localized("¥1,980", "JPY", "ja-JP"));
+    // Rounded to the nearest minor unit
+    assert_eq!(Money::new(2000, "USD"), Money::from_major(19.995, "USD"));
+    assert_eq!(Money::new(1980, "JPY"), Money::from_major(1979.5, "JPY"));
+    assert_eq!("19.99", Money::from_major(19.99, "USD").to_string());
+    assert_eq!("1980", Money::from_major(1980.0, "JPY").to_string());
+    assert_eq!(3, Money::get_decimals("kwd"));
+    assert_eq!("12.345", Money::from_major(12.345, "BHD").to_string());
+    // Only prices in the same currency are compared
+    assert!(Money::new(999, "USD") < Money::new(1999, "USD"));
+    assert_eq!(None, Money::new(999, "USD").partial_cmp(&Money::new(1999, "EUR")));
+}
+
+#[test]
+fn sale_discount() {
+    assert_eq!(70.0, storefront::get_sale_discount(&Money::new(3999, "USD"), &Money::new(1199, "USD")));
+    assert_eq!(100.0, storefront::get_sale_discount(&Money::new(2499, "USD"), &Money::new(0, "USD")));
+    assert_eq!(0.0, storefront::get_sale_discount(&Money::new(0, "USD"), &Money::new(0, "USD")), "Free games are not discounted");
+    assert_eq!(0.0, storefront::get_sale_discount(&Money::new(3999, "USD"), &Money::new(1199, "EUR")), "Currencies should match");
+}
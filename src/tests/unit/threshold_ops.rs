@@ -1,19 +1,22 @@
 #[cfg(test)]
+use chrono::{DateTime, Duration, Utc};
 use serde_json::json;
 use crate::thresholds;
 use crate::json as json_data;
 use crate::settings::{GOG_STORE_ID, MICROSOFT_STORE_ID, STEAM_STORE_ID};
-use crate::data::GameThreshold;
+use crate::data::{DealKind, GameThreshold, Money, PriceHistory, PriceRecord, SaleInfo, SteamItemType, SubscriptionInfo};
 use crate::steam_response::App;
 use crate::gog_response::{GameInfoBuilder as GOGGameBuilder, GameInfo as GOGGame, Price, FinalMoney, BaseMoney};
 use crate::microsoft_store_response::{ProductInfoBuilder as MSGameBuilder, ProductInfo as MSGame, PriceInfo};
+use crate::humble_bundle_response::{ProductInfo as HBGame, Money as HBMoney};
+use crate::epic_response::{Offer as EpicGame, OfferPrice, TotalPrice, CurrencyInfo};
 
 // Constants
 static THRESHOLD_FILENAME: &str = "thresholds.json";
 
 fn delete_thresholds() {
     let mut config_path = json_data::get_data_path();
-    config_path.push_str("/");
+    config_path.push('/');
     config_path.push_str(THRESHOLD_FILENAME);
     json_data::delete_file(config_path);
 }
@@ -25,13 +28,12 @@ fn add_simple_threshold(game_title: &str, game_alias: &str, price: f64) {
         steam_id: 123,
         gog_id: 456,
         microsoft_store_id: String::from("abc"),
-        currency: String::from("USD"),
-        desired_price: price
+        desired_price: Money::from_major(price, "USD"),
+        ..Default::default()
     }]);
     let filepath = thresholds::get_path();
-    match serde_json::to_string(&data){
-        Ok(data) => json_data::write_to_file(filepath, data),
-        Err(_) => ()
+    if let Ok(data) = serde_json::to_string(&data) {
+        json_data::write_to_file(filepath, data);
     }
 }
 
@@ -82,6 +84,43 @@ fn test_ms_game() -> MSGame {
     MSGameBuilder::new(id_str, title, price, icon_link, store_page_link)
 }
 
+fn test_humble_bundle_game() -> HBGame {
+    HBGame {
+        title: String::from("Fake Game 4"),
+        machine_name: String::from("fakegame4_storefront"),
+        human_url: String::from("fake-game-4"),
+        current_price: Some(HBMoney { amount: 5.99, currency: String::from("USD") }),
+        full_price: Some(HBMoney { amount: 11.99, currency: String::from("USD") }),
+        icon_link: String::new(),
+        delivery_methods: vec![],
+        platforms: vec![],
+        sale_end: None,
+    }
+}
+
+fn test_epic_game() -> EpicGame {
+    EpicGame {
+        title: String::from("Fake Game 5"),
+        id: String::from("fakeoffer5"),
+        namespace: String::from("fakenamespace5"),
+        product_slug: Some(String::from("fake-game-5")),
+        url_slug: None,
+        key_images: vec![],
+        price: Some(OfferPrice {
+            total_price: TotalPrice {
+                discount_price: 999,
+                original_price: 1999,
+                discount: 1000,
+                currency_code: String::from("USD"),
+                currency_info: CurrencyInfo { decimals: 2 },
+            },
+            line_offers: Vec::new(),
+        }),
+        offer_mappings: None,
+        promotions: None,
+    }
+}
+
 #[tokio::test]
 async fn add_steam_game() {
     delete_thresholds();
@@ -96,7 +135,25 @@ async fn add_steam_game() {
             assert_eq!(game_title.clone(), thresholds[0].title, "Expected {} not {}", game_title.clone(), thresholds[0].title);
             assert_eq!(game_id, thresholds[0].steam_id, "Expected {} not {}", game_id, thresholds[0].steam_id);
         },
-        Err(_) => assert!(false, "Could not find game: {} ({})", game_title.clone(), game_id),
+        Err(_) => panic!("Could not find game: {} ({})", game_title.clone(), game_id),
+    }
+}
+
+#[tokio::test]
+async fn add_steam_package() {
+    delete_thresholds();
+    let client = reqwest::Client::new();
+    let game_title = String::from("The Orange Box");
+    let package_id : usize = 469;
+    thresholds::add_steam_item(game_title.clone(), &game_title, SteamItemType::Package, package_id, 10.00, &client).await;
+
+    match thresholds::load_data() {
+        Ok(thresholds) => {
+            assert_eq!(game_title.clone(), thresholds[0].title, "Expected {} not {}", game_title.clone(), thresholds[0].title);
+            assert_eq!(package_id, thresholds[0].steam_id, "Expected {} not {}", package_id, thresholds[0].steam_id);
+            assert_eq!(SteamItemType::Package, thresholds[0].steam_type, "Expected a package not {:?}", thresholds[0].steam_type);
+        },
+        Err(_) => panic!("Could not find package: {} ({})", game_title.clone(), package_id),
     }
 }
 
@@ -106,14 +163,30 @@ fn add_gog_game() {
     let game = test_gog_game();
     let game_title = &game.title.clone();
     let game_id = game.id.parse::<usize>().unwrap();
-    thresholds::add_gog_game(game_title.clone(), &game, 10.00);
+    thresholds::add_gog_game(game_title.clone(), &game, 10.00, false);
 
     match thresholds::load_data() {
         Ok(thresholds) => {
             assert_eq!(game_title.clone(), thresholds[0].title, "Expected {} not {}", game_title.clone(), thresholds[0].title);
             assert_eq!(game_id, thresholds[0].gog_id, "Expected {} not {}", game_id, thresholds[0].gog_id);
+            assert!(!thresholds[0].gog_cheapest_edition, "{} should follow a single edition", game_title.clone());
         },
-        Err(_) => assert!(false, "Could not find game: {} ({})", game_title.clone(), game_id),
+        Err(_) => panic!("Could not find game: {} ({})", game_title.clone(), game_id),
+    }
+}
+
+#[test]
+fn add_gog_game_cheapest_edition() {
+    delete_thresholds();
+    let game = test_gog_game();
+    let game_title = &game.title.clone();
+    thresholds::add_gog_game(game_title.clone(), &game, 10.00, true);
+
+    match thresholds::load_data() {
+        Ok(thresholds) => {
+            assert!(thresholds[0].gog_cheapest_edition, "{} should follow the cheapest edition", game_title.clone());
+        },
+        Err(_) => panic!("Could not find game: {}", game_title.clone()),
     }
 }
 
@@ -123,14 +196,48 @@ fn add_microsoft_store_game() {
     let game = test_ms_game();
     let game_title = &game.title.clone();
     let game_id = &game.product_id.clone();
-    thresholds::add_microsoft_store_game(game_title.clone(), &game, 10.00);
+    thresholds::add_microsoft_store_game(game_title.clone(), &game.product_id, &game.title, None, 10.00);
 
     match thresholds::load_data() {
         Ok(thresholds) => {
             assert_eq!(game_title.clone(), thresholds[0].title, "Expected {} not {}", game_title.clone(), thresholds[0].title);
             assert_eq!(*game_id, thresholds[0].microsoft_store_id, "Expected {} not {}", game_id, thresholds[0].microsoft_store_id);
         },
-        Err(_) => assert!(false, "Could not find game: {} ({})", game_title.clone(), game_id),
+        Err(_) => panic!("Could not find game: {} ({})", game_title.clone(), game_id),
+    }
+}
+
+#[test]
+fn add_humble_bundle_game() {
+    delete_thresholds();
+    let game = test_humble_bundle_game();
+    let game_title = &game.title.clone();
+    let game_id = &game.machine_name.clone();
+    thresholds::add_humble_bundle_game(game_title.clone(), &game, 10.00);
+
+    match thresholds::load_data() {
+        Ok(thresholds) => {
+            assert_eq!(game_title.clone(), thresholds[0].title, "Expected {} not {}", game_title.clone(), thresholds[0].title);
+            assert_eq!(*game_id, thresholds[0].humble_bundle_id, "Expected {} not {}", game_id, thresholds[0].humble_bundle_id);
+        },
+        Err(_) => panic!("Could not find game: {} ({})", game_title.clone(), game_id),
+    }
+}
+
+#[test]
+fn add_epic_game() {
+    delete_thresholds();
+    let game = test_epic_game();
+    let game_title = &game.title.clone();
+    let game_id = format!("{}:{}", game.namespace, game.id);
+    thresholds::add_epic_game(game_title.clone(), &game, 10.00);
+
+    match thresholds::load_data() {
+        Ok(thresholds) => {
+            assert_eq!(game_title.clone(), thresholds[0].title, "Expected {} not {}", game_title.clone(), thresholds[0].title);
+            assert_eq!(game_id, thresholds[0].epic_id, "Expected {} not {}", game_id, thresholds[0].epic_id);
+        },
+        Err(_) => panic!("Could not find game: {} ({})", game_title.clone(), game_id),
     }
 }
 
@@ -149,7 +256,7 @@ fn update_alias() {
     match thresholds::load_data(){
         Ok(thresholds) =>
             assert_eq!(game_alias, thresholds[0].alias, "Alias should be \'{}\' not \'{}\'.", "", thresholds[0].alias),
-        Err(_) => assert!(false, "Could not load the thresholds when alias is expected to be empty.")
+        Err(_) => panic!("Could not load the thresholds when alias is expected to be empty.")
     }
 
     // Check that new alias is present in threshold
@@ -158,10 +265,20 @@ fn update_alias() {
     match thresholds::load_data(){
         Ok(thresholds) =>
             assert_eq!(new_alias, thresholds[0].alias, "Alias should be \'{}\' not \'{}\'.", new_alias, thresholds[0].alias),
-        Err(_) => assert!(false, "Could not load the thresholds when alias is expected to be {}.", new_alias)
+        Err(_) => panic!("Could not load the thresholds when alias is expected to be {}.", new_alias)
     }
 }
 
+#[test]
+fn get_threshold_normalized() {
+    delete_thresholds();
+    add_simple_threshold("Clair Obscur: Expedition 33™", "E33", 30.0);
+    for title in ["clair obscur expedition 33", "Clair Obscur: Expedition 33", "e33"] {
+        assert!(thresholds::get_threshold(title).is_some(), "\'{}\' should match the threshold", title);
+    }
+    assert!(thresholds::get_threshold("Clair Obscur").is_none(), "Partial titles should not match the threshold");
+}
+
 #[test]
 fn update_price() {
     delete_thresholds();
@@ -175,11 +292,29 @@ fn update_price() {
     thresholds::update_price(&game_title, new_price);
     match thresholds::load_data(){
         Ok(thresholds) =>
-            assert_eq!(new_price, thresholds[0].desired_price, "Price should be \'{}\' not \'{}\'.", new_price, thresholds[0].desired_price),
-        Err(_) => assert!(false, "Could not load thresholds when desired price was updated..")
+            assert_eq!(new_price, thresholds[0].desired_price.to_major(), "Price should be \'{}\' not \'{}\'.", new_price, thresholds[0].desired_price),
+        Err(_) => panic!("Could not load thresholds when desired price was updated..")
     }
 }
 
+#[test]
+fn load_saved_thresholds() {
+    // Thresholds saved before prices were Money have a number and a separate currency
+    let data = r#"[
+        {"title": "Random Game", "alias": "", "steam_id": 0, "gog_id": 0, "microsoft_store_id": "", "currency": "JPY", "desired_price": 1980.0},
+        {"title": "Other Game", "alias": "", "steam_id": 0, "gog_id": 0, "microsoft_store_id": "", "currency": "", "desired_price": 9.99}
+    ]"#;
+    let saved = serde_json::from_str::<Vec<GameThreshold>>(data).unwrap();
+    assert_eq!(Money::new(1980, "JPY"), saved[0].desired_price);
+    assert_eq!(Money::new(999, ""), saved[1].desired_price);
+    assert_eq!("9.99", thresholds::get_desired_price(&saved[1]).to_string(), "Price without a currency should be kept");
+    // Saved again with the currency in the price
+    let data = serde_json::to_string(&saved).unwrap();
+    assert!(data.contains(r#""desired_price":{"amount":1980,"currency":"JPY"}"#), "Unexpected threshold format: {}", data);
+    let loaded = serde_json::from_str::<Vec<GameThreshold>>(&data).unwrap();
+    assert_eq!(saved[0].desired_price, loaded[0].desired_price);
+}
+
 #[test]
 fn update_id(){
     delete_thresholds();
@@ -198,7 +333,7 @@ fn update_id(){
             assert_eq!(new_steam_id, thresholds[0].steam_id, "Steam ID should be \'{}\' not \'{}\'.", new_steam_id, thresholds[0].steam_id);
             assert_eq!(new_gog_id, thresholds[0].gog_id, "GOG ID should be \'{}\' not \'{}\'.", new_gog_id, thresholds[0].gog_id);
         },
-        Err(_) => assert!(false, "Could not load thresholds when store IDs (integer) where updated.")
+        Err(_) => panic!("Could not load thresholds when store IDs (integer) where updated.")
     }
 }
 
@@ -217,7 +352,7 @@ fn update_id_str(){
         Ok(thresholds) => {
             assert_eq!(new_ms_id, thresholds[0].microsoft_store_id, "Microsoft Store ID should be \'{}\' not \'{}\'.", new_ms_id, thresholds[0].microsoft_store_id);
         },
-        Err(_) => assert!(false, "Could not load thresholds when store IDs (string) where updated.")
+        Err(_) => panic!("Could not load thresholds when store IDs (string) where updated.")
     }
 }
 
@@ -235,13 +370,153 @@ fn remove_game(){
             assert_eq!(1, thresholds.len(), "Thresholds length before deletion should be 1");
             assert_eq!(game_title, thresholds[0].title, "Game title should {} not {}", game_title, thresholds[0].title);
         },
-        Err(e) => assert!(false, "Could not load thresholds before deletion.\n{}",e)
+        Err(e) => panic!("Could not load thresholds before deletion.\n{}",e)
     }
 
     // Delete test threshold
     thresholds::remove(&game_title);
     match thresholds::load_data(){
         Ok(thresholds) => assert_eq!(0, thresholds.len(), "Thresholds length after deletion should be 0"),
-        Err(_) => assert!(false, "Could not load thresholds after deletion.")
+        Err(_) => panic!("Could not load thresholds after deletion.")
+    }
+}
+
+// Sale of "Random Game" in USD
+fn sale(current: f64, original: f64) -> SaleInfo {
+    SaleInfo {
+        icon_link: String::new(),
+        title: String::from("Random Game"),
+        original_price: Money::from_major(original, "USD"),
+        current_price: Money::from_major(current, "USD"),
+        discount_percentage: ((original - current) / original * 100.0).round(),
+        store_page_link: String::new(),
+        deal: DealKind::Sale,
+        subscription: SubscriptionInfo::default(),
+        sale_ends_at: None,
+    }
+}
+
+// Steam price of "Random Game" in USD (full price of 40)
+fn record(price: f64, timestamp: DateTime<Utc>) -> PriceRecord {
+    PriceRecord {
+        store_id: String::from(STEAM_STORE_ID),
+        timestamp,
+        original_price: Money::from_major(40.0, "USD"),
+        current_price: Money::from_major(price, "USD"),
+        discount_percentage: 0.0,
+        game_pass: false,
+        sale_ends_at: None,
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn alert_rules() {
+    let mut threshold = GameThreshold {
+        title: String::from("Random Game"),
+        desired_price: Money::from_major(10.0, "USD"),
+        ..Default::default()
+    };
+    let sale = sale(20.0, 40.0);
+    let records = [30.0, 25.0, 40.0, 35.0].iter().map(|price| record(*price, Utc::now())).collect();
+    let game_history = PriceHistory { title: threshold.title.clone(), records };
+
+    // Fixed price only
+    assert!(!thresholds::is_alert(&threshold, &sale, Some(&game_history)), "20 should not meet a price threshold of 10");
+    // Any rule
+    threshold.rules.all_time_low = true;
+    assert!(thresholds::is_alert(&threshold, &sale, Some(&game_history)), "20 should be below the all-time low of 25");
+    assert!(!thresholds::is_alert(&threshold, &sale, None), "All-time low should not be met without price history");
+    // All rules
+    threshold.rules.match_all = true;
+    assert!(!thresholds::is_alert(&threshold, &sale, Some(&game_history)), "20 should not meet every rule");
+    threshold.rules.use_desired_price = false;
+    threshold.rules.min_discount = Some(50.0);
+    threshold.rules.bottom_percentile = Some(10.0);
+    assert!(thresholds::is_alert(&threshold, &sale, Some(&game_history)), "20 at 50% off should meet every rule");
+    threshold.rules.min_discount = Some(75.0);
+    assert!(!thresholds::is_alert(&threshold, &sale, Some(&game_history)), "50% off should not meet a minimum discount of 75%");
+}
+
+#[test]
+fn game_pass_rule() {
+    let mut threshold = GameThreshold {
+        title: String::from("Random Game"),
+        desired_price: Money::from_major(10.0, "USD"),
+        ..Default::default()
+    };
+    threshold.rules.use_desired_price = false;
+    threshold.rules.game_pass = true;
+    let mut sale = sale(40.0, 40.0);
+    let store_record = |store_id: &str, game_pass: bool| PriceRecord { store_id: store_id.to_string(), game_pass, ..record(40.0, Utc::now()) };
+    let mut game_history = PriceHistory { title: threshold.title.clone(), records: vec![store_record(MICROSOFT_STORE_ID, false)] };
+    assert!(!thresholds::is_alert(&threshold, &sale, Some(&game_history)), "Game is not included with Game Pass");
+    sale.subscription.included_with = vec![String::from("PC Game Pass")];
+    assert!(thresholds::is_alert(&threshold, &sale, Some(&game_history)), "Game was added to Game Pass");
+    assert!(thresholds::is_alert(&threshold, &sale, None), "Game without a recorded check was added to Game Pass");
+    // Only alert once (other stores never report Game Pass)
+    game_history.records.push(store_record(MICROSOFT_STORE_ID, true));
+    game_history.records.push(store_record(STEAM_STORE_ID, false));
+    assert!(!thresholds::is_alert(&threshold, &sale, Some(&game_history)), "Game was already included with Game Pass");
+    assert_eq!("added to Game Pass", thresholds::get_rules_str(&threshold));
+}
+
+#[test]
+fn ending_soon_rule() {
+    let mut threshold = GameThreshold {
+        title: String::from("Random Game"),
+        desired_price: Money::from_major(10.0, "USD"),
+        ..Default::default()
+    };
+    threshold.rules.use_desired_price = false;
+    threshold.rules.ending_soon = true;
+    let now = Utc::now();
+    let mut sale = SaleInfo { sale_ends_at: Some(now + Duration::days(5)), ..sale(20.0, 40.0) };
+    assert!(!thresholds::is_alert(&threshold, &sale, None), "Sale does not end within {}h", thresholds::ENDING_SOON_HOURS);
+    assert_eq!("ends in 5d 0h", thresholds::get_countdown_str(&sale, now));
+    sale.sale_ends_at = Some(now + Duration::hours(30));
+    assert!(thresholds::is_alert(&threshold, &sale, None), "Sale ends within {}h", thresholds::ENDING_SOON_HOURS);
+    assert_eq!("ends in 1d 6h", thresholds::get_countdown_str(&sale, now));
+    // Only alert once per sale
+    let game_history = PriceHistory { title: threshold.title.clone(), records: vec![PriceRecord {
+        discount_percentage: 50.0,
+        sale_ends_at: sale.sale_ends_at,
+        ..record(20.0, now - Duration::hours(12))
+    }] };
+    assert!(!thresholds::is_alert(&threshold, &sale, Some(&game_history)), "Sale ending soon was already reported");
+    // Sales without a discount or end date never qualify
+    sale.discount_percentage = 0.0;
+    assert!(!thresholds::is_alert(&threshold, &sale, None), "Full price should not be a sale");
+    sale.discount_percentage = 50.0;
+    sale.sale_ends_at = None;
+    assert!(!thresholds::is_alert(&threshold, &sale, None), "Sale without an end date should not be ending soon");
+    assert_eq!("", thresholds::get_countdown_str(&sale, now));
+}
+
+#[test]
+fn sort_by_sale_end() {
+    let now = Utc::now();
+    let ends_in = [None, Some(100), Some(40), None, Some(2)];
+    let mut sales : Vec<SaleInfo> = ends_in.iter().enumerate().map(|(idx, hours)| SaleInfo {
+        title: idx.to_string(),
+        sale_ends_at: hours.map(|hours| now + Duration::hours(hours)),
+        ..sale(20.0, 40.0)
+    }).collect();
+    thresholds::sort_by_sale_end(&mut sales, now, |sale| sale);
+    let order : Vec<&str> = sales.iter().map(|sale| sale.title.as_str()).collect();
+    // Sales ending within 48h first (earliest first), then the rest in their original order
+    assert_eq!(vec!["4", "2", "0", "1", "3"], order);
+}
+
+#[test]
+fn currency_mismatch() {
+    let threshold = GameThreshold {
+        title: String::from("Random Game"),
+        desired_price: Money::from_major(100.0, "EUR"),
+        ..Default::default()
+    };
+    let mut sale = sale(20.0, 40.0);
+    assert!(!thresholds::is_same_currency(&threshold, &sale), "USD price should not match EUR threshold");
+    assert!(!thresholds::is_alert(&threshold, &sale, None), "USD price should not be compared against EUR threshold");
+    sale.current_price = Money::from_major(20.0, "EUR");
+    assert!(thresholds::is_alert(&threshold, &sale, None), "EUR price should be compared against EUR threshold");
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+use crate::title_match::{self, TitleQuery};
+
+#[test]
+fn normalize_title() {
+    assert_eq!("clair obscur expedition 33", title_match::normalize_title("Clair Obscur: Expedition 33™"));
+    assert_eq!("assassins creed 2", title_match::normalize_title("Assassin’s Creed® II"));
+    assert_eq!("ratchet and clank rift apart", title_match::normalize_title("Ratchet & Clank: Rift Apart"));
+}
+
+#[test]
+fn get_base_title() {
+    assert_eq!("the witcher 3 wild hunt", title_match::get_base_title("The Witcher 3: Wild Hunt - Game of the Year Edition"));
+    assert_eq!("cyberpunk 2077", title_match::get_base_title("Cyberpunk 2077: Ultimate Edition"));
+    assert_eq!("deluxe edition", title_match::get_base_title("Deluxe Edition"), "A title should not be reduced to nothing");
+}
+
+#[test]
+fn titles_match() {
+    assert!(title_match::titles_match("Clair Obscur Expedition 33", "Clair Obscur: Expedition 33™"));
+    assert!(title_match::titles_match("final fantasy vii", "FINAL FANTASY 7"));
+    assert!(!title_match::titles_match("Half-Life", "Half-Life 2"));
+    assert!(!title_match::titles_match("Mega Man X", "Mega Man 10"), "Single letter roman numerals should not be converted");
+    assert!(!title_match::titles_match("The Witcher 3", "The Witcher 3 GOTY"), "Editions should be tracked separately");
+    assert!(!title_match::titles_match("", "™"), "Empty titles should never match");
+}
+
+#[test]
+fn match_score() {
+    let query = TitleQuery::new("Half-Life 2");
+    assert_eq!(1.0, query.score("Half-Life 2"));
+    assert_eq!(1.0, query.score("HALF-LIFE II"), "Normalized titles should be an exact match");
+    assert!(query.score("Half-Life 2: Episode One") > query.score("Half-Life"), "Prefix matches should rank above partial matches");
+    assert!(query.score("Half-Life 2 Deluxe Edition") > query.score("Half-Life 2: Episode One"), "Other editions should rank above prefix matches");
+    assert!(title_match::match_score("Clair Obscure Expedition 33", "Clair Obscur: Expedition 33") >= title_match::FUZZY_MIN_SCORE,
+            "Typos should still be a fuzzy match");
+    assert!(title_match::match_score("Portal", "Grand Theft Auto V") < title_match::FUZZY_MIN_SCORE);
+}
+
+#[test]
+fn rank_by_title() {
+    let mut titles = vec!["Half-Life 2: Deathmatch", "Half-Life", "Half-Life 2™", "Portal"];
+    title_match::rank_by_title("half-life 2", &mut titles, |title| title);
+    assert_eq!(vec!["Half-Life 2™", "Half-Life 2: Deathmatch", "Half-Life", "Portal"], titles);
+}
@@ -0,0 +1,127 @@
+use std::cmp::Reverse;
+
+// Lowest score for a title to be treated as a fuzzy match
+pub static FUZZY_MIN_SCORE : f64 = 0.6;
+// Scores below an exact match (so an exact title is always ranked first)
+static EDITION_SCORE : f64 = 0.95;
+static PREFIX_SCORE : f64 = 0.9;
+static FUZZY_MAX_SCORE : f64 = 0.85;
+
+static TRADEMARK_SYMBOLS : [char; 3] = ['™', '®', '©'];
+static APOSTROPHES : [char; 3] = ['\'', '’', '`'];
+// Single letters ("v" and "x") are not converted since they are often part of the name (e.g. "Mega Man X")
+static ROMAN_NUMERALS : [(&str, &str); 17] = [
+    ("ii", "2"), ("iii", "3"), ("iv", "4"), ("vi", "6"), ("vii", "7"), ("viii", "8"), ("ix", "9"),
+    ("xi", "11"), ("xii", "12"), ("xiii", "13"), ("xiv", "14"), ("xv", "15"), ("xvi", "16"),
+    ("xvii", "17"), ("xviii", "18"), ("xix", "19"), ("xx", "20"),
+];
+// Removed from the end of a title (more specific suffixes first so "digital deluxe edition" is removed before "deluxe edition")
+static EDITION_SUFFIXES : [&str; 17] = [
+    "game of the year edition", "game of the year", "goty edition", "digital deluxe edition", "collectors edition", "anniversary edition",
+    "definitive edition", "standard edition", "complete edition", "ultimate edition", "enhanced edition",
+    "special edition", "premium edition", "deluxe edition", "digital edition", "gold edition", "goty",
+];
+
+// Case-folds the title, strips trademark symbols and punctuation and converts roman numerals
+pub fn normalize_title(title: &str) -> String {
+    let mut cleaned = String::with_capacity(title.len());
+    for c in title.chars() {
+        if TRADEMARK_SYMBOLS.contains(&c) || APOSTROPHES.contains(&c) { continue; }
+        if c == '&' { cleaned.push_str(" and "); }
+        else if c.is_alphanumeric() { cleaned.extend(c.to_lowercase()); }
+        else { cleaned.push(' '); }
+    }
+    let words : Vec<&str> = cleaned.split_whitespace()
+        .map(|word| ROMAN_NUMERALS.iter().find(|(numeral, _)| *numeral == word).map_or(word, |(_, number)| *number))
+        .collect();
+    words.join(" ")
+}
+
+// Normalized title without the edition suffix (e.g. "The Witcher 3: Wild Hunt - Game of the Year Edition" -> "the witcher 3 wild hunt")
+pub fn get_base_title(title: &str) -> String {
+    let mut normalized = normalize_title(title);
+    // Keep the suffix if it is the whole title
+    if let Some(suffix) = EDITION_SUFFIXES.iter().find(|suffix| normalized.len() > suffix.len() && normalized.ends_with(&format!(" {}", suffix))) {
+        normalized.truncate(normalized.len() - suffix.len() - 1);
+    }
+    normalized
+}
+
+// True if both titles are the same after normalization (editions are still different titles so each can be tracked)
+pub fn titles_match(a: &str, b: &str) -> bool {
+    let a = normalize_title(a);
+    !a.is_empty() && a == normalize_title(b)
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev : Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+// Normalized search query that can be scored against many titles
+pub struct TitleQuery {
+    normalized: String,
+    base: String,
+    chars: Vec<char>,
+    tokens: Vec<String>,
+}
+
+impl TitleQuery {
+    pub fn new(query: &str) -> TitleQuery {
+        let normalized = normalize_title(query);
+        let base = get_base_title(query);
+        TitleQuery {
+            chars: base.chars().collect(),
+            tokens: base.split(' ').filter(|token| !token.is_empty()).map(String::from).collect(),
+            normalized,
+            base,
+        }
+    }
+
+    // 1.0 for the same normalized title, EDITION_SCORE for another edition of it, PREFIX_SCORE if the title starts with the query
+    // and otherwise the best of the shared words and the edit distance (scaled to FUZZY_MAX_SCORE)
+    pub fn score(&self, title: &str) -> f64 {
+        if self.normalized.is_empty() { return 0.0; }
+        if normalize_title(title) == self.normalized { return 1.0; }
+        let title = get_base_title(title);
+        if title.is_empty() { return 0.0; }
+        if title == self.base { return EDITION_SCORE; }
+        if title.starts_with(&format!("{} ", self.base)) { return PREFIX_SCORE; }
+
+        let title_tokens : Vec<&str> = title.split(' ').collect();
+        let shared = self.tokens.iter().filter(|token| title_tokens.contains(&token.as_str())).count() as f64;
+        let token_score = if shared == 0.0 { 0.0 } else {
+            let recall = shared / self.tokens.len() as f64;
+            let precision = shared / title_tokens.len() as f64;
+            2.0 * precision * recall / (precision + recall)
+        };
+
+        let title_chars : Vec<char> = title.chars().collect();
+        let max_len = self.chars.len().max(title_chars.len());
+        let min_len = self.chars.len().min(title_chars.len());
+        // The edit distance can't score higher than the length ratio so skip it for titles of very different length
+        let edit_score = if (min_len as f64 / max_len as f64) * FUZZY_MAX_SCORE < FUZZY_MIN_SCORE { 0.0 } else {
+            1.0 - levenshtein(&self.chars, &title_chars) as f64 / max_len as f64
+        };
+        token_score.max(edit_score) * FUZZY_MAX_SCORE
+    }
+}
+
+pub fn match_score(query: &str, title: &str) -> f64 {
+    TitleQuery::new(query).score(title)
+}
+
+// Sorts items by how well their title matches the query (stable, so the store order is kept for equal scores)
+pub fn rank_by_title<T, F: Fn(&T) -> &str>(query: &str, items: &mut [T], get_title: F) {
+    let query = TitleQuery::new(query);
+    items.sort_by_cached_key(|item| Reverse((query.score(get_title(item)) * 1000.0) as u32));
+}